@@ -1,5 +1,10 @@
+use std::cell::RefCell;
 use std::io::stdin;
 use std::mem::replace;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use crate::match_stats::StrategyStats;
+use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
 use crate::monte_carlo_game_v2::MonteCarloGameND;
 
 pub trait GameRepr {
@@ -12,6 +17,100 @@ impl <G: MonteCarloGameND> GameRepr for G {
 
 pub trait GamePlayer<G: GameRepr> {
     fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE;
+    // called before the first move of every game
+    fn new_game(&mut self) {}
+    // called once the game is over, game is the final state and last_mover made the final move, the one
+    // that won on WIN
+    fn finish(&mut self, _winner: Winner, _last_mover: TwoPlayer, _game: &G) {}
+    // nodes the search for the last move visited, None for players not searching or not counting
+    fn searched_nodes(&self) -> Option<u64> {
+        None
+    }
+    fn name(&self) -> String {
+        short_type_name::<Self>()
+    }
+}
+
+impl <G: GameRepr, P: GamePlayer<G> + ?Sized> GamePlayer<G> for Box<P> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        (**self).make_move(game, enemy_move)
+    }
+
+    fn new_game(&mut self) {
+        (**self).new_game()
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G) {
+        (**self).finish(winner, last_mover, game)
+    }
+
+    fn searched_nodes(&self) -> Option<u64> {
+        (**self).searched_nodes()
+    }
+
+    fn name(&self) -> String {
+        (**self).name()
+    }
+}
+
+// a player seated next to another one with state both can use, like an opening book or a transposition table
+pub trait SharedGamePlayer<G: GameRepr, S> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>, shared: &mut S) -> G::MOVE;
+    fn new_game(&mut self, _shared: &mut S) {}
+    fn finish(&mut self, _winner: Winner, _last_mover: TwoPlayer, _game: &G, _shared: &mut S) {}
+    fn name(&self) -> String {
+        short_type_name::<Self>()
+    }
+}
+
+// seats a player that does not use the shared state
+pub struct Unshared<P>(pub P);
+
+impl <G: GameRepr, S, P: GamePlayer<G>> SharedGamePlayer<G, S> for Unshared<P> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>, _shared: &mut S) -> G::MOVE {
+        self.0.make_move(game, enemy_move)
+    }
+
+    fn new_game(&mut self, _shared: &mut S) {
+        self.0.new_game()
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G, _shared: &mut S) {
+        self.0.finish(winner, last_mover, game)
+    }
+
+    fn name(&self) -> String {
+        self.0.name()
+    }
+}
+
+// both seats continue with the carry of the last move, whoever made it, so a search tree or cache
+// built for one side is reused by the other
+pub struct SharedStrategyPlayer<GS>(pub GS);
+
+impl <G: MonteCarloGameND, GS: GameStrategy<G>> SharedGamePlayer<G, Option<GS::Carry>> for SharedStrategyPlayer<GS> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>, shared: &mut Option<GS::Carry>) -> G::MOVE {
+        if let Some(m) = forced_move(game) {
+            if let Some(carry) = shared {
+                self.0.reset(carry);
+            }
+            return m;
+        }
+        let carry = enemy_move.zip(shared.take());
+        let (m, carry) = self.0.make_move(game, carry);
+        *shared = Some(carry);
+        m
+    }
+
+    fn new_game(&mut self, shared: &mut Option<GS::Carry>) {
+        if let Some(carry) = shared {
+            self.0.reset(carry);
+        }
+    }
+
+    fn name(&self) -> String {
+        self.0.name()
+    }
 }
 
 pub trait GameStrategy<G: GameRepr> {
@@ -22,6 +121,45 @@ pub trait GameStrategy<G: GameRepr> {
         GameStrategyPlayer::new(Self::new(config))
     }
     fn make_move(&self, game: &G, carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry);
+    // drops everything in the carry that is only valid for the game played so far
+    fn reset(&self, _carry: &mut Self::Carry) {}
+    // see GamePlayer::searched_nodes, the carry is the one returned with the last move
+    fn searched_nodes(&self, _carry: &Self::Carry) -> Option<u64> {
+        None
+    }
+    fn name(&self) -> String {
+        short_type_name::<Self>()
+    }
+}
+
+// the type name without module paths, also inside the generic arguments
+pub fn short_type_name<T: ?Sized>() -> String {
+    let mut parts = std::any::type_name::<T>().split("::").peekable();
+    let mut short = String::new();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_some() {
+            let keep = part.rfind(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+            short.push_str(&part[..keep]);
+        } else {
+            short.push_str(part);
+        }
+    }
+    short
+}
+
+// the seed for the counter-th strategy of an experiment, the same master seed always gives the same seeds.
+// Every word is a splitmix64 step, so neighbouring counters give unrelated seeds
+pub fn sub_seed(master: u64, counter: u64) -> [u8; 32] {
+    let mut state = master ^ counter.wrapping_mul(0xD1B5_4A32_D192_ED03);
+    let mut seed = [0; 32];
+    for word in seed.chunks_exact_mut(8) {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        word.copy_from_slice(&(z ^ (z >> 31)).to_le_bytes());
+    }
+    seed
 }
 
 pub struct GameStrategyPlayer<G: GameRepr, GS: GameStrategy<G>> {
@@ -38,35 +176,71 @@ impl <G: GameRepr, GS: GameStrategy<G>> GameStrategyPlayer<G, GS>{
     }
 }
 
-impl <G: GameRepr, GS: GameStrategy<G>> GamePlayer<G> for GameStrategyPlayer<G, GS> {
+// the only legal move, strategies are not asked for it. Their carry misses the move then, so it is reset
+// like for a new game, which every strategy has to handle anyway
+fn forced_move<G: MonteCarloGameND>(game: &G) -> Option<G::MOVE> {
+    let mut moves = game.moves().into_iter();
+    match (moves.next(), moves.next()) {
+        (Some(m), None) => Some(m),
+        _ => None,
+    }
+}
+
+impl <G: MonteCarloGameND, GS: GameStrategy<G>> GamePlayer<G> for GameStrategyPlayer<G, GS> {
     fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        if let Some(m) = forced_move(game) {
+            if let Some(carry) = &mut self.carry {
+                self.strategy.reset(carry);
+            }
+            return m;
+        }
         let carry = enemy_move.zip(replace(&mut self.carry, None));
         let (m, carry) = self.strategy.make_move(game, carry);
         self.carry = Some(carry);
         m
     }
+
+    fn new_game(&mut self) {
+        if let Some(carry) = &mut self.carry {
+            self.strategy.reset(carry);
+        }
+    }
+
+    fn searched_nodes(&self) -> Option<u64> {
+        self.carry.as_ref().and_then(|carry| self.strategy.searched_nodes(carry))
+    }
+
+    fn name(&self) -> String {
+        self.strategy.name()
+    }
 }
 
 pub struct PlayerInput;
-impl <G: MonteCarloGameND> GamePlayer<G> for PlayerInput where G::MOVE: TryFrom<u32> {
+// accepts the notation of the move type, like "col 4" or "b2", or the one based move number
+impl <G: MonteCarloGameND> GamePlayer<G> for PlayerInput where G::MOVE: TryFrom<u32> + for<'a> TryFrom<&'a str> {
     fn make_move(&mut self, game: &G, _enemy_move: Option<G::MOVE>) -> G::MOVE {
         loop {
             let mut s = String::with_capacity(10);
             println!("enter your turn");
             stdin().read_line(&mut s).expect("Failed to from stdin");
             s = s.trim().to_string();
-            let as_num = match s.parse() {
-                Err(_) | Ok(0) => {
-                println!("cannot parse move num!");
-                continue;
-                }
-                Ok(num) => num,
-            };
-            let m = match G::MOVE::try_from(as_num - 1) {
+            let m = match G::MOVE::try_from(s.as_str()) {
                 Ok(m) => m,
                 Err(_) => {
-                    println!("cannot parse move num!");
-                    continue;
+                    let as_num = match s.parse() {
+                        Err(_) | Ok(0) => {
+                            println!("cannot parse move num!");
+                            continue;
+                        }
+                        Ok(num) => num,
+                    };
+                    match G::MOVE::try_from(as_num - 1) {
+                        Ok(m) => m,
+                        Err(_) => {
+                            println!("cannot parse move num!");
+                            continue;
+                        }
+                    }
                 }
             };
             let is_valid_move = game.moves().into_iter().any(|it| it == m);
@@ -85,4 +259,348 @@ impl <G: MonteCarloGameND> GamePlayer<G> for RecordedMoves<G::MOVE> {
     fn make_move(&mut self, _game: &G, _enemy_move: Option<G::MOVE>) -> G::MOVE {
         self.0.remove(0)
     }
-}
\ No newline at end of file
+}
+
+// plays the script as long as it lasts and hands off to the wrapped player afterwards, the wrapped
+// player only sees the game from the first move after the script on
+pub struct ScriptedThenStrategy<G: GameRepr, P> {
+    script: RecordedMoves<G::MOVE>,
+    then: P,
+}
+
+impl <G: GameRepr, P> ScriptedThenStrategy<G, P> {
+    pub fn new(script: Vec<G::MOVE>, then: P) -> Self {
+        Self {
+            script: RecordedMoves(script),
+            then,
+        }
+    }
+}
+
+impl <G: MonteCarloGameND, P: GamePlayer<G>> GamePlayer<G> for ScriptedThenStrategy<G, P> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        if self.script.0.is_empty() {
+            return self.then.make_move(game, enemy_move);
+        }
+        let m = self.script.make_move(game, enemy_move);
+        assert!(game.moves().into_iter().any(|it| it == m), "scripted move {m:?} is not legal in\n{game:?}");
+        m
+    }
+
+    fn new_game(&mut self) {
+        self.then.new_game()
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G) {
+        self.then.finish(winner, last_mover, game)
+    }
+
+    fn name(&self) -> String {
+        format!("{} after script", self.then.name())
+    }
+}
+
+// switches between players by the plies made so far, each phase lasts until the next one starts. Like
+// with a script the player of a phase only sees the game from its first move on
+pub struct PhasedPlayer<G: GameRepr> {
+    // (first ply, player), ordered by the first ply
+    phases: Vec<(usize, Box<dyn GamePlayer<G>>)>,
+}
+
+impl <G: GameRepr> PhasedPlayer<G> {
+    pub fn new(opening: Box<dyn GamePlayer<G>>) -> Self {
+        Self {
+            phases: vec![(0, opening)],
+        }
+    }
+
+    pub fn with_phase(mut self, from_ply: usize, player: Box<dyn GamePlayer<G>>) -> Self {
+        assert!(self.phases.iter().all(|(ply, _)| *ply < from_ply), "phases have to start in order");
+        self.phases.push((from_ply, player));
+        self
+    }
+}
+
+impl <G: MonteCarloGame> GamePlayer<G> for PhasedPlayer<G> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        let plies = game.ply_count();
+        let (_, player) = self.phases.iter_mut().rev()
+            .find(|(from_ply, _)| *from_ply <= plies)
+            .expect("the first phase starts at ply 0");
+        player.make_move(game, enemy_move)
+    }
+
+    fn new_game(&mut self) {
+        self.phases.iter_mut().for_each(|(_, player)| player.new_game())
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G) {
+        self.phases.iter_mut().for_each(|(_, player)| player.finish(winner, last_mover, game))
+    }
+
+    fn name(&self) -> String {
+        let phases = self.phases.iter()
+            .map(|(from_ply, player)| format!("{} from {from_ply}", player.name()))
+            .collect::<Vec<_>>();
+        format!("Phased({})", phases.join(", "))
+    }
+}
+
+// takes at least the duration for every move, so quick answers do not feel rushed when playing against people
+pub struct PacedPlayer<G: GameRepr>(pub Box<dyn GamePlayer<G>>, pub Duration);
+
+impl <G: GameRepr> GamePlayer<G> for PacedPlayer<G> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        let start = Instant::now();
+        let m = self.0.make_move(game, enemy_move);
+        if let Some(remaining) = self.1.checked_sub(start.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+        m
+    }
+
+    fn new_game(&mut self) {
+        self.0.new_game()
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G) {
+        self.0.finish(winner, last_mover, game)
+    }
+
+    fn searched_nodes(&self) -> Option<u64> {
+        self.0.searched_nodes()
+    }
+
+    fn name(&self) -> String {
+        self.0.name()
+    }
+}
+
+// every player proposes a move and the one most of them proposed is played, ties go to the player listed
+// first. Players that were outvoted see the game go on with the chosen move
+pub struct EnsemblePlayer<G: GameRepr> {
+    players: Vec<Box<dyn GamePlayer<G>>>,
+}
+
+impl <G: GameRepr> EnsemblePlayer<G> {
+    pub fn new(players: Vec<Box<dyn GamePlayer<G>>>) -> Self {
+        assert!(!players.is_empty(), "an ensemble needs a player");
+        Self {
+            players,
+        }
+    }
+}
+
+impl <G: MonteCarloGameND> GamePlayer<G> for EnsemblePlayer<G> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        let proposals = self.players.iter_mut()
+            .map(|player| player.make_move(game, enemy_move.clone()))
+            .collect::<Vec<_>>();
+        let mut best = (0, &proposals[0]);
+        for m in &proposals {
+            let votes = proposals.iter().filter(|other| *other == m).count();
+            if votes > best.0 {
+                best = (votes, m);
+            }
+        }
+        best.1.clone()
+    }
+
+    fn new_game(&mut self) {
+        self.players.iter_mut().for_each(|player| player.new_game())
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G) {
+        self.players.iter_mut().for_each(|player| player.finish(winner, last_mover, game))
+    }
+
+    // all players searched for every move
+    fn searched_nodes(&self) -> Option<u64> {
+        self.players.iter().filter_map(|player| player.searched_nodes()).reduce(|a, b| a + b)
+    }
+
+    fn name(&self) -> String {
+        let players = self.players.iter().map(|player| player.name()).collect::<Vec<_>>();
+        format!("Ensemble({})", players.join(", "))
+    }
+}
+
+// adds the effort of the player to stats, which can be shared by the players of several games
+pub struct MeasuredPlayer<G, P> {
+    player: P,
+    stats: Rc<RefCell<StrategyStats>>,
+    plies: u32,
+    // the state after the last own move, to tell whether the opponent made the final move
+    after_own_move: Option<G>,
+}
+
+impl <G, P> MeasuredPlayer<G, P> {
+    pub fn new(player: P, stats: Rc<RefCell<StrategyStats>>) -> Self {
+        Self {
+            player,
+            stats,
+            plies: 0,
+            after_own_move: None,
+        }
+    }
+}
+
+impl <G: MonteCarloGame, P: GamePlayer<G>> GamePlayer<G> for MeasuredPlayer<G, P> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        let branching = game.moves().into_iter().count() as u64;
+        let start = Instant::now();
+        let m = self.player.make_move(game, enemy_move);
+        let think_time = start.elapsed();
+        let mut stats = self.stats.borrow_mut();
+        stats.moves += 1;
+        stats.branching += branching;
+        stats.think_time += think_time;
+        stats.nodes += self.player.searched_nodes().unwrap_or(0);
+        self.plies += 1 + enemy_move.is_some() as u32;
+        self.after_own_move = game.make_move(&m).ok().map(|(next, _)| next);
+        m
+    }
+
+    fn new_game(&mut self) {
+        self.plies = 0;
+        self.after_own_move = None;
+        self.player.new_game()
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G) {
+        let opponent_ended = self.after_own_move.as_ref() != Some(game);
+        let mut stats = self.stats.borrow_mut();
+        stats.games += 1;
+        stats.plies += self.plies + opponent_ended as u32;
+        drop(stats);
+        self.player.finish(winner, last_mover, game)
+    }
+
+    fn searched_nodes(&self) -> Option<u64> {
+        self.player.searched_nodes()
+    }
+
+    fn name(&self) -> String {
+        self.player.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+    use crate::ai_infra::{EnsemblePlayer, GamePlayer, GameStrategy, MeasuredPlayer, PacedPlayer, PhasedPlayer, RecordedMoves, ScriptedThenStrategy, SharedGamePlayer, SharedStrategyPlayer};
+    use crate::alpha_beta_ai::AlphaBetaStrategy;
+    use crate::dumm_ai::DummAi;
+    use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove, TicTacToeMove::*};
+    use crate::{run_game, run_shared_game, GameLimits};
+
+    #[test]
+    fn hands_off_after_script() {
+        let players: [Box<dyn GamePlayer<TicTacToe>>; 2] = [
+            Box::new(ScriptedThenStrategy::new(vec![I1, I2], DummAi::strategy_of(()))),
+            Box::new(RecordedMoves(vec![I4, I5, I9])),
+        ];
+        let (winner, _, last_mover) = run_game(players, false, GameLimits::default());
+        assert_eq!(Winner::WIN, winner);
+        assert_eq!(TwoPlayer::P1, last_mover);
+    }
+
+    #[test]
+    fn phases_switch_by_ply() {
+        let phased = PhasedPlayer::new(Box::new(RecordedMoves(vec![I1])))
+            .with_phase(2, Box::new(RecordedMoves(vec![I2, I3])));
+        assert_eq!("Phased(RecordedMoves<TicTacToeMove> from 0, RecordedMoves<TicTacToeMove> from 2)", phased.name());
+        let players: [Box<dyn GamePlayer<TicTacToe>>; 2] = [Box::new(phased), Box::new(RecordedMoves(vec![I4, I5]))];
+        let (winner, game, last_mover) = run_game(players, false, GameLimits::default());
+        assert_eq!((Winner::WIN, TwoPlayer::P1), (winner, last_mover));
+        assert_eq!(TicTacToe::new().apply_moves(&[I1, I4, I2, I5, I3]).unwrap().0, game);
+    }
+
+    #[test]
+    fn paced_player_waits() {
+        let mut paced = PacedPlayer(Box::new(RecordedMoves(vec![I5])), Duration::from_millis(30));
+        let start = Instant::now();
+        assert_eq!(I5, paced.make_move(&TicTacToe::new(), None));
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn ensemble_plays_the_majority() {
+        let mut ensemble = EnsemblePlayer::<TicTacToe>::new(vec![
+            Box::new(RecordedMoves(vec![I1, I2])),
+            Box::new(RecordedMoves(vec![I5, I3])),
+            Box::new(RecordedMoves(vec![I5, I4])),
+        ]);
+        assert_eq!("Ensemble(RecordedMoves<TicTacToeMove>, RecordedMoves<TicTacToeMove>, RecordedMoves<TicTacToeMove>)", ensemble.name());
+        assert_eq!(I5, ensemble.make_move(&TicTacToe::new(), None));
+        // no majority, the first player decides
+        assert_eq!(I2, ensemble.make_move(&TicTacToe::new(), None));
+    }
+
+    #[test]
+    fn measured_players_count_their_games() {
+        let stats = [(); 2].map(|_| Rc::new(RefCell::new(Default::default())));
+        for _ in 0..2 {
+            let players: [Box<dyn GamePlayer<TicTacToe>>; 2] = [
+                Box::new(MeasuredPlayer::new(RecordedMoves(vec![I1, I2, I3]), stats[0].clone())),
+                Box::new(MeasuredPlayer::new(RecordedMoves(vec![I4, I5]), stats[1].clone())),
+            ];
+            run_game(players, false, GameLimits::default());
+        }
+        let [p1, p2] = stats.map(|stats| *stats.borrow());
+        assert_eq!((2, 6, 10, 2 * (9 + 7 + 5)), (p1.games, p1.moves, p1.plies, p1.branching));
+        assert_eq!((2, 4, 10, 2 * (8 + 6)), (p2.games, p2.moves, p2.plies, p2.branching));
+        assert_eq!(5.0, p2.mean_game_length());
+        assert_eq!(0, p1.nodes);
+    }
+
+    #[test]
+    fn games_ended_by_a_limit_are_finished() {
+        let stats = [(); 2].map(|_| Rc::new(RefCell::new(Default::default())));
+        let players: [Box<dyn GamePlayer<TicTacToe>>; 2] = [
+            Box::new(MeasuredPlayer::new(RecordedMoves(vec![I1, I2, I3]), stats[0].clone())),
+            Box::new(MeasuredPlayer::new(RecordedMoves(vec![I4, I5]), stats[1].clone())),
+        ];
+        let (winner, _, _) = run_game(players, false, GameLimits { max_plies: Some(3) });
+        assert_eq!(Winner::TIE, winner);
+        let [p1, p2] = stats.map(|stats| *stats.borrow());
+        assert_eq!((1, 3), (p1.games, p1.plies));
+        assert_eq!((1, 3), (p2.games, p2.plies));
+    }
+
+    #[test]
+    fn seats_share_carry() {
+        let seat = || SharedStrategyPlayer(<AlphaBetaStrategy as GameStrategy<TicTacToe>>::new((9, 10_000)));
+        let players: [Box<dyn SharedGamePlayer<TicTacToe, _>>; 2] = [Box::new(seat()), Box::new(seat())];
+        let mut shared = None;
+        let (winner, _, _) = run_shared_game(players, &mut shared, false, GameLimits::default());
+        assert_eq!(Winner::TIE, winner);
+        assert!(shared.is_some());
+    }
+
+    struct NeverAsked;
+
+    impl GameStrategy<TicTacToe> for NeverAsked {
+        type Carry = ();
+        type Config = ();
+
+        fn new(_config: ()) -> Self {
+            NeverAsked
+        }
+
+        fn make_move(&self, _game: &TicTacToe, _carry: Option<(TicTacToeMove, ())>) -> (TicTacToeMove, ()) {
+            panic!("asked for a forced move");
+        }
+    }
+
+    #[test]
+    fn forced_move_skips_strategy() {
+        let (game, winner) = TicTacToe::new().apply_moves(&[I1, I2, I3, I5, I4, I6, I8, I7]).unwrap();
+        assert_eq!(None, winner);
+        assert_eq!(I9, NeverAsked::strategy_of(()).make_move(&game, Some(I7)));
+        assert_eq!(I9, SharedStrategyPlayer(NeverAsked).make_move(&game, Some(I7), &mut Some(())));
+    }
+}
@@ -0,0 +1,136 @@
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use crate::ai_infra::GameStrategy;
+use crate::eval_cache::EvalCache;
+use crate::monte_carlo_game::{MonteCarloGame, Winner};
+
+// a win is worth WIN_SCORE plus the remaining depth, so quicker wins are preferred;
+// positions at the depth limit are not evaluated and count as 0
+const WIN_SCORE: i32 = 1000;
+
+// depth limited negamax, positions are cached across moves
+pub struct AlphaBetaStrategy {
+    depth: u32,
+    cache_capacity: usize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct CachedEval {
+    depth: u32,
+    score: i32,
+    bound: Bound,
+}
+
+pub struct AlphaBetaCarry<G> {
+    cache: EvalCache<G, CachedEval>,
+    rng: SmallRng,
+}
+
+impl <G: MonteCarloGame> GameStrategy<G> for AlphaBetaStrategy {
+    type Carry = AlphaBetaCarry<G>;
+    // (search depth, cache capacity)
+    type Config = (u32, usize);
+
+    fn new((depth, cache_capacity): Self::Config) -> Self {
+        Self {
+            depth: depth.max(1),
+            cache_capacity,
+        }
+    }
+
+    fn make_move(&self, game: &G, carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
+        let mut carry = carry.map(|(_, carry)| carry).unwrap_or_else(|| AlphaBetaCarry {
+            cache: EvalCache::new(self.cache_capacity),
+            rng: SmallRng::from_entropy(),
+        });
+        let mut moves = game.moves().into_iter().collect::<Vec<_>>();
+        moves.shuffle(&mut carry.rng);
+        let (m, _) = best_move(game, moves, self.depth, &mut carry.cache).expect("no moves available");
+        (m, carry)
+    }
+}
+
+// the first of the best moves with its score, None if there are no moves
+pub(crate) fn best_move<G: MonteCarloGame>(game: &G, moves: impl IntoIterator<Item = G::MOVE>, depth: u32, cache: &mut EvalCache<G, CachedEval>) -> Option<(G::MOVE, i32)> {
+    let mut best = None;
+    let mut alpha = -i32::MAX;
+    for m in moves {
+        let score = score_move(game, &m, depth, alpha, i32::MAX, cache);
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some(m);
+        }
+    }
+    best.map(|m| (m, alpha))
+}
+
+// score of m for the player making it
+pub(crate) fn score_move<G: MonteCarloGame>(game: &G, m: &G::MOVE, depth: u32, alpha: i32, beta: i32, cache: &mut EvalCache<G, CachedEval>) -> i32 {
+    match game.make_move(m).expect("generated move is illegal") {
+        (_, Some(Winner::WIN)) => WIN_SCORE + depth as i32,
+        (_, Some(Winner::TIE)) => 0,
+        (next, None) => -negamax(&next, depth - 1, -beta, -alpha, cache),
+    }
+}
+
+fn negamax<G: MonteCarloGame>(game: &G, depth: u32, mut alpha: i32, mut beta: i32, cache: &mut EvalCache<G, CachedEval>) -> i32 {
+    if depth == 0 {
+        return 0;
+    }
+    let original_alpha = alpha;
+    if let Some(cached) = cache.get(game).filter(|cached| cached.depth >= depth) {
+        match cached.bound {
+            Bound::Exact => return cached.score,
+            Bound::Lower => alpha = alpha.max(cached.score),
+            Bound::Upper => beta = beta.min(cached.score),
+        }
+        if alpha >= beta {
+            return cached.score;
+        }
+    }
+    let mut best = -i32::MAX;
+    for m in game.ordered_moves() {
+        let score = score_move(game, &m, depth, alpha, beta, cache);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    let bound = if best <= original_alpha {
+        Bound::Upper
+    } else if best >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    cache.insert(game.clone(), CachedEval { depth, score: best, bound });
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::GameStrategy;
+    use crate::alpha_beta_ai::AlphaBetaStrategy;
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove};
+
+    #[test]
+    fn blocks_and_wins() {
+        use TicTacToeMove::*;
+        let strategy = <AlphaBetaStrategy as GameStrategy<TicTacToe>>::new((9, 10_000));
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2]).unwrap();
+        assert_eq!(I3, strategy.make_move(&game, None).0);
+        // O threatens I6, but X wins first
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5]).unwrap();
+        assert_eq!(I3, strategy.make_move(&game, None).0);
+    }
+}
@@ -0,0 +1,156 @@
+use std::io::Write;
+use serde::{Deserialize, Serialize};
+use crate::ai_infra::GamePlayer;
+use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PositionRecord<G, M> {
+    pub position: G,
+    pub chosen_move: M,
+    pub player: TwoPlayer,
+    // None if the game ended in a tie
+    pub eventual_winner: Option<TwoPlayer>,
+}
+
+// records every position the wrapped player decides on; the records of a game are only
+// available once the runner called finish
+pub struct DataCollectingPlayer<G: MonteCarloGame, P> {
+    player: P,
+    pending: Vec<(G, G::MOVE)>,
+    records: Vec<PositionRecord<G, G::MOVE>>,
+}
+
+impl <G: MonteCarloGame, P: GamePlayer<G>> DataCollectingPlayer<G, P> {
+    pub fn new(player: P) -> Self {
+        Self {
+            player,
+            pending: Vec::new(),
+            records: Vec::new(),
+        }
+    }
+
+    pub fn records(&self) -> &[PositionRecord<G, G::MOVE>] {
+        &self.records
+    }
+
+    pub fn take_records(&mut self) -> Vec<PositionRecord<G, G::MOVE>> {
+        std::mem::take(&mut self.records)
+    }
+
+    pub fn write_json(&self, writer: impl Write) -> serde_json::Result<()> where G: Serialize, G::MOVE: Serialize {
+        serde_json::to_writer(writer, &self.records)
+    }
+}
+
+impl <G: MonteCarloGame, P: GamePlayer<G>> GamePlayer<G> for DataCollectingPlayer<G, P> {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        let m = self.player.make_move(game, enemy_move);
+        self.pending.push((game.clone(), m));
+        m
+    }
+
+    fn new_game(&mut self) {
+        // positions of a game that was never finished have no outcome
+        self.pending.clear();
+        self.player.new_game();
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G) {
+        let eventual_winner = match winner {
+            Winner::WIN => Some(last_mover),
+            Winner::TIE => None,
+        };
+        self.records.extend(self.pending.drain(..).map(|(position, chosen_move)| PositionRecord {
+            player: position.player(),
+            position,
+            chosen_move,
+            eventual_winner,
+        }));
+        self.player.finish(winner, last_mover, game);
+    }
+
+    fn name(&self) -> String {
+        self.player.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::{GamePlayer, RecordedMoves};
+    use crate::data_collection::{DataCollectingPlayer, PositionRecord};
+    use crate::monte_carlo_game::conformance::HighCard;
+    use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+    use crate::simultaneous_game::Simultaneous;
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove, TicTacToeMove::*};
+
+    type Collector<G> = DataCollectingPlayer<G, RecordedMoves<<G as MonteCarloGame>::MOVE>>;
+
+    fn collectors<G: MonteCarloGame>(p1: Vec<G::MOVE>, p2: Vec<G::MOVE>) -> [Collector<G>; 2] {
+        [DataCollectingPlayer::new(RecordedMoves(p1)), DataCollectingPlayer::new(RecordedMoves(p2))]
+    }
+
+    // plays a whole game like the runners do
+    fn play<G: MonteCarloGame>(players: &mut [Collector<G>; 2]) {
+        players.iter_mut().for_each(|player| player.new_game());
+        let mut game = G::new();
+        loop {
+            let mover = game.player();
+            let player = match mover {
+                TwoPlayer::P1 => &mut players[0],
+                TwoPlayer::P2 => &mut players[1],
+            };
+            let m = player.make_move(&game, None);
+            let (next, winner) = game.make_move(&m).unwrap();
+            game = next;
+            if let Some(winner) = winner {
+                players.iter_mut().for_each(|player| player.finish(winner, mover, &game));
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn records_are_labelled_on_finish() {
+        let mut players = collectors::<TicTacToe>(vec![I1, I2, I3], vec![I4, I5]);
+        play(&mut players);
+        let [p1, p2] = &players;
+        assert_eq!(vec![I1, I2, I3], p1.records().iter().map(|record| record.chosen_move).collect::<Vec<_>>());
+        assert_eq!(2, p2.records().len());
+        assert!(p1.records().iter().chain(p2.records()).all(|record| record.eventual_winner == Some(TwoPlayer::P1)));
+        assert!(p2.records().iter().all(|record| record.player == TwoPlayer::P2));
+    }
+
+    #[test]
+    fn winner_is_the_last_mover() {
+        // p2 wins every round, the final state is the turn of p1 again
+        let mut players = collectors::<Simultaneous<HighCard>>(vec![1; 3], vec![3; 3]);
+        play(&mut players);
+        assert!(players.iter().flat_map(|player| player.records()).all(|record| record.eventual_winner == Some(TwoPlayer::P2)));
+    }
+
+    #[test]
+    fn unfinished_games_are_dropped() {
+        let mut player = DataCollectingPlayer::new(RecordedMoves(vec![I1, I5]));
+        player.make_move(&TicTacToe::new(), None);
+        // nothing is recorded before the game is finished
+        assert!(player.records().is_empty());
+        player.new_game();
+        let (game, _) = TicTacToe::new().make_move(&I1).unwrap();
+        player.make_move(&game, Some(I1));
+        player.finish(Winner::TIE, TwoPlayer::P2, &game);
+        assert_eq!(1, player.records().len());
+        assert_eq!(None, player.records()[0].eventual_winner);
+        assert_eq!(I5, player.records()[0].chosen_move);
+    }
+
+    #[test]
+    fn records_survive_json() {
+        let mut players = collectors::<TicTacToe>(vec![I1, I2, I3], vec![I4, I5]);
+        play(&mut players);
+        let mut json = Vec::new();
+        players[0].write_json(&mut json).unwrap();
+        let read = serde_json::from_slice::<Vec<PositionRecord<TicTacToe, TicTacToeMove>>>(&json).unwrap();
+        assert_eq!(players[0].take_records(), read);
+        assert!(players[0].records().is_empty());
+    }
+}
@@ -16,21 +16,20 @@ impl <G: MonteCarloGame> GameStrategy<G> for DummAi {
     }
 
     fn make_move(&self, game: &G, carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
-        let mut rng = carry.map(|(_, rng)| rng).unwrap_or(SmallRng::from_entropy());
-        let moves = game.moves().into_iter().map(|m| (game.make_move(&m).unwrap(), m)).collect::<Vec<_>>();
-        for ((_, res), m) in &moves{
+        let mut rng = carry.map(|(_, rng)| rng).unwrap_or_else(SmallRng::from_entropy);
+        let moves = game.successors().collect::<Vec<_>>();
+        for (m, _, res) in &moves {
             if res.is_some() {
-                return (m.clone(), rng)
+                return (*m, rng)
             }
         }
         let viable_moves = moves.iter()
-            .map(|((game,_), m)| (game.moves().into_iter().map(|m| game.make_move(&m).unwrap()), m))
-            .filter_map(|(mut result, m)| result.all(|(_, res)| res.is_none()).then_some(m))
+            .filter_map(|(m, game, _)| game.successors().all(|(_, _, res)| res.is_none()).then_some(m))
             .collect::<Vec<_>>();
         let mov = if !viable_moves.is_empty() {
-            viable_moves.choose(&mut rng).map(|m| (*m).clone()).unwrap()
+            viable_moves.choose(&mut rng).map(|m| **m).unwrap()
         } else {
-            moves.choose(&mut rng).map(|(_, m)| m).cloned().unwrap()
+            moves.choose(&mut rng).map(|(m, _, _)| *m).unwrap()
         };
         (mov, rng)
     }
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::hash::Hash;
+use rustc_hash::FxHashMap;
+
+// capacity bounded map, evicting the least recently used entry once full
+pub struct EvalCache<K, V> {
+    capacity: usize,
+    entries: FxHashMap<K, (V, u64)>,
+    by_use: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl <K: Hash + Eq + Clone, V> EvalCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: FxHashMap::default(),
+            by_use: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let (value, last_used) = self.entries.get_mut(key)?;
+        let key = self.by_use.remove(last_used).expect("cache order out of sync");
+        self.tick += 1;
+        *last_used = self.tick;
+        self.by_use.insert(self.tick, key);
+        Some(value)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.by_use.remove(&last_used);
+        } else if self.entries.len() > self.capacity {
+            let (_, oldest) = self.by_use.pop_first().expect("cache order out of sync");
+            self.entries.remove(&oldest);
+        }
+        self.by_use.insert(self.tick, key);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EvalCache;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = EvalCache::new(2);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        assert_eq!(Some(&"a"), cache.get(&1));
+        cache.insert(3, "c");
+        assert_eq!(2, cache.len());
+        assert_eq!(None, cache.get(&2));
+        assert_eq!(Some(&"a"), cache.get(&1));
+        assert_eq!(Some(&"c"), cache.get(&3));
+        cache.insert(3, "d");
+        assert_eq!(Some(&"d"), cache.get(&3));
+        assert_eq!(2, cache.len());
+    }
+}
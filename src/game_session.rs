@@ -0,0 +1,74 @@
+use crate::monte_carlo_game::{MonteCarloGame, MoveError, Winner};
+
+// drives a single game move by move without any io or threads, so it can be used from wasm glue;
+// strategies are asked for moves by the caller, the session only validates and applies them
+pub struct GameSession<G: MonteCarloGame> {
+    current: G,
+    winner: Option<Winner>,
+}
+
+impl <G: MonteCarloGame> GameSession<G> {
+    pub fn new() -> Self {
+        Self::from_state(G::new())
+    }
+
+    pub fn from_state(state: G) -> Self {
+        Self {
+            current: state,
+            winner: None,
+        }
+    }
+
+    pub fn current(&self) -> &G {
+        &self.current
+    }
+
+    pub fn winner(&self) -> Option<Winner> {
+        self.winner
+    }
+
+    pub fn legal_moves(&self) -> Vec<G::MOVE> {
+        if self.winner.is_some() {
+            return Vec::new();
+        }
+        self.current.moves().into_iter().collect()
+    }
+
+    // fails if the game already ended or the move is illegal, the session is unchanged then
+    pub fn play(&mut self, m: G::MOVE) -> Result<Option<Winner>, MoveError> {
+        if self.winner.is_some() {
+            return Err(MoveError::GameOver);
+        }
+        let (next, winner) = self.current.make_move(&m)?;
+        self.current = next;
+        self.winner = winner;
+        Ok(winner)
+    }
+}
+
+impl <G: MonteCarloGame> Default for GameSession<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_session::GameSession;
+    use crate::monte_carlo_game::{MonteCarloGame, MoveError, TwoPlayer, Winner};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove};
+
+    #[test]
+    fn plays_until_win() {
+        let mut session = GameSession::<TicTacToe>::new();
+        assert_eq!(9, session.legal_moves().len());
+        for m in [TicTacToeMove::I1, TicTacToeMove::I4, TicTacToeMove::I2, TicTacToeMove::I5] {
+            assert_eq!(Ok(None), session.play(m));
+        }
+        assert_eq!(Err(MoveError::Illegal), session.play(TicTacToeMove::I1));
+        assert_eq!(Ok(Some(Winner::WIN)), session.play(TicTacToeMove::I3));
+        assert_eq!(TwoPlayer::P1, session.current().player());
+        assert!(session.legal_moves().is_empty());
+        assert_eq!(Err(MoveError::GameOver), session.play(TicTacToeMove::I6));
+    }
+}
@@ -1,24 +1,34 @@
 use std::fs::{DirEntry, File, FileType, ReadDir};
-use std::io::{stdin, stdout};
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::io::{stdin, stdout, BufReader};
+use std::path::Path;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::time::{Duration, Instant, SystemTime};
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
-use rand_distr::{LogNormal, SkewNormal};
-use rayon::iter::IntoParallelRefIterator;
-use crate::ai_infra::{GamePlayer, GameStrategy};
+use rand_distr::{Distribution, Normal};
+use crate::ai_infra::{sub_seed, GamePlayer, GameStrategy, MeasuredPlayer};
 use crate::monte_carlo_win_reducer::{WinFactorReduce, WinFactorReduceFactory};
-use crate::multi_score_reducer::{CheckWinMonteCarloGame, ExecutionLimiter, ScoreReducer, TwoScoreReducer, TwoScoreReducerExecutionLimiterFactory, TwoScoreReducerFactory, WinRewardInit};
-use crate::old_monte_carlo::monte_carlo_main8::MonteCarloStrategyV8;
+use crate::multi_score_reducer::{CheckWinMonteCarloGame, ExecutionLimiter, ScoreReducer, TwoScoreReducer, TwoScoreReducerFactory, WinRewardInit};
+use crate::old_monte_carlo::monte_carlo_main8::{MctsV8Reducer, MonteCarloStrategyV8};
 use crate::old_monte_carlo::monte_carlo_main::MonteLimit;
 use rayon::prelude::*;
+use crate::match_stats::StrategyStats;
 use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
 use serde::{Serialize, Deserialize};
 use crate::monte_carlo_game_v2::MonteCarloGameND;
 use crate::old_monte_carlo::monte_carlo_main7::MonteCarloStrategyV7;
 
+const RANDOM_VALUES_VERSION: u32 = 1;
+
+// new fields need #[serde(default)] (and a migration in RandomValues::migrate if the default is not sensible),
+// so that older checkpoints keep loading
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct RandomValues {
+pub struct RandomValues {
+    #[serde(default)]
+    version: u32,
     c: f64,
     el_threshold: f64,
     degregation_1: f64,
@@ -27,25 +37,102 @@ struct RandomValues {
     win_reward_2: (f64, f64),
 }
 
+#[derive(Debug)]
+pub enum CheckpointError {
+    NoCheckpoint,
+    Io(std::io::Error),
+    Corrupt(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl std::fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::NoCheckpoint => write!(f, "no checkpoint found"),
+            CheckpointError::Io(e) => write!(f, "failed to read checkpoint: {e}"),
+            CheckpointError::Corrupt(e) => write!(f, "failed to parse checkpoint: {e}"),
+            CheckpointError::UnsupportedVersion(v) => write!(f, "checkpoint has unsupported version {v}"),
+        }
+    }
+}
+
+impl RandomValues {
+    fn migrate(mut self) -> Result<Self, CheckpointError> {
+        match self.version {
+            // version 0 checkpoints predate the version field, the values are unchanged
+            0 => self.version = RANDOM_VALUES_VERSION,
+            RANDOM_VALUES_VERSION => {}
+            v => return Err(CheckpointError::UnsupportedVersion(v)),
+        }
+        Ok(self)
+    }
+}
+
 pub fn load_best_from_pop<G: MonteCarloGameND + CheckWinMonteCarloGame + 'static>(monte_limit: MonteLimit) -> Option<impl GamePlayer<G>> {
-    let first = read_last_checkpoint()?.drain(..).next()?;
-    let config = config_from_rv(monte_limit, &first);
+    let first = read_last_checkpoint().ok()?.drain(..).next()?;
+    let config = config_from_rv(monte_limit, &first, None);
 
     Some(MonteCarloStrategyV8::strategy_of(config))
 }
 
 
-pub fn opt<G: MonteCarloGame+ CheckWinMonteCarloGame + 'static>() {
+const C_RANGE: (f64, f64) = (0.0, 10.0);
+const EL_THRESHOLD_RANGE: (f64, f64) = (0.0, 10.0);
+const DEGREGATION_RANGE: (f64, f64) = (0.0, 1.0);
+const WIN_REWARD_RANGE: (f64, f64) = (-10.0, 10.0);
+
+#[derive(Copy, Clone, Debug)]
+pub struct OptConfig {
+    // chance of each value to be mutated after crossover
+    pub mutation_rate: f64,
+    // standard deviation of the mutation, relative to the range the value is initially sampled from
+    pub mutation_sigma: f64,
+    pub early_stopping: Option<EarlyStopping>,
+    // level of the per generation summary, which is one line of key=value pairs
+    pub log_level: log::Level,
+    // the game counter printed to stdout while a generation plays
+    pub show_progress: bool,
+    // fixes the population and the seeds of all games, None draws them from entropy. The games only play out the
+    // same with a limit of playoffs, the optimizer limits them by time
+    pub seed: Option<u64>,
+}
+
+// stops the optimization once the win total of the best candidate has not improved by more than epsilon
+// for patience generations
+#[derive(Copy, Clone, Debug)]
+pub struct EarlyStopping {
+    pub patience: u32,
+    pub epsilon: f64,
+}
+
+impl Default for OptConfig {
+    fn default() -> Self {
+        Self {
+            mutation_rate: 0.1,
+            mutation_sigma: 0.05,
+            early_stopping: None,
+            log_level: log::Level::Info,
+            show_progress: true,
+            seed: None,
+        }
+    }
+}
+
+pub fn opt<G: MonteCarloGame+ CheckWinMonteCarloGame + 'static>(config: OptConfig) -> RandomValues {
     let monte_limit = MonteLimit::duration(100);
-    let mut rng = SmallRng::from_entropy();
+    let mut seeds = config.seed.map(SmallRng::seed_from_u64);
+    let mut next_seed = move || seeds.as_mut().map(|seeds| seeds.gen::<u64>());
+    let mut rng = next_seed().map_or_else(SmallRng::from_entropy, SmallRng::seed_from_u64);
     let mut random_variants = move || {
-        let c = rng.gen_range((0.0)..(10.0));
-        let degregation_1 = rng.gen_range(0.0..(1.0));
-        let degregation_2 = rng.gen_range(0.0..(1.0));
-        let win_reward_1 = (rng.gen_range((-10.0)..(10.0)), rng.gen_range((-10.0)..(10.0)));
-        let win_reward_2 = (rng.gen_range((-10.0)..(10.0)), rng.gen_range((-10.0)..(10.0)));
-        let el_threshold = rng.gen_range((0.0)..(10.0));
+        let mut sample = |(low, high): (f64, f64)| rng.gen_range(low..high);
+        let c = sample(C_RANGE);
+        let degregation_1 = sample(DEGREGATION_RANGE);
+        let degregation_2 = sample(DEGREGATION_RANGE);
+        let win_reward_1 = (sample(WIN_REWARD_RANGE), sample(WIN_REWARD_RANGE));
+        let win_reward_2 = (sample(WIN_REWARD_RANGE), sample(WIN_REWARD_RANGE));
+        let el_threshold = sample(EL_THRESHOLD_RANGE);
         RandomValues {
+            version: RANDOM_VALUES_VERSION,
             c,
             el_threshold,
             degregation_1,
@@ -56,60 +143,94 @@ pub fn opt<G: MonteCarloGame+ CheckWinMonteCarloGame + 'static>() {
     };
 
     let mut candidates = match read_last_checkpoint() {
-        Some(c) => c,
-        None => {
+        Ok(c) => c,
+        Err(CheckpointError::NoCheckpoint) => {
             log::info!("no existing population found: starting new one");
             Vec::new()
         }
+        Err(e) => {
+            log::warn!("{e}: starting new population");
+            Vec::new()
+        }
     };
     candidates.extend(std::iter::repeat_with(|| random_variants()).take(100usize.saturating_sub(candidates.len())));
-    let mut candidates = candidates.into_iter().map(|rv| (rv, AtomicU32::new(0))).collect::<Vec<_>>();
+    // score and searched nodes of every candidate
+    let mut candidates = candidates.into_iter().map(|rv| (rv, AtomicU32::new(0), AtomicU64::new(0))).collect::<Vec<_>>();
 
     let mut last_saved = Instant::now();
+    let mut best_score = None::<u32>;
+    let mut stale_generations = 0u32;
+
+    let mut generation = 0u32;
 
     //916.1772972 s
     loop {
         let playoffs_start = Instant::now();
-        do_random_playoffs::<G>(monte_limit, 1, &candidates);
-        println!("commencing_mutation after {} seconds", playoffs_start.elapsed().as_secs_f64());
+        do_random_playoffs::<G>(monte_limit, 1, &candidates, config.show_progress, next_seed());
+        let elapsed = playoffs_start.elapsed();
 
-        candidates.sort_unstable_by_key(|(_, k)| k.load(Ordering::Relaxed));
+        // of two equally strong candidates the one that needed fewer nodes is better
+        candidates.sort_unstable_by_key(|(_, k, nodes)| (k.load(Ordering::Relaxed), Reverse(nodes.load(Ordering::Relaxed))));
 
+        let mut checkpoint = None;
         if last_saved.elapsed() > Duration::from_secs(60 * 20) {
             last_saved= Instant::now();
-            let save = candidates.iter().rev().take(20).map(|(rv, _)| rv.clone()).collect::<Vec<_>>();
-            let file = File::create(format!("checkpoint{}", SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs()));
+            let save = candidates.iter().rev().take(20).map(|(rv, _, _)| rv.clone()).collect::<Vec<_>>();
+            let path = format!("checkpoint{}", SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs());
 
-            match file {
+            match File::create(&path) {
                 Ok(file) => {
-                    let e = serde_json::to_writer(file, &save);
-                    if let Err(e) = e {
-                        eprintln!("failed to write json: {e}")
+                    match serde_json::to_writer(file, &save) {
+                        Ok(()) => checkpoint = Some(path),
+                        Err(e) => log::warn!("failed to write checkpoint {path}: {e}"),
                     }
                 }
-                Err(e) => eprintln!("failed to write to file: {e}"),
+                Err(e) => log::warn!("failed to create checkpoint {path}: {e}"),
+            }
+        }
+
+        let (best, score, best_nodes) = candidates.last().map(|(rv, k, nodes)| (rv, k.load(Ordering::Relaxed), nodes.load(Ordering::Relaxed))).expect("population is empty");
+        let mean = candidates.iter().map(|(_, k, _)| f64::from(k.load(Ordering::Relaxed))).sum::<f64>() / candidates.len() as f64;
+        log::log!(
+            config.log_level,
+            "generation={generation} best_fitness={score} best_nodes={best_nodes} mean_fitness={mean:.2} elapsed_s={:.3} checkpoint={}",
+            elapsed.as_secs_f64(), checkpoint.as_deref().unwrap_or("-"),
+        );
+
+        if let Some(EarlyStopping { patience, epsilon }) = config.early_stopping {
+            match best_score {
+                Some(prev) if f64::from(score) - f64::from(prev) <= epsilon => stale_generations += 1,
+                _ => {
+                    best_score = Some(score);
+                    stale_generations = 0;
+                }
+            }
+            log::log!(config.log_level, "generation={generation} stale_generations={stale_generations}");
+            if stale_generations >= patience {
+                return best.clone();
             }
         }
-        let first = candidates.iter().rev().take(10).map(|(rv, _)| rv.clone()).collect::<Vec<_>>();
+        let first = candidates.iter().rev().take(10).map(|(rv, _, _)| rv.clone()).collect::<Vec<_>>();
         let random_pop = std::iter::repeat_with(|| random_variants()).take(10).collect::<Vec<_>>();
-        let highest_value = candidates.iter_mut().fold(0, |acc, (_, rv)| {
+        let highest_value = candidates.iter_mut().fold(0, |acc, (_, rv, _)| {
             *rv.get_mut() += acc;
             *rv.get_mut() = rv.get_mut().pow(2);
             *rv.get_mut()
         });
-        let mut rng = SmallRng::from_entropy();
+        let mut rng = next_seed().map_or_else(SmallRng::from_entropy, SmallRng::seed_from_u64);
 
         let mutants = std::iter::repeat_with(|| {
             let first = rng.gen_range(0..highest_value);
             let second = rng.gen_range(0..highest_value);
 
-            let first = candidates.iter().map(|(rv, a)| (rv, a.load(Ordering::Relaxed))).find(|(_, c)| first < *c).unwrap();
-            let second = candidates.iter().map(|(rv, a)| (rv, a.load(Ordering::Relaxed))).find(|(_, c)| second < *c).unwrap();
+            let first = candidates.iter().map(|(rv, a, _)| (rv, a.load(Ordering::Relaxed))).find(|(_, c)| first < *c).unwrap();
+            let second = candidates.iter().map(|(rv, a, _)| (rv, a.load(Ordering::Relaxed))).find(|(_, c)| second < *c).unwrap();
 
             let mut merge_factor = first.1 as f64 / (first.1 as f64 + second.1 as f64);
             merge_factor += 0.0;
 
-            merge_rvs(first.0.clone(), second.0.clone(), merge_factor)
+            let merged = merge_rvs(first.0.clone(), second.0.clone(), merge_factor);
+            mutate_rv(merged, &mut rng, config)
         })
             .take(80)
             .map(|rv| {
@@ -122,8 +243,9 @@ pub fn opt<G: MonteCarloGame+ CheckWinMonteCarloGame + 'static>() {
         candidates = first.into_iter()
             .chain(random_pop.into_iter())
             .chain(mutants.into_iter())
-            .map(|rv| (rv, AtomicU32::new(0)))
-            .collect::<Vec<_>>()
+            .map(|rv| (rv, AtomicU32::new(0), AtomicU64::new(0)))
+            .collect::<Vec<_>>();
+        generation += 1;
     }
 }
 
@@ -135,6 +257,7 @@ fn merge_rvs(first: RandomValues, second: RandomValues, merge_factor: f64) -> Ra
             }
 
     RandomValues {
+        version: RANDOM_VALUES_VERSION,
         c: mval!(c),
         el_threshold: mval!(el_threshold),
         degregation_1: mval!(degregation_1),
@@ -144,12 +267,32 @@ fn merge_rvs(first: RandomValues, second: RandomValues, merge_factor: f64) -> Ra
     }
 }
 
-fn read_last_checkpoint() -> Option<Vec<RandomValues>> {
+fn mutate_rv(rv: RandomValues, rng: &mut impl Rng, config: OptConfig) -> RandomValues {
+    let mut mutate = |value: f64, (low, high): (f64, f64)| {
+        if !rng.gen_bool(config.mutation_rate.clamp(0.0, 1.0)) {
+            return value;
+        }
+        let noise = Normal::new(0.0, config.mutation_sigma * (high - low)).expect("invalid mutation sigma");
+        (value + noise.sample(rng)).clamp(low, high)
+    };
+
+    RandomValues {
+        version: rv.version,
+        c: mutate(rv.c, C_RANGE),
+        el_threshold: mutate(rv.el_threshold, EL_THRESHOLD_RANGE),
+        degregation_1: mutate(rv.degregation_1, DEGREGATION_RANGE),
+        degregation_2: mutate(rv.degregation_2, DEGREGATION_RANGE),
+        win_reward_1: (mutate(rv.win_reward_1.0, WIN_REWARD_RANGE), mutate(rv.win_reward_1.1, WIN_REWARD_RANGE)),
+        win_reward_2: (mutate(rv.win_reward_2.0, WIN_REWARD_RANGE), mutate(rv.win_reward_2.1, WIN_REWARD_RANGE)),
+    }
+}
+
+fn read_last_checkpoint() -> Result<Vec<RandomValues>, CheckpointError> {
     let dir = match std::fs::read_dir("./") {
         Ok(dir) => dir,
         Err(err) => {
             log::warn!("failed to open current dir: {err}");
-            return None;
+            return Err(CheckpointError::Io(err));
         }
     };
     let checkpoint_regex = regex::Regex::new("^checkpoint(\\d+)$").expect("failed to compile checkpoint regex");
@@ -163,56 +306,65 @@ fn read_last_checkpoint() -> Option<Vec<RandomValues>> {
         Some(f) => f,
         None => {
             log::info!("no file to load from found");
-            return None;
+            return Err(CheckpointError::NoCheckpoint);
         }
     };
     let file_name = file.file_name();
-    let mut file = match std::fs::File::open(file.path()) {
-        Ok(f) => f,
-        Err(e) => {
-            log::warn!("found checkpoint file {:?} but failed to read from it({e})", file_name);
-            return None;
-        }
-    };
-    match serde_json::from_reader::<_, Vec<RandomValues>>(&mut file) {
-        Ok(r) => {
+    match load_checkpoint(&file.path()) {
+        Ok(values) => {
             log::info!("starting from checkpoint file {:?}", file_name);
-            Some(r)
-        },
+            Ok(values)
+        }
         Err(e) => {
-            log::warn!("found checkpoint file {:?} but failed to parse content({e})", file_name);
-            None
+            log::warn!("found checkpoint file {:?} but could not load it: {e}", file_name);
+            Err(e)
         }
     }
 }
 
-fn config_from_rv(monte_limit: MonteLimit, RandomValues{ c, el_threshold, degregation_1, degregation_2, win_reward_1, win_reward_2 }: &RandomValues) -> (MonteLimit, f64, TwoScoreReducerExecutionLimiterFactory<WinRewardInit<WinFactorReduceFactory>, WinRewardInit<WinFactorReduceFactory>>, Option<[u8; 32]>) {
+// the population stored in a checkpoint file, migrated to the current version
+pub fn load_checkpoint(path: &Path) -> Result<Vec<RandomValues>, CheckpointError> {
+    let file = File::open(path).map_err(CheckpointError::Io)?;
+    let values = serde_json::from_reader::<_, Vec<RandomValues>>(BufReader::new(file)).map_err(CheckpointError::Corrupt)?;
+    values.into_iter().map(RandomValues::migrate).collect()
+}
+
+fn config_from_rv(monte_limit: MonteLimit, RandomValues{ c, el_threshold, degregation_1, degregation_2, win_reward_1, win_reward_2, .. }: &RandomValues, seed: Option<[u8; 32]>) -> (MonteLimit, f64, MctsV8Reducer, Option<[u8; 32]>) {
     let wri1 = WinRewardInit::new(win_reward_1.0, win_reward_1.1, WinFactorReduceFactory { by: *degregation_1 });
     let wri2 = WinRewardInit::new(win_reward_2.0, win_reward_2.1, WinFactorReduceFactory { by: *degregation_2 });
-    (monte_limit, *c, TwoScoreReducerFactory::new(wri1, wri2).limiter_from(*el_threshold), None)
+    (monte_limit, *c, TwoScoreReducerFactory::new(wri1, wri2).limiter_from(*el_threshold), seed)
 }
 
-fn do_random_playoffs<G: MonteCarloGame + CheckWinMonteCarloGame + 'static>(monte_limit: MonteLimit, times: usize, vals: &[(RandomValues, AtomicU32)]) {
-    let config_from_random_val = |rv| config_from_rv(monte_limit, rv);
+// with a seed, the players of every game get their own seed derived from it and the position of the game
+fn do_random_playoffs<G: MonteCarloGame + CheckWinMonteCarloGame + 'static>(monte_limit: MonteLimit, times: usize, vals: &[(RandomValues, AtomicU32, AtomicU64)], show_progress: bool, seed: Option<u64>) {
+    let config_from_random_val = |rv, counter: usize| config_from_rv(monte_limit, rv, seed.map(|seed| sub_seed(seed, counter as u64)));
 
     let game_count = AtomicU32::new(0);
     let total_game_count = (0..vals.len()).map(|i| i * times).sum::<usize>();
 
-    vals.par_iter().enumerate()
-        .flat_map(|(i, p1)| vals[..i].par_iter().map(move |p2| (p1, p2)))
-        .for_each(|((rv1, wins1), (rv2, wins2))| {
-            let config1 = config_from_random_val(rv1);
-            let config2 = config_from_random_val(rv2);
+    // a flat list of all pairings balances better across threads than nesting the opponents per candidate
+    let pairings = (0..vals.len())
+        .flat_map(|i| (0..i).map(move |j| (i, j)))
+        .collect::<Vec<(usize, usize)>>();
+
+    pairings.into_par_iter()
+        .enumerate()
+        .map(|(pairing, (i, j))| (pairing, &vals[i], &vals[j]))
+        .for_each(|(pairing, (rv1, wins1, nodes1), (rv2, wins2, nodes2))| {
             for i in 0..times {
+                let game = pairing * times + i;
+                let stats = [Rc::new(RefCell::new(StrategyStats::default())), Rc::new(RefCell::new(StrategyStats::default()))];
                 let mut players: [Box<dyn GamePlayer<G>>; 2] = [
-                    Box::new(MonteCarloStrategyV7::strategy_of(config1.clone())),
-                    Box::new(MonteCarloStrategyV7::strategy_of(config2.clone())),
+                    Box::new(MeasuredPlayer::new(MonteCarloStrategyV7::strategy_of(config_from_random_val(rv1, 2 * game)), stats[0].clone())),
+                    Box::new(MeasuredPlayer::new(MonteCarloStrategyV7::strategy_of(config_from_random_val(rv2, 2 * game + 1)), stats[1].clone())),
                 ];
                 let switch = i % 2 != 0;
                 if switch {
                     players.swap(0, 1)
                 }
                 let (winner, player) = run_game(players);
+                nodes1.fetch_add(stats[0].borrow().nodes, Ordering::Relaxed);
+                nodes2.fetch_add(stats[1].borrow().nodes, Ordering::Relaxed);
                 if winner == Winner::TIE {
                     wins1.fetch_add(1, Ordering::Relaxed);
                     wins2.fetch_add(1, Ordering::Relaxed);
@@ -226,7 +378,7 @@ fn do_random_playoffs<G: MonteCarloGame + CheckWinMonteCarloGame + 'static>(mont
                 }
 
                 let played_games = game_count.fetch_add(1, Ordering::AcqRel);
-                if played_games % 32 < 8 || played_games as usize == total_game_count {
+                if show_progress && (played_games % 32 < 8 || played_games as usize == total_game_count) {
                     print!("\rgame_count: {} of {total_game_count}", played_games);
                     if played_games as usize == total_game_count {
                         println!()
@@ -237,20 +389,70 @@ fn do_random_playoffs<G: MonteCarloGame + CheckWinMonteCarloGame + 'static>(mont
 }
 
 fn run_game<G: MonteCarloGame + 'static>(mut config: [Box<dyn GamePlayer<G>>; 2]) -> (Winner, TwoPlayer) {
+    config.iter_mut().for_each(|player| player.new_game());
     let mut game = G::new();
     let mut last_move = None;
     loop {
-        let config = match game.player() {
+        let mover = game.player();
+        let player = match mover {
             TwoPlayer::P1 => &mut config[0],
             TwoPlayer::P2 => &mut config[1],
         };
-        let m = config.make_move(&game, last_move);
+        let enemy_move = if game.is_simultaneous() { None } else { last_move };
+        let m = player.make_move(&game, enemy_move);
         let (new_game, winner) = game.make_move(&m)
             .expect("could not make move");
         game = new_game;
         last_move = Some(m);
         if let Some(winner) = winner {
-            break (winner, game.player());
+            config.iter_mut().for_each(|player| player.finish(winner, mover, &game));
+            break (winner, mover);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+    use crate::genetic_algo_op::{do_random_playoffs, load_checkpoint, CheckpointError, RandomValues, RANDOM_VALUES_VERSION};
+    use crate::old_monte_carlo::monte_carlo_main::MonteLimit;
+    use crate::tic_tac_toe::TicTacToe;
+
+    fn write_checkpoint(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{name}_{}", std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_named_checkpoint() {
+        // the first candidate predates the version field
+        let path = write_checkpoint("checkpoint_load_test", r#"[
+            {"c": 1.5, "el_threshold": 0.1, "degregation_1": 0.5, "degregation_2": 0.25, "win_reward_1": [1.0, 0.0], "win_reward_2": [-1.0, 0.5]},
+            {"version": 1, "c": 2.0, "el_threshold": 0.2, "degregation_1": 0.5, "degregation_2": 0.5, "win_reward_1": [2.0, 0.0], "win_reward_2": [-2.0, 0.0]}
+        ]"#);
+        let values = load_checkpoint(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(2, values.len());
+        assert!(values.iter().all(|rv| rv.version == RANDOM_VALUES_VERSION));
+        assert_eq!((1.5, (-1.0, 0.5)), (values[0].c, values[0].win_reward_2));
+        assert_eq!(2.0, values[1].c);
+
+        let corrupt = write_checkpoint("checkpoint_corrupt_test", "[{\"c\": 1.0");
+        let result = load_checkpoint(&corrupt);
+        std::fs::remove_file(&corrupt).unwrap();
+        assert!(matches!(result, Err(CheckpointError::Corrupt(_))));
+        assert!(matches!(load_checkpoint(&corrupt), Err(CheckpointError::Io(_))));
+    }
+
+    #[test]
+    fn playoffs_count_the_nodes_of_every_candidate() {
+        let candidate = |c| (RandomValues { version: RANDOM_VALUES_VERSION, c, el_threshold: 0.0, degregation_1: 0.9, degregation_2: 0.9, win_reward_1: (1.0, 0.0), win_reward_2: (1.0, 0.0) }, AtomicU32::new(0), AtomicU64::new(0));
+        let candidates = [candidate(1.0), candidate(2.0)];
+        do_random_playoffs::<TicTacToe>(MonteLimit::times(50), 2, &candidates, false, Some(5));
+        // two points per game are handed out, a tie gives one to each
+        assert_eq!(4, candidates.iter().map(|(_, score, _)| score.load(Ordering::Relaxed)).sum::<u32>());
+        assert!(candidates.iter().all(|(_, _, nodes)| nodes.load(Ordering::Relaxed) > 0));
+    }
+}
@@ -0,0 +1,118 @@
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use crate::ai_infra::GameStrategy;
+use crate::monte_carlo_game::{MonteCarloGame, Winner};
+
+// picks the move leaving the opponent with the fewest immediately winning replies, with a lookahead of
+// depth opponent turns in which we keep answering greedily. Ties are broken randomly, with the seed of the
+// config if there is one
+pub struct GreedyDefensiveStrategy {
+    depth: usize,
+    seed: Option<[u8; 32]>,
+}
+
+impl <G: MonteCarloGame> GameStrategy<G> for GreedyDefensiveStrategy {
+    type Carry = SmallRng;
+    type Config = (usize, Option<[u8; 32]>);
+
+    fn new((depth, seed): Self::Config) -> Self {
+        Self {
+            depth: depth.max(1),
+            seed,
+        }
+    }
+
+    fn make_move(&self, game: &G, carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
+        let mut rng = carry.map(|(_, rng)| rng)
+            .unwrap_or_else(|| self.seed.map_or_else(SmallRng::from_entropy, SmallRng::from_seed));
+        let moves = rated_moves(game, self.depth);
+        let best = moves.iter().map(|(_, rating)| *rating).min().expect("no moves available");
+        let best_moves = moves.into_iter().filter(|(_, rating)| *rating == best).collect::<Vec<_>>();
+        let (m, _) = best_moves.choose(&mut rng).unwrap();
+        (*m, rng)
+    }
+}
+
+// rating is (does not win immediately, winning replies of the opponent, winning replies further into the
+// lookahead), lower is better. The lookahead only breaks ties between moves with as many immediate replies
+fn rated_moves<G: MonteCarloGame>(game: &G, depth: usize) -> Vec<(G::MOVE, (bool, usize, usize))> {
+    game.moves().into_iter()
+        .map(|m| {
+            let (next, winner) = game.make_move(&m).unwrap();
+            let rating = match winner {
+                Some(Winner::WIN) => (false, 0, 0),
+                Some(Winner::TIE) => (true, 0, 0),
+                None => {
+                    let (now, later) = opponent_wins(&next, depth);
+                    (true, now, later)
+                }
+            };
+            (m, rating)
+        })
+        .collect()
+}
+
+// the winning replies of the opponent and the ones left after our best answer to every other reply
+fn opponent_wins<G: MonteCarloGame>(game: &G, depth: usize) -> (usize, usize) {
+    game.moves().into_iter()
+        .fold((0, 0), |(now, later), m| match game.make_move(&m).unwrap() {
+            (_, Some(Winner::WIN)) => (now + 1, later),
+            (next, None) if depth > 1 => {
+                let answer = rated_moves(&next, depth - 1).into_iter().map(|(_, rating)| rating).min();
+                (now, later + answer.map_or(0, |(_, now, later)| now + later))
+            }
+            _ => (now, later),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::GameStrategy;
+    use crate::greedy_defensive_ai::GreedyDefensiveStrategy;
+    use crate::line_four_7x6::LineFourGame;
+    use crate::monte_carlo_game::conformance::{random_walks, self_play};
+    use crate::monte_carlo_game::{MonteCarloGame, Winner};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+
+    #[test]
+    fn blocks_immediate_win() {
+        // x threatens to complete the top row
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I5, I2]).unwrap();
+        for seed in 0..10 {
+            let strategy = <GreedyDefensiveStrategy as GameStrategy<TicTacToe>>::new((1, Some([seed; 32])));
+            assert_eq!(I3, strategy.make_move(&game, None).0);
+        }
+    }
+
+    fn hands_over_win<G: MonteCarloGame>(game: &G, m: &G::MOVE) -> bool {
+        match game.make_move(m).unwrap() {
+            (next, None) => next.moves_with_outcome().any(|(_, winner)| winner == Some(Winner::WIN)),
+            _ => false,
+        }
+    }
+
+    // whenever a move exists that leaves the opponent no winning reply, one of those is played
+    fn assert_no_winning_reply<G: MonteCarloGame>(depth: usize) {
+        let strategy = <GreedyDefensiveStrategy as GameStrategy<G>>::new((depth, Some([1; 32])));
+        for (game, _) in random_walks::<G>().into_iter().filter(|(_, winner)| winner.is_none()).take(300) {
+            if game.moves().into_iter().all(|m| hands_over_win(&game, &m)) {
+                continue;
+            }
+            let (m, _) = strategy.make_move(&game, None);
+            assert!(!hands_over_win(&game, &m), "{m:?} hands over the win in {game:?}");
+        }
+    }
+
+    #[test]
+    fn never_hands_over_a_win() {
+        assert_no_winning_reply::<TicTacToe>(2);
+        assert_no_winning_reply::<LineFourGame>(1);
+    }
+
+    #[test]
+    fn seeded_games_repeat() {
+        let play = || self_play::<LineFourGame, _>(&<GreedyDefensiveStrategy as GameStrategy<LineFourGame>>::new((1, Some([4; 32]))));
+        assert_eq!(play(), play());
+    }
+}
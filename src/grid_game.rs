@@ -0,0 +1,73 @@
+use std::fmt::Formatter;
+
+// a game on a rectangular board, so it can be printed with fmt_grid. Row 0 is the top row
+pub trait GridGame {
+    // (rows, columns)
+    fn dimensions(&self) -> (usize, usize);
+
+    fn cell(&self, row: usize, column: usize) -> char;
+
+    // the labels should match the move notation, letters and rows counted from the top by default
+    fn column_label(&self, column: usize) -> String {
+        match u8::try_from(column).ok().filter(|column| *column < 26) {
+            Some(column) => char::from(b'a' + column).to_string(),
+            None => (column + 1).to_string(),
+        }
+    }
+
+    fn row_label(&self, row: usize) -> String {
+        (row + 1).to_string()
+    }
+}
+
+// the board in a border with the column labels above and the row labels left of it
+//     a b c
+//   +-------+
+// 1 | x . . |
+// 2 | . o . |
+// 3 | . . . |
+//   +-------+
+pub fn fmt_grid<G: GridGame + ?Sized>(game: &G, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let (rows, columns) = game.dimensions();
+    let row_labels = (0..rows).map(|row| game.row_label(row)).collect::<Vec<_>>();
+    let column_labels = (0..columns).map(|column| game.column_label(column)).collect::<Vec<_>>();
+    let margin = row_labels.iter().map(String::len).max().unwrap_or(0);
+    let width = column_labels.iter().map(String::len).max().unwrap_or(0).max(1);
+    let border = "-".repeat(columns * (width + 1) + 1);
+
+    write!(f, "{:margin$}  ", "")?;
+    for label in &column_labels {
+        write!(f, " {label:>width$}")?;
+    }
+    writeln!(f)?;
+    writeln!(f, "{:margin$} +{border}+", "")?;
+    for (row, label) in row_labels.iter().enumerate() {
+        write!(f, "{label:>margin$} |")?;
+        for column in 0..columns {
+            write!(f, " {:>width$}", game.cell(row, column))?;
+        }
+        writeln!(f, " |")?;
+    }
+    writeln!(f, "{:margin$} +{border}+", "")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line_four_8x8::{LineFour8x8, LineFour8x8Index};
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+
+    #[test]
+    fn grids_are_labeled() {
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I5, I9]).unwrap();
+        assert_eq!("    a b c\n  +-------+\n1 | x . . |\n2 | . o . |\n3 | . . x |\n  +-------+\n", game.to_string());
+        // line four counts the columns like its moves and the rows from the bottom
+        let (game, _) = LineFour8x8::new().apply_moves(&[LineFour8x8Index::I3, LineFour8x8Index::I3]).unwrap();
+        let text = game.to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!("    1 2 3 4 5 6 7 8", lines[0]);
+        assert_eq!("2 | . . . o . . . . |", lines[8]);
+        assert_eq!("1 | . . . x . . . . |", lines[9]);
+        assert_eq!(format!("{game:?}"), text);
+    }
+}
@@ -0,0 +1,94 @@
+use std::marker::PhantomData;
+use crate::ai_infra::GameStrategy;
+use crate::alpha_beta_ai::{best_move, score_move};
+use crate::eval_cache::EvalCache;
+use crate::monte_carlo_game::MonteCarloGame;
+
+const VERIFY_DEPTH: u32 = 2;
+
+// plays the move of the wrapped strategy unless a shallow alpha-beta search finds that it
+// allows a forced loss, then the move preferred by alpha-beta is played instead
+pub struct HybridStrategy<G, H> {
+    inner: H,
+    game: PhantomData<G>,
+}
+
+impl <G: MonteCarloGame, H: GameStrategy<G>> GameStrategy<G> for HybridStrategy<G, H> {
+    type Carry = H::Carry;
+    type Config = H::Config;
+
+    fn new(config: Self::Config) -> Self {
+        Self {
+            inner: H::new(config),
+            game: PhantomData,
+        }
+    }
+
+    fn make_move(&self, game: &G, carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
+        let (m, carry) = self.inner.make_move(game, carry);
+        // the searches cache the positions up to VERIFY_DEPTH - 1 plies deep, with room for all of them the
+        // second search gets the score of m from the first one
+        let branching = game.moves().into_iter().count();
+        let mut cache = EvalCache::new(branching.saturating_pow(VERIFY_DEPTH - 1));
+        let score = score_move(game, &m, VERIFY_DEPTH, -i32::MAX, i32::MAX, &mut cache);
+        if score >= 0 {
+            return (m, carry);
+        }
+        match best_move(game, game.ordered_moves(), VERIFY_DEPTH, &mut cache) {
+            Some((verified, verified_score)) if verified_score > score => {
+                log::debug!("replacing {m:?} with {verified:?}, it loses by force");
+                (verified, carry)
+            }
+            _ => (m, carry),
+        }
+    }
+
+    fn reset(&self, carry: &mut Self::Carry) {
+        self.inner.reset(carry);
+    }
+
+    fn name(&self) -> String {
+        format!("Hybrid({})", self.inner.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::GameStrategy;
+    use crate::hybrid_ai::HybridStrategy;
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove, TicTacToeMove::*};
+
+    // always plays the move of its config
+    struct Fixed(TicTacToeMove);
+
+    impl GameStrategy<TicTacToe> for Fixed {
+        type Carry = ();
+        type Config = TicTacToeMove;
+
+        fn new(config: Self::Config) -> Self {
+            Fixed(config)
+        }
+
+        fn make_move(&self, _game: &TicTacToe, _carry: Option<(TicTacToeMove, ())>) -> (TicTacToeMove, ()) {
+            (self.0, ())
+        }
+    }
+
+    #[test]
+    fn losing_move_is_replaced() {
+        // x threatens to complete the top row, only I3 keeps o in the game
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I5, I2]).unwrap();
+        let strategy = <HybridStrategy<TicTacToe, Fixed> as GameStrategy<TicTacToe>>::new(I9);
+        assert_eq!(I3, strategy.make_move(&game, None).0);
+        let strategy = <HybridStrategy<TicTacToe, Fixed> as GameStrategy<TicTacToe>>::new(I3);
+        assert_eq!(I3, strategy.make_move(&game, None).0);
+    }
+
+    #[test]
+    fn safe_move_is_kept() {
+        // nothing is lost in the opening, so the inner move is played
+        let strategy = <HybridStrategy<TicTacToe, Fixed> as GameStrategy<TicTacToe>>::new(I2);
+        assert_eq!(I2, strategy.make_move(&TicTacToe::new(), None).0);
+    }
+}
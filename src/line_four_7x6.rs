@@ -1,13 +1,19 @@
-use std::fmt::{Debug, Formatter, Write};
-use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+use std::fmt::{Debug, Formatter};
+use serde::{Deserialize, Serialize};
+use rand::Rng;
+use crate::grid_game::{fmt_grid, GridGame};
+use crate::monte_carlo_game::{bitboard_key, pack_move_indices, parse_column_notation, random_set_bit, unpack_move_indices, MonteCarloGame, MoveError, MoveIndex, TwoPlayer, Winner};
+use crate::multi_score_reducer::CheckWinMonteCarloGame;
 
-#[derive(Copy, Clone, Hash, Eq,  PartialEq)]
+const TIE: u64 = 0b111111_111111_111111_111111_111111_111111_111111;
+
+#[derive(Copy, Clone, Hash, Eq,  PartialEq, Serialize, Deserialize)]
 pub struct LineFourGame {
     set_by_p1: u64,
     set_by_p2: u64,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum LineFourIndex {
     I0 = 0, I1 = 1, I2 = 2, I3 = 3, I4 = 4, I5 = 5, I6 = 6
@@ -29,17 +35,90 @@ impl TryFrom<u32> for LineFourIndex {
     }
 }
 
+impl TryFrom<&str> for LineFourIndex {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from(parse_column_notation(value, 7)?)
+    }
+}
+
+// the notation PlayerInput parses, "column 4" for I3
+impl std::fmt::Display for LineFourIndex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}", *self as u8 + 1)
+    }
+}
+
+impl MoveIndex for LineFourIndex {
+    fn to_index(&self) -> u32 {
+        *self as u32
+    }
+}
+
+pub fn pack_moves(moves: &[LineFourIndex]) -> Vec<u8> {
+    pack_move_indices(moves, 3)
+}
+
+pub fn unpack_moves(packed: &[u8], count: usize) -> Option<Vec<LineFourIndex>> {
+    unpack_move_indices(packed, 3, count)
+}
+
 impl LineFourGame {
+    // fails if a slot is set by both players, a piece floats above an empty slot, bits outside the
+    // board are set or the piece counts do not allow p1 to have started
+    pub fn from_raw(set_by_p1: u64, set_by_p2: u64) -> Result<Self, ()> {
+        const BOARD: u64 = 0b111111_111111_111111_111111_111111_111111_111111;
+        let all_set = set_by_p1 | set_by_p2;
+        if set_by_p1 & set_by_p2 != 0 || all_set & !BOARD != 0 {
+            return Err(())
+        }
+        let columns_settled = (0..7).all(|i| {
+            let column = (all_set >> (i * 6)) & 0b111111;
+            column & (column + 1) == 0
+        });
+        let piece_diff = set_by_p1.count_ones() as i32 - set_by_p2.count_ones() as i32;
+        if !columns_settled || !(0..=1).contains(&piece_diff) {
+            return Err(())
+        }
+        Ok(Self {
+            set_by_p1,
+            set_by_p2,
+        })
+    }
+
+    // the boards of p1 and p2 in the layout expected by from_raw
+    pub fn raw(&self) -> (u64, u64) {
+        (self.set_by_p1, self.set_by_p2)
+    }
+
+    // bit i is set if a piece can be dropped into column i, none is after a win
+    pub fn legal_mask(&self) -> u8 {
+        if Self::has_won_in(self.set_by_p1) || Self::has_won_in(self.set_by_p2) {
+            return 0;
+        }
+        let used = self.set_by_p1 | self.set_by_p2;
+        let mut viable = 0u8;
+        for i in 0..7 {
+            let mask = 1 << i;
+            let shift_by = 5 * (i + 1);
+            let column = used >> shift_by;
+            let column_top = column & mask;
+            let column_free = column_top ^ mask;
+            viable |= column_free as u8;
+        }
+        viable
+    }
+
     pub fn set_at_index(&mut self, index: &LineFourIndex) -> Result<Option<Winner>, ()> {
         let index: u8 = *index as u8;
         let mut set_index = (((self.set_by_p1 | self.set_by_p2) >> index * 6) & 0b111111).trailing_ones();
         if set_index >= 6 { return Err(()) }
         set_index += (index as u32) * 6;
-        let pnum = self.player() as u8 as u64;
+        let pnum = self.player().as_bit();
         self.set_by_p1 |= pnum << set_index;
         self.set_by_p2 |= (pnum ^ 0b1) << set_index;
         let board = if pnum == 1 { self.set_by_p1 } else { self.set_by_p2 };
-        const TIE: u64 = 0b111111_111111_111111_111111_111111_111111_111111;
         if Self::has_won_in(board) {
             Ok(Some(Winner::WIN))
         } else if self.set_by_p2 | self.set_by_p1 == TIE {
@@ -111,23 +190,29 @@ impl MonteCarloGame for  LineFourGame {
     }
 
     fn moves<'s>(&'s self) -> Self::MOVES<'s> {
-        let used = self.set_by_p1 | self.set_by_p2;
-        let mut viable = 0u8;
-        for i in 0..7 {
-            let mask = 1 << i;
-            let shift_by = 5 * (i + 1);
-            let column = used >> shift_by;
-            let column_top = column & mask;
-            let column_free = column_top ^ mask;
-            viable |= column_free as u8;
-        }
+        let viable = self.legal_mask();
         let moves = &VALID_MOVES[viable as usize][0..(viable.count_ones() as usize)];
         moves.iter().cloned()
     }
 
-    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), ()> {
+    fn random_move(&self, rng: &mut impl Rng) -> Option<Self::MOVE> {
+        random_set_bit(self.legal_mask() as u64, rng).map(|i| LineFourIndex::try_from(i).unwrap())
+    }
+
+    fn ordered_moves(&self) -> impl Iterator<Item = Self::MOVE> + '_ {
+        use LineFourIndex::*;
+        // center columns take part in the most lines
+        const CENTER_FIRST: [LineFourIndex; 7] = [I3, I2, I4, I1, I5, I0, I6];
+        let free = self.legal_mask();
+        CENTER_FIRST.into_iter().filter(move |m| free & (1 << *m as u8) != 0)
+    }
+
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError> {
+        if self.win_state().is_some() {
+            return Err(MoveError::GameOver);
+        }
         let mut new = self.clone();
-        new.set_at_index(m).map(|res| (new, res))
+        new.set_at_index(m).map(|res| (new, res)).map_err(|()| MoveError::Illegal)
     }
 
     fn player(&self) -> TwoPlayer {
@@ -137,30 +222,90 @@ impl MonteCarloGame for  LineFourGame {
             TwoPlayer::P2
         }
     }
+
+    fn ply_count(&self) -> usize {
+        (self.set_by_p1.count_ones() + self.set_by_p2.count_ones()) as usize
+    }
+
+    fn no_moves_result(&self) -> Option<Winner> {
+        self.win_state()
+    }
+
+    fn state_key(&self) -> Vec<u8> {
+        bitboard_key(self.set_by_p1, self.set_by_p2, self.player())
+    }
 }
 
-impl Debug for LineFourGame {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        fn get_char(state: &LineFourGame, index: u8) -> char {
-            if (state.set_by_p1 >> index) & 1 == 1 {
-                'x'
-            } else if (state.set_by_p2 >> index) & 1 == 1 {
-                'o'
-            } else {
-                ' '
-            }
-        }
-        for i in (0..6).rev() {
-            for j in 0..7 {
-                f.write_char('|')?;
-                f.write_char(get_char(self, j * 6 + i))?;
-            }
-            f.write_char('|')?;
-            f.write_char('\n')?;
+impl CheckWinMonteCarloGame for LineFourGame {
+    fn win_state(&self) -> Option<Winner> {
+        if Self::has_won_in(self.set_by_p1) || Self::has_won_in(self.set_by_p2) {
+            Some(Winner::WIN)
+        } else if self.set_by_p2 | self.set_by_p1 == TIE {
+            Some(Winner::TIE)
+        } else {
+            None
         }
-        for _ in 0..15 {
-            f.write_char('-')?;
+    }
+}
+
+impl GridGame for LineFourGame {
+    fn dimensions(&self) -> (usize, usize) {
+        (6, 7)
+    }
+
+    fn cell(&self, row: usize, column: usize) -> char {
+        let index = column * 6 + (5 - row);
+        if (self.set_by_p1 >> index) & 1 == 1 {
+            'x'
+        } else if (self.set_by_p2 >> index) & 1 == 1 {
+            'o'
+        } else {
+            '.'
         }
-        return Ok(())
     }
-}
\ No newline at end of file
+
+    // the column numbers of the moves, rows from the bottom
+    fn column_label(&self, column: usize) -> String {
+        (column + 1).to_string()
+    }
+
+    fn row_label(&self, row: usize) -> String {
+        (6 - row).to_string()
+    }
+}
+
+impl std::fmt::Display for LineFourGame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_grid(self, f)
+    }
+}
+
+impl Debug for LineFourGame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_grid(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line_four_7x6::LineFourGame;
+
+    #[test]
+    fn from_raw_validates() {
+        assert!(LineFourGame::from_raw(0b1, 0b1).is_err());
+        assert!(LineFourGame::from_raw(0b10, 0).is_err());
+        assert!(LineFourGame::from_raw(1 << 42, 0).is_err());
+        assert!(LineFourGame::from_raw(0b1, 0b11 << 6).is_err());
+        assert!(LineFourGame::from_raw(0b10, 0b1).is_ok());
+    }
+
+    #[test]
+    fn column_wrap_is_no_win() {
+        // the top two slots of the first column and the bottom two of the second column
+        let p1 = 0b110100 | 0b11 << 6;
+        let p2 = 0b001011 | 0b11 << 12;
+        let game = LineFourGame::from_raw(p1, p2).unwrap();
+        assert!(!LineFourGame::has_won_in(game.set_by_p1));
+        assert!(LineFourGame::has_won_in(0b1111 << 6));
+    }
+}
@@ -1,9 +1,13 @@
-use std::fmt::{Debug, Formatter, Write};
+use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
+use serde::{Deserialize, Serialize};
 use crate::{MonteCarloGame, TwoPlayer, Winner};
+use crate::grid_game::{fmt_grid, GridGame};
+use rand::Rng;
+use crate::monte_carlo_game::{bitboard_key, pack_move_indices, parse_column_notation, random_set_bit, unpack_move_indices, MoveError, MoveIndex};
 use crate::multi_score_reducer::CheckWinMonteCarloGame;
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct LineFour8x8 {
     //Layout bytes = rows, first byte = first row, etc.
     set_by_p1: u64,
@@ -13,7 +17,7 @@ pub struct LineFour8x8 {
 
 macro_rules! column_index {
     ($name: ident, $($column: ident = $num: literal),* ) => {
-        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
         #[repr(u8)]
         pub enum $name {
             $($column = $num),*
@@ -35,10 +39,36 @@ macro_rules! column_index {
             type Error = ();
             fn try_from(num: u32) -> Result<Self, ()> { Self::try_from(num as u64) }
         }
+        impl MoveIndex for $name {
+            fn to_index(&self) -> u32 { *self as u32 }
+        }
     };
 }
 column_index!(LineFour8x8Index, I0 = 0, I1 = 1, I2 = 2, I3 = 3, I4 = 4, I5 = 5, I6 = 6, I7 = 7);
 
+// the notation PlayerInput parses, "column 4" for I3
+impl std::fmt::Display for LineFour8x8Index {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}", *self as u8 + 1)
+    }
+}
+
+impl TryFrom<&str> for LineFour8x8Index {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_from(parse_column_notation(value, 8)?)
+    }
+}
+
+pub fn pack_moves(moves: &[LineFour8x8Index]) -> Vec<u8> {
+    pack_move_indices(moves, 3)
+}
+
+pub fn unpack_moves(packed: &[u8], count: usize) -> Option<Vec<LineFour8x8Index>> {
+    unpack_move_indices(packed, 3, count)
+}
+
 pub struct AdHocMoves<M: TryFrom<u8>> {
     remaining: u8,
     conv: PhantomData<*const M>
@@ -63,6 +93,55 @@ impl <M: TryFrom<u8>> Iterator for AdHocMoves<M> {
 }
 
 impl LineFour8x8 {
+    // fails if a slot is set by both players, a piece floats above an empty slot, the piece counts do not
+    // allow p1 to have started or player is not the one at turn
+    pub fn from_raw(set_by_p1: u64, set_by_p2: u64, player: TwoPlayer) -> Result<Self, ()> {
+        if set_by_p1 & set_by_p2 != 0 {
+            return Err(())
+        }
+        let all_set = set_by_p1 | set_by_p2;
+        // every row may only contain pieces where the row below has some as well
+        if (all_set >> 8) & !all_set != 0 {
+            return Err(())
+        }
+        let piece_diff = set_by_p1.count_ones() as i32 - set_by_p2.count_ones() as i32;
+        if !(0..=1).contains(&piece_diff) {
+            return Err(())
+        }
+        let (last_mover, last_board, other_board) = if piece_diff == 1 {
+            (TwoPlayer::P1, set_by_p1, set_by_p2)
+        } else {
+            (TwoPlayer::P2, set_by_p2, set_by_p1)
+        };
+        // only the last mover can have won, it stays at turn once the game is over
+        if Self::won(other_board) {
+            return Err(())
+        }
+        let at_turn = if Self::won(last_board) || all_set == u64::MAX { last_mover } else { last_mover.next() };
+        if player != at_turn {
+            return Err(())
+        }
+        Ok(Self {
+            set_by_p1,
+            set_by_p2,
+            player,
+        })
+    }
+
+    // the boards of p1 and p2 in the layout expected by from_raw
+    pub fn raw(&self) -> (u64, u64) {
+        (self.set_by_p1, self.set_by_p2)
+    }
+
+    // bit i is set if a piece can be dropped into column i, none is after a win
+    pub fn legal_mask(&self) -> u8 {
+        if Self::won(self.set_by_p1) || Self::won(self.set_by_p2) {
+            return 0;
+        }
+        let all_unset = !(self.set_by_p1 | self.set_by_p2);
+        (all_unset >> (8 * 7)) as u8
+    }
+
     fn won(board: u64) -> bool {
         // check vertical wins by ANDing each slot the three slots BEFORE it, only check the last 5 slots,
         // since the first 3 are polluted by the elements from the last row
@@ -110,16 +189,25 @@ impl MonteCarloGame for LineFour8x8 {
     }
 
     fn moves(&self) -> Self::MOVES<'_> {
-        let all_set = self.set_by_p2 | self.set_by_p1;
-        let all_unset = !all_set;
-        let unset_top_row = all_unset >> 8 * 7;
-        return AdHocMoves {
-            remaining: unset_top_row as u8,
+        AdHocMoves {
+            remaining: self.legal_mask(),
             conv: Default::default()
         }
     }
 
-    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), ()> {
+    fn ordered_moves(&self) -> impl Iterator<Item = Self::MOVE> + '_ {
+        use LineFour8x8Index::*;
+        // center columns take part in the most lines
+        const CENTER_FIRST: [LineFour8x8Index; 8] = [I3, I4, I2, I5, I1, I6, I0, I7];
+        let free = self.legal_mask();
+        CENTER_FIRST.into_iter().filter(move |m| free & (1 << *m as u8) != 0)
+    }
+
+    fn random_move(&self, rng: &mut impl Rng) -> Option<Self::MOVE> {
+        random_set_bit(self.legal_mask() as u64, rng).map(|i| LineFour8x8Index::try_from(i).unwrap())
+    }
+
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError> {
         //1 in the first slot of each row, effectively 1 in  all slots of the first column
         const COLUMN_MASK: u64 = 0x01_01_01_01_01_01_01_01;
         let index = *m as u8 as u32;
@@ -135,13 +223,18 @@ impl MonteCarloGame for LineFour8x8 {
         // bit index of the new piece
         let set_index = not_set_in_column.trailing_zeros();
 
+        let own = match self.player() {
+            TwoPlayer::P1 => self.set_by_p1,
+            TwoPlayer::P2 => self.set_by_p2,
+        };
+        // the winner stays at turn
+        if Self::won(own) || all_set == u64::MAX {
+            return Err(MoveError::GameOver)
+        }
         if not_set_in_column == 0 {
-            return Err(())
+            return Err(MoveError::Illegal)
         }
-        let pnum = match self.player() {
-            TwoPlayer::P1 => 1,
-            TwoPlayer::P2 => 0,
-        };
+        let pnum = self.player().as_bit();
 
         // set the piece in p1 if p1 is at turn an vise-versa
         let new_p1 = self.set_by_p1 | (pnum << set_index);
@@ -168,6 +261,18 @@ impl MonteCarloGame for LineFour8x8 {
     fn player(&self) -> TwoPlayer {
         self.player
     }
+
+    fn no_moves_result(&self) -> Option<Winner> {
+        self.win_state()
+    }
+
+    fn ply_count(&self) -> usize {
+        (self.set_by_p1.count_ones() + self.set_by_p2.count_ones()) as usize
+    }
+
+    fn state_key(&self) -> Vec<u8> {
+        bitboard_key(self.set_by_p1, self.set_by_p2, self.player())
+    }
 }
 
 impl CheckWinMonteCarloGame for LineFour8x8 {
@@ -183,23 +288,93 @@ impl CheckWinMonteCarloGame for LineFour8x8 {
     }
 }
 
+impl GridGame for LineFour8x8 {
+    fn dimensions(&self) -> (usize, usize) {
+        (8, 8)
+    }
+
+    fn cell(&self, row: usize, column: usize) -> char {
+        let index = (7 - row) * 8 + column;
+        if (self.set_by_p1 >> index) & 1 == 1 {
+            'x'
+        } else if (self.set_by_p2 >> index) & 1 == 1 {
+            'o'
+        } else {
+            '.'
+        }
+    }
+
+    // the column numbers of the moves, rows from the bottom
+    fn column_label(&self, column: usize) -> String {
+        (column + 1).to_string()
+    }
+
+    fn row_label(&self, row: usize) -> String {
+        (8 - row).to_string()
+    }
+}
+
+impl std::fmt::Display for LineFour8x8 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_grid(self, f)
+    }
+}
+
 impl Debug for LineFour8x8 {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for r in (0..8).rev() {
-            for c in 0..8 {
-                f.write_char('|')?;
-                let char = if (self.set_by_p1 >> (r * 8 + c)) & 1 == 1 {
-                    'x'
-                } else if (self.set_by_p2 >> (r * 8 + c)) & 1 == 1 {
-                    'o'
-                } else {
-                    ' '
-                };
-                f.write_char(char)?;
-            }
-            f.write_char('|')?;
-            f.write_char('\n')?;
+        fmt_grid(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line_four_8x8::{LineFour8x8, LineFour8x8Index};
+    use crate::monte_carlo_game::{MoveIndex, TwoPlayer, Winner};
+    use crate::multi_score_reducer::CheckWinMonteCarloGame;
+
+    #[test]
+    fn column_index_conversions() {
+        for i in 0..8u8 {
+            let m = LineFour8x8Index::try_from(i).unwrap();
+            assert_eq!(u32::from(i), m.to_index());
+            assert_eq!(Ok(m), LineFour8x8Index::try_from(u32::from(i)));
+            assert_eq!(Ok(m), LineFour8x8Index::try_from(u64::from(i)));
+            assert_eq!(Ok(m), LineFour8x8Index::try_from(m.to_index()));
         }
-        return Ok(())
+        for i in [8u8, 9, 15, 255] {
+            assert_eq!(Err(()), LineFour8x8Index::try_from(i));
+            assert_eq!(Err(()), LineFour8x8Index::try_from(u32::from(i)));
+            assert_eq!(Err(()), LineFour8x8Index::try_from(u64::from(i)));
+        }
+        assert_eq!(Err(()), LineFour8x8Index::try_from(u32::MAX));
+        assert_eq!(Err(()), LineFour8x8Index::try_from(u64::MAX));
+        assert_eq!(Err(()), LineFour8x8Index::try_from(1u64 << 32));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn from_raw_validates() {
+        assert!(LineFour8x8::from_raw(0b1, 0b1, TwoPlayer::P1).is_err());
+        assert!(LineFour8x8::from_raw(1 << 8, 0, TwoPlayer::P2).is_err());
+        assert!(LineFour8x8::from_raw(1 << 8, 1, TwoPlayer::P1).is_ok());
+        // p2 can not have more pieces than p1 or p1 two more than p2
+        assert!(LineFour8x8::from_raw(0b1, 0b110, TwoPlayer::P1).is_err());
+        assert!(LineFour8x8::from_raw(0b111, 0, TwoPlayer::P2).is_err());
+        // p2 is at turn after p1 moved last
+        assert!(LineFour8x8::from_raw(0b11, 0b100, TwoPlayer::P1).is_err());
+        assert!(LineFour8x8::from_raw(0b11, 0b100, TwoPlayer::P2).is_ok());
+        // p1 won with the last move and stays at turn, p2 can not have won as well
+        assert!(LineFour8x8::from_raw(0b1111, 0b111 << 8, TwoPlayer::P1).is_ok());
+        assert!(LineFour8x8::from_raw(0b1111, 0b111 << 8, TwoPlayer::P2).is_err());
+        assert!(LineFour8x8::from_raw(0b111 << 8 | 0b11 << 4, 0b1111, TwoPlayer::P1).is_err());
+    }
+
+    #[test]
+    fn row_wrap_is_no_win() {
+        // the last two slots of the first row and the first two of the second row
+        let p1 = 0b11 << 6 | 0b11 << 8;
+        let game = LineFour8x8::from_raw(p1, 0b111, TwoPlayer::P2).unwrap();
+        assert_eq!(None, game.win_state());
+        let game = LineFour8x8::from_raw(0b1111 << 2, 0b11 | 0b1 << 8, TwoPlayer::P1).unwrap();
+        assert_eq!(Some(Winner::WIN), game.win_state());
+    }
+}
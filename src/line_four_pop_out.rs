@@ -0,0 +1,340 @@
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use serde::{Deserialize, Serialize};
+use crate::grid_game::{fmt_grid, GridGame};
+use crate::line_four_7x6::{LineFourGame, LineFourIndex};
+use crate::monte_carlo_game::{bitboard_key, MonteCarloGame, MoveError, TwoPlayer, Winner};
+
+// connect four on the 7x6 board, where a player may also remove one of their own pieces from the
+// bottom of a column instead of dropping one. Uses the same layout as LineFourGame, the player and the
+// plies are stored as they cannot be derived from the piece counts anymore
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct ConnectFourPopOut {
+    set_by_p1: u64,
+    set_by_p2: u64,
+    player: TwoPlayer,
+    plies: u16,
+    // a pop completed only the line of the player to move. A WIN always belongs to the player who moved
+    // last, so like in Misere they get Claim as only move, which wins the game for them
+    claiming: bool,
+}
+
+// the same position reached after a different number of plies is the same state, otherwise a position
+// could never repeat
+impl PartialEq for ConnectFourPopOut {
+    fn eq(&self, other: &Self) -> bool {
+        (self.set_by_p1, self.set_by_p2, self.player, self.claiming) == (other.set_by_p1, other.set_by_p2, other.player, other.claiming)
+    }
+}
+
+impl Eq for ConnectFourPopOut {}
+
+impl Hash for ConnectFourPopOut {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.set_by_p1, self.set_by_p2, self.player, self.claiming).hash(state)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum PopOutMove {
+    Drop(LineFourIndex),
+    Pop(LineFourIndex),
+    Claim,
+}
+
+impl std::fmt::Display for PopOutMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PopOutMove::Drop(column) => write!(f, "drop {column}"),
+            PopOutMove::Pop(column) => write!(f, "pop {column}"),
+            PopOutMove::Claim => write!(f, "claim"),
+        }
+    }
+}
+
+const COLUMN: u64 = 0b111111;
+const FULL: u64 = 0b111111_111111_111111_111111_111111_111111_111111;
+
+pub struct PopOutMoves {
+    drops: u8,
+    pops: u8,
+    claim: bool,
+}
+
+impl Iterator for PopOutMoves {
+    type Item = PopOutMove;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        fn take_lowest(mask: &mut u8) -> LineFourIndex {
+            let index = mask.trailing_zeros();
+            *mask &= *mask - 1;
+            LineFourIndex::try_from(index).unwrap()
+        }
+        if self.claim {
+            self.claim = false;
+            Some(PopOutMove::Claim)
+        } else if self.drops != 0 {
+            Some(PopOutMove::Drop(take_lowest(&mut self.drops)))
+        } else if self.pops != 0 {
+            Some(PopOutMove::Pop(take_lowest(&mut self.pops)))
+        } else {
+            None
+        }
+    }
+}
+
+impl ConnectFourPopOut {
+    // whoever completed a line is the player of the state, see make_move
+    fn result(&self) -> Option<Winner> {
+        if self.claiming {
+            None
+        } else if LineFourGame::has_won_in(self.set_by_p1) || LineFourGame::has_won_in(self.set_by_p2) {
+            Some(Winner::WIN)
+        } else if self.set_by_p1 | self.set_by_p2 == FULL {
+            Some(Winner::TIE)
+        } else {
+            None
+        }
+    }
+
+    fn own_board(&self) -> u64 {
+        match self.player {
+            TwoPlayer::P1 => self.set_by_p1,
+            TwoPlayer::P2 => self.set_by_p2,
+        }
+    }
+
+    fn drop_piece(&mut self, column: u32) -> Result<(), MoveError> {
+        let height = (((self.set_by_p1 | self.set_by_p2) >> (column * 6)) & COLUMN).trailing_ones();
+        if height >= 6 {
+            return Err(MoveError::Illegal)
+        }
+        let set_index = column * 6 + height;
+        let pnum = self.player.as_bit();
+        self.set_by_p1 |= pnum << set_index;
+        self.set_by_p2 |= (pnum ^ 1) << set_index;
+        Ok(())
+    }
+
+    fn pop_piece(&mut self, column: u32) -> Result<(), MoveError> {
+        let shift = column * 6;
+        if (self.own_board() >> shift) & 1 == 0 {
+            return Err(MoveError::Illegal)
+        }
+        // remove the bottom piece, the pieces above fall down by one
+        let settle = |board: u64| {
+            let column_bits = (board >> shift) & COLUMN;
+            (board & !(COLUMN << shift)) | ((column_bits >> 1) << shift)
+        };
+        self.set_by_p1 = settle(self.set_by_p1);
+        self.set_by_p2 = settle(self.set_by_p2);
+        Ok(())
+    }
+}
+
+impl MonteCarloGame for ConnectFourPopOut {
+    type MOVE = PopOutMove;
+    type MOVES<'s> = PopOutMoves;
+
+    fn new() -> Self {
+        Self {
+            set_by_p1: 0,
+            set_by_p2: 0,
+            player: TwoPlayer::P1,
+            plies: 0,
+            claiming: false,
+        }
+    }
+
+    fn moves(&self) -> Self::MOVES<'_> {
+        if self.result().is_some() {
+            return PopOutMoves { drops: 0, pops: 0, claim: false };
+        }
+        if self.claiming {
+            return PopOutMoves { drops: 0, pops: 0, claim: true };
+        }
+        let used = self.set_by_p1 | self.set_by_p2;
+        let own = self.own_board();
+        let mut drops = 0u8;
+        let mut pops = 0u8;
+        for i in 0..7 {
+            drops |= (((used >> (i * 6 + 5)) & 1 ^ 1) as u8) << i;
+            pops |= (((own >> (i * 6)) & 1) as u8) << i;
+        }
+        PopOutMoves { drops, pops, claim: false }
+    }
+
+    // the winner is the player of the returned state; a pop may complete a line of the opponent,
+    // who then has to claim the win unless the popping player completed a line as well
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError> {
+        if self.result().is_some() {
+            return Err(MoveError::GameOver);
+        }
+        let mut new = *self;
+        new.plies += 1;
+        match (*m, self.claiming) {
+            // the claimer stays the player, like the winner of the other moves
+            (PopOutMove::Claim, true) => {
+                new.claiming = false;
+                return Ok((new, Some(Winner::WIN)));
+            }
+            (PopOutMove::Drop(column), false) => new.drop_piece(column as u32)?,
+            (PopOutMove::Pop(column), false) => new.pop_piece(column as u32)?,
+            _ => return Err(MoveError::Illegal),
+        }
+        let own_won = LineFourGame::has_won_in(new.own_board());
+        new.player = self.player.next();
+        let other_won = LineFourGame::has_won_in(new.own_board());
+        let winner = if own_won {
+            new.player = self.player;
+            Some(Winner::WIN)
+        } else if other_won {
+            new.claiming = true;
+            None
+        } else if new.set_by_p1 | new.set_by_p2 == FULL {
+            Some(Winner::TIE)
+        } else {
+            None
+        };
+        Ok((new, winner))
+    }
+
+    fn player(&self) -> TwoPlayer {
+        self.player
+    }
+
+    fn no_moves_result(&self) -> Option<Winner> {
+        self.result()
+    }
+
+    fn ply_count(&self) -> usize {
+        usize::from(self.plies)
+    }
+
+    // the bitboard key with a byte for a pending claim
+    fn state_key(&self) -> Vec<u8> {
+        let mut key = bitboard_key(self.set_by_p1, self.set_by_p2, self.player);
+        key.push(u8::from(self.claiming));
+        key
+    }
+
+    // popping allows the same position to come up again, the third occurrence is a draw
+    fn is_drawn_by_repetition(history: &[Self]) -> bool {
+        match history.last() {
+            Some(current) => history.iter().filter(|state| *state == current).count() >= 3,
+            None => false,
+        }
+    }
+
+    const TRACKS_REPETITION: bool = true;
+}
+
+impl GridGame for ConnectFourPopOut {
+    fn dimensions(&self) -> (usize, usize) {
+        (6, 7)
+    }
+
+    fn cell(&self, row: usize, column: usize) -> char {
+        let index = column * 6 + (5 - row);
+        if (self.set_by_p1 >> index) & 1 == 1 {
+            'x'
+        } else if (self.set_by_p2 >> index) & 1 == 1 {
+            'o'
+        } else {
+            '.'
+        }
+    }
+
+    // the column numbers of the moves, rows from the bottom
+    fn column_label(&self, column: usize) -> String {
+        (column + 1).to_string()
+    }
+
+    fn row_label(&self, row: usize) -> String {
+        (6 - row).to_string()
+    }
+}
+
+impl std::fmt::Display for ConnectFourPopOut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_grid(self, f)
+    }
+}
+
+impl Debug for ConnectFourPopOut {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_grid(self, f)?;
+        if self.claiming {
+            writeln!(f, "{:?} can claim", self.player)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::{GamePlayer, RecordedMoves};
+    use crate::line_four_7x6::LineFourIndex::*;
+    use crate::line_four_pop_out::ConnectFourPopOut;
+    use crate::line_four_pop_out::PopOutMove::*;
+    use crate::monte_carlo_game::{outcome_for, MonteCarloGame, MoveError, Score, TwoPlayer, Winner};
+    use crate::{run_game, GameLimits};
+
+    #[test]
+    fn pop_settles_column() {
+        let (game, winner) = ConnectFourPopOut::new().apply_moves(&[Drop(I0), Drop(I0), Drop(I1)]).unwrap();
+        assert_eq!(None, winner);
+        assert!(!game.moves().any(|m| m == Pop(I0)));
+        let (game, _) = game.apply_moves(&[Drop(I0)]).unwrap();
+        assert!(game.moves().any(|m| m == Pop(I0)));
+        assert!(game.make_move(&Pop(I2)).is_err());
+        let (game, _) = game.make_move(&Pop(I0)).unwrap();
+        // both of p2's pieces fell down by one
+        assert_eq!(1 << 6, game.set_by_p1);
+        assert_eq!(0b11, game.set_by_p2);
+        // the pop counts as a ply though a piece left the board
+        assert_eq!(5, game.ply_count());
+    }
+
+    #[test]
+    fn pop_can_complete_opponent_line() {
+        // p1 has three in the bottom row next to the first column, which holds a p1 piece above p2's
+        let game = ConnectFourPopOut {
+            set_by_p1: 0b10 | 1 << 6 | 1 << 12 | 1 << 18,
+            set_by_p2: 0b01 | 1 << 30 | 1 << 36,
+            player: TwoPlayer::P2,
+            plies: 7,
+            claiming: false,
+        };
+        let (game, winner) = game.make_move(&Pop(I0)).unwrap();
+        assert_eq!(None, winner);
+        assert_eq!(TwoPlayer::P1, game.player());
+        assert_eq!(vec![Claim], game.moves().collect::<Vec<_>>());
+        assert_eq!(Err(MoveError::Illegal), game.make_move(&Drop(I4)).map(|_| ()));
+        let (game, winner) = game.make_move(&Claim).unwrap();
+        assert_eq!(Some(Winner::WIN), winner);
+        assert_eq!(TwoPlayer::P1, game.player());
+        assert_eq!(Some(Winner::WIN), game.no_moves_result());
+    }
+
+    #[test]
+    fn runner_credits_the_opponent_of_the_pop() {
+        // p2 pops its bottom piece of the first column, the p1 piece above falls next to p1's three
+        let p1 = RecordedMoves(vec![Drop(I1), Drop(I2), Drop(I0), Drop(I3), Claim]);
+        let p2 = RecordedMoves(vec![Drop(I0), Drop(I6), Drop(I6), Pop(I0)]);
+        let players: [Box<dyn GamePlayer<ConnectFourPopOut>>; 2] = [Box::new(p1), Box::new(p2)];
+        let (winner, game, last_mover) = run_game(players, false, GameLimits::default());
+        assert_eq!(Winner::WIN, winner);
+        assert_eq!(TwoPlayer::P1, last_mover);
+        assert_eq!(Score::Win, outcome_for(winner, last_mover, TwoPlayer::P1));
+        assert_eq!(9, game.ply_count());
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let start = ConnectFourPopOut::new();
+        let (a, _) = start.apply_moves(&[Drop(I0), Drop(I1)]).unwrap();
+        assert!(!ConnectFourPopOut::is_drawn_by_repetition(&[a, start, a]));
+        assert!(ConnectFourPopOut::is_drawn_by_repetition(&[a, start, a, start, a]));
+    }
+}
@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::ai_infra::GameStrategy;
+use crate::line_four_7x6::LineFourGame;
+use crate::line_four_8x8::LineFour8x8;
+use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+
+// worth more than any evaluation, plus the remaining depth so quicker wins are preferred
+const WIN_VALUE: f32 = 1.0e6;
+
+// fixed size description of a position from the view of the player to move
+pub trait LinearFeatures: MonteCarloGame {
+    const FEATURE_COUNT: usize;
+    fn features(&self) -> Vec<f32>;
+}
+
+// one-hot per cell, feature 2 * i is set if the player to move owns cell i, 2 * i + 1 if the opponent does
+fn cell_features(set_by_p1: u64, set_by_p2: u64, cells: u32, player: TwoPlayer) -> Vec<f32> {
+    let (own, other) = match player {
+        TwoPlayer::P1 => (set_by_p1, set_by_p2),
+        TwoPlayer::P2 => (set_by_p2, set_by_p1),
+    };
+    (0..cells)
+        .flat_map(|i| [(own >> i) & 1, (other >> i) & 1])
+        .map(|bit| bit as f32)
+        .collect()
+}
+
+impl LinearFeatures for LineFourGame {
+    const FEATURE_COUNT: usize = 2 * 42;
+
+    fn features(&self) -> Vec<f32> {
+        let (set_by_p1, set_by_p2) = self.raw();
+        cell_features(set_by_p1, set_by_p2, 42, self.player())
+    }
+}
+
+impl LinearFeatures for LineFour8x8 {
+    const FEATURE_COUNT: usize = 2 * 64;
+
+    fn features(&self) -> Vec<f32> {
+        let (set_by_p1, set_by_p2) = self.raw();
+        cell_features(set_by_p1, set_by_p2, 64, self.player())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LinearWeights {
+    pub weights: Vec<f32>,
+}
+
+#[derive(Debug)]
+pub enum WeightsError {
+    Io(std::io::Error),
+    Corrupt(serde_json::Error),
+    WrongLength { expected: usize, found: usize },
+}
+
+impl LinearWeights {
+    // reads the weights as json and checks that there is one weight per feature of G
+    pub fn load<G: LinearFeatures>(path: impl AsRef<Path>) -> Result<Self, WeightsError> {
+        let file = std::fs::File::open(path).map_err(WeightsError::Io)?;
+        let weights = serde_json::from_reader::<_, LinearWeights>(file).map_err(WeightsError::Corrupt)?;
+        if weights.weights.len() != G::FEATURE_COUNT {
+            return Err(WeightsError::WrongLength { expected: G::FEATURE_COUNT, found: weights.weights.len() });
+        }
+        Ok(weights)
+    }
+
+    pub fn evaluate<G: LinearFeatures>(&self, game: &G) -> f32 {
+        game.features().iter().zip(&self.weights).map(|(f, w)| f * w).sum()
+    }
+}
+
+// negamax to a fixed depth, the positions at the depth limit are scored by the weights
+pub struct LinearEvalStrategy<G> {
+    weights: LinearWeights,
+    depth: u32,
+    game: PhantomData<G>,
+}
+
+impl <G: LinearFeatures> GameStrategy<G> for LinearEvalStrategy<G> {
+    type Carry = ();
+    // (weights, search depth)
+    type Config = (LinearWeights, u32);
+
+    fn new((weights, depth): Self::Config) -> Self {
+        Self {
+            weights,
+            depth: depth.max(1),
+            game: PhantomData,
+        }
+    }
+
+    fn make_move(&self, game: &G, _carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
+        let mut best = None;
+        for m in game.ordered_moves() {
+            let score = score_move(game, &m, self.depth, &self.weights);
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((m, score));
+            }
+        }
+        (best.expect("no moves available").0, ())
+    }
+}
+
+// score of the move for the player making it
+fn score_move<G: LinearFeatures>(game: &G, m: &G::MOVE, depth: u32, weights: &LinearWeights) -> f32 {
+    match game.make_move(m).expect("generated move is illegal") {
+        (_, Some(Winner::WIN)) => WIN_VALUE + depth as f32,
+        (_, Some(Winner::TIE)) => 0.0,
+        (next, None) => -negamax(&next, depth - 1, weights),
+    }
+}
+
+fn negamax<G: LinearFeatures>(game: &G, depth: u32, weights: &LinearWeights) -> f32 {
+    if depth == 0 {
+        return weights.evaluate(game);
+    }
+    game.moves().into_iter()
+        .map(|m| score_move(game, &m, depth, weights))
+        .reduce(f32::max)
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::GameStrategy;
+    use crate::line_four_7x6::{LineFourGame, LineFourIndex};
+    use crate::linear_eval_ai::{LinearEvalStrategy, LinearFeatures, LinearWeights};
+    use crate::monte_carlo_game::MonteCarloGame;
+
+    #[test]
+    fn prefers_center_with_center_weights() {
+        // cell i is in column i / 6, own pieces count by their closeness to the center, the opponent's against
+        let weights = (0..42)
+            .map(|i| 3.0 - (3.0 - (i / 6) as f32).abs())
+            .flat_map(|value| [value, -value])
+            .collect::<Vec<_>>();
+        assert_eq!(LineFourGame::FEATURE_COUNT, weights.len());
+        let weights = LinearWeights { weights };
+        for depth in [1, 2, 3] {
+            let strategy = LinearEvalStrategy::<LineFourGame>::new((weights.clone(), depth));
+            assert_eq!(LineFourIndex::I3, strategy.make_move(&LineFourGame::new(), None).0);
+        }
+    }
+}
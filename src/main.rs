@@ -1,7 +1,10 @@
 extern crate core;
 
 
+use std::cell::RefCell;
+use std::fmt::Display;
 use std::mem::size_of;
+use std::rc::Rc;
 use log::LevelFilter;
 use old_monte_carlo::monte_carlo_main::*;
 use old_monte_carlo::monte_carlo_main3::*;
@@ -11,14 +14,15 @@ use crate::ai_infra::*;
 use crate::dumm_ai::DummAi;
 use crate::genetic_algo_op::opt;
 use crate::line_four_8x8::{LineFour8x8};
-use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+use crate::match_stats::{is_significantly_better, MatchTally, StrategyStats};
+use crate::monte_carlo_game::{outcome_for, MonteCarloGame, TwoPlayer, Winner};
 
 use crate::monte_carlo_win_reducer::{ScoreAveragerFactory, WinFactorReduceFactory, WinIdentFactory};
 use crate::multi_score_reducer::{TwoScoreReducerFactory, WinRewardInit};
 
 
 
-use crate::old_monte_carlo::monte_carlo_main8::MonteCarloStrategyV8;
+use crate::old_monte_carlo::monte_carlo_main8::{MctsV8Builder, MctsV8Reducer, MonteCarloStrategyV8};
 
 
 mod line_four_7x6;
@@ -26,36 +30,47 @@ mod monte_carlo_game;
 mod ai_infra;
 mod monte_carlo_win_reducer;
 mod line_four_8x8;
+mod line_four_pop_out;
 mod old_monte_carlo;
 mod monte_carlo_v2;
 mod multi_score_reducer;
 mod tic_tac_toe;
 mod monte_carlo_game_v2;
 mod dumm_ai;
+mod greedy_defensive_ai;
+mod alpha_beta_ai;
+mod eval_cache;
+mod hybrid_ai;
 mod genetic_algo_op;
 mod uno_basic_game;
+mod net;
+mod data_collection;
+mod game_session;
+mod solver;
+mod linear_eval_ai;
+mod mirrored_game;
+mod simultaneous_game;
+mod match_stats;
+mod misere_game;
+mod transcript;
+mod tablebase_ai;
+mod mnk_game;
+mod grid_game;
 
 fn main() {
     println!("Hello, world!");
     env_logger::builder().filter_level(LevelFilter::Info).init();
     //rayon::ThreadPoolBuilder::new().num_threads(4).build_global().expect("failed to build thread pool");
-    //opt::<LineFour8x8>();
+    //println!("best genome: {:?}", opt::<LineFour8x8>(OptConfig::default()));
 
 
-    run_games::<LineFour8x8,  _>(15, || {
+    run_games::<LineFour8x8,  _>(15, 0, |seeds| {
         let long_view_eval = WinFactorReduceFactory { by: 0.5 };
-        let score_reducer1 = TwoScoreReducerFactory::new(
-            WinRewardInit::new
-                (-1.5, 5.0, long_view_eval),
-            WinRewardInit::new(1.0, 5.0, long_view_eval),
-        );
-
         let score_reducer2 =TwoScoreReducerFactory::new(
             WinRewardInit::new(1.0, 5.0, long_view_eval),
             WinRewardInit::new(-1.5, 5.0, long_view_eval),
         );
 
-        let trs1 = score_reducer1.limiter_from(0.0001);
         let _trs2 = score_reducer2.limiter_from(0.0001);
 
         //let best_ai = genetic_algo_op::load_best_from_pop::<LineFour8x8>(MonteLimit::duration(100)).expect("no impl");
@@ -66,7 +81,7 @@ fn main() {
             //Box::new(MonteCarloStrategyV5::strategy_of((MonteLimit::Duration { millis: NonZeroU64::new(2000).unwrap() }, std::f64::consts::SQRT_2, half_wr, win_reward2, None))),
             //Box::new(MonteCarloStrategyV6::strategy_of((MonteLimit::duration(1000), 1.0, score_reducer.clone(), None))),
             Box::new(DummAi::strategy_of(())),
-            Box::new(MonteCarloStrategyV8::strategy_of((MonteLimit::duration(100), 1.0, trs1, None))),
+            Box::new(MonteCarloStrategyV8::strategy_of(MctsV8Builder::new().time_ms(100).exploration(1.0).win_reward(1.0, 5.0).loss_reward(-1.5).decay(0.5).seed(seeds[1]).build())),
             //Box::new(MonteCarloStrategyV6::strategy_of((MonteLimit::duration(100), 1.0, score_reducer, None))),
             //Box::new(PlayerInput)
             //Box::new(RecordedMoves(vec![LineFour8x8Index::I3, LineFour8x8Index::I3, LineFour8x8Index::I5, LineFour8x8Index::I3]))
@@ -74,66 +89,253 @@ fn main() {
 
         config
     });
-}
 
-fn run_games<G: MonteCarloGame + 'static, F: FnMut() -> [Box<dyn GamePlayer<G>>; 2]>(times: u32, mut config: F) {
-    let mut p1_win = 0u32;
-    let mut p2_win = 0u32;
-    let mut tie = 0u32;
+    let advantage = measure_first_player_advantage::<LineFour8x8, MonteCarloStrategyV8<_, MctsV8Reducer>>(|seat| {
+        MctsV8Builder::new().playoffs(1000).seed(sub_seed(0, u64::from(seat))).build()
+    }, 10);
+    println!("first player advantage: {advantage:.3}");
+    print_pv(&LineFour8x8::new(), &MonteCarloStrategyV8::new(MctsV8Builder::new().time_ms(100).exploration(1.0).build()), 8);
+}
 
-    //is swapped immediately
-    let mut p1_win_ref = &mut p2_win;
-    let mut p2_win_ref = &mut p1_win;
+// config gets the seeds for the first and the second player of every game, derived from seed, so a match of
+// seeded strategies with a fixed number of playoffs plays out the same every time.
+// Returns the tally of the first player and the effort of the first and the second player
+fn run_games<G: MonteCarloGame + 'static, F: FnMut([[u8; 32]; 2]) -> [Box<dyn GamePlayer<G>>; 2]>(times: u32, seed: u64, mut config: F) -> (MatchTally, [StrategyStats; 2]) where G::MOVE: Display {
+    let mut tally = MatchTally::default();
+    let mut names = None;
+    let stats = [(); 2].map(|_| Rc::new(RefCell::new(StrategyStats::default())));
     for i in 0..times {
         println!("game: {i}");
-        let mut config = config();
+        let game = u64::from(i);
+        let [p1, p2] = config([sub_seed(seed, 2 * game), sub_seed(seed, 2 * game + 1)]);
+        let mut config: [Box<dyn GamePlayer<G>>; 2] = [
+            Box::new(MeasuredPlayer::new(p1, stats[0].clone())),
+            Box::new(MeasuredPlayer::new(p2, stats[1].clone())),
+        ];
+        let [p1_name, p2_name] = names.get_or_insert_with(|| [config[0].name(), config[1].name()]);
         let swap = i % 2 != 0;
-        (p1_win_ref, p2_win_ref) = (p2_win_ref, p1_win_ref);
         if swap {
             config.swap(0, 1);
         }
-        let (winner, game) = run_game(config, true);
-        match winner {
-            Winner::WIN => {
-                let player = game.player();
-                match player {
-                    TwoPlayer::P1 => *p1_win_ref += 1,
-                    TwoPlayer::P2 => *p2_win_ref += 1,
-                }
-            }
-            Winner::TIE => tie += 1,
+        if swap {
+            println!("{p2_name} vs {p1_name}");
+        } else {
+            println!("{p1_name} vs {p2_name}");
         }
+        let (winner, _, last_mover) = run_game(config, true, GameLimits::default());
+        // the first player plays P2 in swapped games
+        let perspective = if swap { TwoPlayer::P2 } else { TwoPlayer::P1 };
+        tally.add(outcome_for(winner, last_mover, perspective));
     }
-    assert!(p1_win <= times);
-    assert!(p2_win <= times);
+    assert_eq!(times, tally.games());
     let times = f64::from(times);
-    println!("p1_rate: {}, p2_rate: {}, tie_rate: {}", f64::from(p1_win) / times, f64::from(p2_win) / times, f64::from(tie) / times);
+    let [p1_name, p2_name] = names.unwrap_or_default();
+    println!("{p1_name}: {}, {p2_name}: {}, tie_rate: {}", f64::from(tally.wins) / times, f64::from(tally.losses) / times, f64::from(tally.ties) / times);
+    let (low, high) = tally.wilson_interval(0.05);
+    println!("{p1_name} score rate: {:.3}, 95% interval: [{low:.3}, {high:.3}]", tally.score_rate());
+    match is_significantly_better(&tally, 0.05) {
+        Some(TwoPlayer::P1) => println!("{p1_name} is significantly better"),
+        Some(TwoPlayer::P2) => println!("{p2_name} is significantly better"),
+        None => println!("no significant difference"),
+    }
+    let stats = stats.map(|stats| *stats.borrow());
+    for (name, stats) in [p1_name, p2_name].iter().zip(&stats) {
+        println!(
+            "{name}: {:.1} plies per game, {:.1} moves to choose from, {} nodes in {:.2}s ({:.0} nodes/s)",
+            stats.mean_game_length(), stats.mean_branching(), stats.nodes, stats.think_time.as_secs_f64(), stats.nodes_per_second(),
+        );
+    }
+    (tally, stats)
+}
+
+// plays the strategy against itself, config is called with a distinct number for every seat of every game,
+// so seeds can be derived from it. Returns the score rate of the starting player (ties count half) minus 0.5
+fn measure_first_player_advantage<G: MonteCarloGame + 'static, S: GameStrategy<G> + 'static>(mut config: impl FnMut(u32) -> S::Config, games: u32) -> f64 where S::Carry: 'static, G::MOVE: Display {
+    let mut p1_score = 0.0;
+    for i in 0..games {
+        let mut players: [Box<dyn GamePlayer<G>>; 2] = [
+            Box::new(S::strategy_of(config(2 * i))),
+            Box::new(S::strategy_of(config(2 * i + 1))),
+        ];
+        if i % 2 != 0 {
+            players.swap(0, 1);
+        }
+        let (winner, _, last_mover) = run_game(players, false, GameLimits::default());
+        p1_score += outcome_for(winner, last_mover, TwoPlayer::P1).to_points();
+    }
+    p1_score / f64::from(games) - 0.5
+}
+
+// the line the strategy expects, by letting it play both sides from start for at most max_plies moves
+fn analyze_pv<G: MonteCarloGame, S: GameStrategy<G>>(start: &G, strategy: &S, max_plies: usize) -> Vec<G::MOVE> {
+    let mut game = start.clone();
+    let mut carry = None;
+    let mut line = Vec::new();
+    while line.len() < max_plies && game.moves().into_iter().next().is_some() {
+        let (m, next_carry) = strategy.make_move(&game, carry);
+        let (next, winner) = game.make_move(&m).expect("could not make move");
+        line.push(m);
+        if winner.is_some() {
+            break;
+        }
+        game = next;
+        carry = Some((m, next_carry));
+    }
+    line
+}
+
+fn print_pv<G: MonteCarloGame, S: GameStrategy<G>>(start: &G, strategy: &S, max_plies: usize) where G::MOVE: Display {
+    let line = analyze_pv(start, strategy, max_plies);
+    let moves = line.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+    println!("pv of {}: {}", short_type_name::<S>(), moves.join(", "));
 }
 
-fn run_game<G: MonteCarloGame + 'static>(mut config: [Box<dyn GamePlayer<G>>; 2], should_print: bool) -> (Winner, G) {
+// games running into one of the limits are declared a tie
+#[derive(Copy, Clone, Default)]
+struct GameLimits {
+    max_plies: Option<u32>,
+}
+
+fn run_game<G: MonteCarloGame + 'static>(config: [Box<dyn GamePlayer<G>>; 2], should_print: bool, limits: GameLimits) -> (Winner, G, TwoPlayer) where G::MOVE: Display {
+    let players = config.map(|player| Box::new(Unshared(player)) as Box<dyn SharedGamePlayer<G, ()>>);
+    run_shared_game(players, &mut (), should_print, limits)
+}
+
+// like run_game, both players get access to shared on every call. Every printed board is preceded by the
+// move that led to it. Returns the result, the final state and the player who moved last, who is the one
+// that won on WIN
+fn run_shared_game<G: MonteCarloGame + 'static, S>(mut config: [Box<dyn SharedGamePlayer<G, S> + '_>; 2], shared: &mut S, should_print: bool, limits: GameLimits) -> (Winner, G, TwoPlayer) where G::MOVE: Display {
     macro_rules! cprintln {
         ($lit: literal $(, $e: expr)*) => {if should_print { println!($lit $(, $e)*) }};
     }
+    config.iter_mut().for_each(|player| player.new_game(shared));
     let mut game = G::new();
     cprintln!("{game:?}");
     let mut last_move = None;
-    loop {
-        let config = match game.player() {
+    let mut plies = 0u32;
+    let mut history = Vec::new();
+    if G::TRACKS_REPETITION {
+        history.push(game.clone());
+    }
+    let mut last_mover = game.player().next();
+    let winner = loop {
+        if game.moves().into_iter().next().is_none() {
+            let winner = game.no_moves_result().expect("no moves left in an unfinished game");
+            cprintln!("no moves left, {winner:?}");
+            break winner;
+        }
+        let mover = game.player();
+        let player = match mover {
             TwoPlayer::P1 => &mut config[0],
             TwoPlayer::P2 => &mut config[1],
         };
-        let m = config.make_move(&game, last_move);
+        // the pick the other player just made is only revealed with this one
+        let enemy_move = if game.is_simultaneous() { None } else { last_move };
+        let m = player.make_move(&game, enemy_move, shared);
         let (new_game, winner) = game.make_move(&m)
-            .expect("could not make move");
+            .unwrap_or_else(|err| panic!("could not make move {m} of {mover:?}: {err:?}"));
         game = new_game;
         last_move = Some(m);
+        last_mover = mover;
+        cprintln!("ply {}: {mover:?} plays {m}", plies + 1);
         cprintln!("{game:?}");
         if let Some(winner) = winner {
             match winner {
-                Winner::WIN => cprintln!("{:?} has won", game.player()),
+                Winner::WIN => cprintln!("{:?} has won", last_mover),
                 Winner::TIE => cprintln!("TIE!")
             }
-            break (winner, game);
+            break winner;
+        }
+        plies += 1;
+        if G::TRACKS_REPETITION {
+            history.push(game.clone());
+        }
+        if G::TRACKS_REPETITION && G::is_drawn_by_repetition(&history) {
+            cprintln!("drawn by repetition, TIE!");
+            break Winner::TIE;
+        }
+        if limits.max_plies.is_some_and(|max_plies| plies >= max_plies) {
+            cprintln!("ply limit reached, TIE!");
+            break Winner::TIE;
+        }
+    };
+    // games ended by a limit are finished like any other
+    config.iter_mut().for_each(|player| player.finish(winner, last_mover, &game, shared));
+    (winner, game, last_mover)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::ai_infra::{sub_seed, GamePlayer, GameStrategy};
+    use crate::line_four_8x8::LineFour8x8;
+    use crate::monte_carlo_game::conformance::HighCard;
+    use crate::monte_carlo_game::{MonteCarloGame, Winner};
+    use crate::old_monte_carlo::monte_carlo_main8::{MctsV8Builder, MctsV8Reducer, MonteCarloStrategyV8};
+    use crate::simultaneous_game::Simultaneous;
+    use crate::alpha_beta_ai::AlphaBetaStrategy;
+    use crate::tic_tac_toe::TicTacToe;
+    use crate::tic_tac_toe::TicTacToeMove::*;
+    use crate::{analyze_pv, measure_first_player_advantage, run_game, run_games, GameLimits};
+
+    #[test]
+    fn same_seed_same_match() {
+        let play = || run_games::<LineFour8x8, _>(4, 42, |seeds| {
+            seeds.map(|seed| Box::new(MonteCarloStrategyV8::strategy_of(MctsV8Builder::new().playoffs(50).seed(seed).build())) as Box<dyn GamePlayer<_>>)
+        });
+        let (tally, stats) = play();
+        assert_eq!(4, tally.games());
+        let (again, again_stats) = play();
+        assert_eq!(tally, again);
+        assert_eq!(stats.map(|stats| (stats.plies, stats.nodes)), again_stats.map(|stats| (stats.plies, stats.nodes)));
+    }
+
+    // shows 3 and keeps the moves of the other player it was told about
+    struct ShowsThree(Rc<RefCell<Vec<Option<u8>>>>);
+
+    impl GamePlayer<Simultaneous<HighCard>> for ShowsThree {
+        fn make_move(&mut self, _game: &Simultaneous<HighCard>, enemy_move: Option<u8>) -> u8 {
+            self.0.borrow_mut().push(enemy_move);
+            3
         }
     }
+
+    #[test]
+    fn simultaneous_picks_are_not_passed_on() {
+        let told = [(); 2].map(|_| Rc::new(RefCell::new(Vec::new())));
+        let players = told.clone().map(|told| Box::new(ShowsThree(told)) as Box<dyn GamePlayer<_>>);
+        let (winner, _, _) = run_game(players, false, GameLimits::default());
+        assert_eq!(Winner::TIE, winner);
+        // P1 learns the pick of P2 with the next turn, P2 never learns the one of P1 before picking
+        assert_eq!(vec![None, Some(3), Some(3)], *told[0].borrow());
+        assert_eq!(vec![None; 3], *told[1].borrow());
+    }
+
+    #[test]
+    fn pv_of_forced_win() {
+        let strategy = <AlphaBetaStrategy as GameStrategy<TicTacToe>>::new((6, 1000));
+        // x completes the top row and the line ends with the win
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5]).unwrap();
+        assert_eq!(vec![I3], analyze_pv(&game, &strategy, 9));
+        // o has to block the top row, x answers by blocking the diagonal of o, threatening I8 and I9 at once
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I7, I6]).unwrap();
+        assert_eq!(vec![I3, I5], analyze_pv(&game, &strategy, 2));
+        let line = analyze_pv(&game, &strategy, 9);
+        assert_eq!(4, line.len());
+        assert_eq!(vec![I3, I5], line[..2]);
+        let (_, winner) = game.apply_moves(&line).unwrap();
+        assert_eq!(Some(Winner::WIN), winner);
+    }
+
+    #[test]
+    fn first_player_advantage_is_repeatable() {
+        let measure = || measure_first_player_advantage::<TicTacToe, MonteCarloStrategyV8<_, MctsV8Reducer>>(|seat| {
+            MctsV8Builder::new().playoffs(200).seed(sub_seed(3, u64::from(seat))).build()
+        }, 20);
+        let advantage = measure();
+        assert_eq!(advantage, measure());
+        // the starting player scores 12 of 20 points
+        assert!((advantage - 0.1).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,110 @@
+use std::time::Duration;
+use crate::monte_carlo_game::{Score, TwoPlayer};
+
+// results of a match from the view of the first player, P1 stands for the first player and P2 for the
+// second one, whichever seat they played in a single game
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MatchTally {
+    pub wins: u32,
+    pub losses: u32,
+    pub ties: u32,
+}
+
+impl MatchTally {
+    pub fn add(&mut self, score: Score) {
+        match score {
+            Score::Win => self.wins += 1,
+            Score::Loss => self.losses += 1,
+            Score::Draw => self.ties += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.ties
+    }
+
+    // ties count half
+    pub fn score_rate(&self) -> f64 {
+        (f64::from(self.wins) + 0.5 * f64::from(self.ties)) / f64::from(self.games())
+    }
+
+    // Wilson score interval of the score rate with confidence 1 - alpha, a tie is taken as half a win.
+    // (0, 1) without games
+    pub fn wilson_interval(&self, alpha: f64) -> (f64, f64) {
+        if self.games() == 0 {
+            return (0.0, 1.0);
+        }
+        let n = f64::from(self.games());
+        let p = self.score_rate();
+        let z = normal_quantile(1.0 - alpha / 2.0);
+        let z2 = z * z;
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let spread = z / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+        ((center - spread).max(0.0), (center + spread).min(1.0))
+    }
+}
+
+// effort of one strategy summed over the games of a match, see MeasuredPlayer
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct StrategyStats {
+    pub games: u32,
+    pub moves: u32,
+    // length of the games, counting the moves of both players
+    pub plies: u32,
+    // as reported by the strategy, strategies that do not count add nothing
+    pub nodes: u64,
+    pub think_time: Duration,
+    // number of legal moves summed over the positions the strategy moved in
+    pub branching: u64,
+}
+
+impl StrategyStats {
+    pub fn mean_branching(&self) -> f64 {
+        self.branching as f64 / f64::from(self.moves.max(1))
+    }
+
+    pub fn mean_game_length(&self) -> f64 {
+        f64::from(self.plies) / f64::from(self.games.max(1))
+    }
+
+    pub fn nodes_per_second(&self) -> f64 {
+        self.nodes as f64 / self.think_time.as_secs_f64().max(1e-9)
+    }
+}
+
+// the player whose score rate is above one half with confidence 1 - alpha, None if the match cannot tell
+pub fn is_significantly_better(results: &MatchTally, alpha: f64) -> Option<TwoPlayer> {
+    let (low, high) = results.wilson_interval(alpha);
+    if low > 0.5 {
+        Some(TwoPlayer::P1)
+    } else if high < 0.5 {
+        Some(TwoPlayer::P2)
+    } else {
+        None
+    }
+}
+
+// inverse of the standard normal cdf for 0.5 <= p < 1, Abramowitz and Stegun 26.2.23 with an error below 4.5e-4
+fn normal_quantile(p: f64) -> f64 {
+    debug_assert!((0.5..1.0).contains(&p), "quantile {p} is not supported");
+    let t = (-2.0 * (1.0 - p).ln()).sqrt();
+    t - (2.515517 + 0.802853 * t + 0.010328 * t * t) / (1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::match_stats::{is_significantly_better, normal_quantile, MatchTally};
+    use crate::monte_carlo_game::TwoPlayer;
+
+    #[test]
+    fn wilson_interval() {
+        assert!((normal_quantile(0.975) - 1.96).abs() < 1e-3);
+        let (low, high) = MatchTally { wins: 8, losses: 7, ties: 0 }.wilson_interval(0.05);
+        // 8 of 15 is about 0.533, the usual 95% interval is 0.30 to 0.75
+        assert!((low - 0.301).abs() < 1e-2 && (high - 0.752).abs() < 1e-2, "{low} {high}");
+        assert_eq!(None, is_significantly_better(&MatchTally { wins: 8, losses: 7, ties: 0 }, 0.05));
+        assert_eq!(Some(TwoPlayer::P1), is_significantly_better(&MatchTally { wins: 70, losses: 30, ties: 0 }, 0.05));
+        assert_eq!(Some(TwoPlayer::P2), is_significantly_better(&MatchTally { wins: 20, losses: 70, ties: 10 }, 0.05));
+        assert_eq!((0.0, 1.0), MatchTally::default().wilson_interval(0.05));
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt::{Debug, Formatter};
+use rand::Rng;
+use crate::monte_carlo_game::{MonteCarloGame, MoveError, TwoPlayer, Winner};
+use crate::multi_score_reducer::CheckWinMonteCarloGame;
+
+// the wrapped game with the seats swapped, the player that starts is reported as P2. A WIN always
+// belongs to the player who moved last, so results need no translation, only the seat of a player does
+#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+pub struct Mirrored<G>(pub G);
+
+impl <G: MonteCarloGame> MonteCarloGame for Mirrored<G> {
+    type MOVE = G::MOVE;
+    type MOVES<'s> = G::MOVES<'s> where Self: 's;
+
+    fn new() -> Self {
+        Mirrored(G::new())
+    }
+
+    fn moves(&self) -> Self::MOVES<'_> {
+        self.0.moves()
+    }
+
+    fn ordered_moves(&self) -> impl Iterator<Item = Self::MOVE> + '_ {
+        self.0.ordered_moves()
+    }
+
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError> {
+        self.0.make_move(m).map(|(next, winner)| (Mirrored(next), winner))
+    }
+
+    fn player(&self) -> TwoPlayer {
+        self.0.player().next()
+    }
+
+    fn ply_count(&self) -> usize {
+        self.0.ply_count()
+    }
+
+    fn state_space_hint(&self) -> Option<usize> {
+        self.0.state_space_hint()
+    }
+
+    fn state_key(&self) -> Vec<u8> {
+        self.0.state_key()
+    }
+
+    fn random_move(&self, rng: &mut impl Rng) -> Option<Self::MOVE> {
+        self.0.random_move(rng)
+    }
+
+    fn no_moves_result(&self) -> Option<Winner> {
+        self.0.no_moves_result()
+    }
+
+    fn is_drawn_by_repetition(history: &[Self]) -> bool {
+        let history = history.iter().map(|state| state.0.clone()).collect::<Vec<_>>();
+        G::is_drawn_by_repetition(&history)
+    }
+
+    const TRACKS_REPETITION: bool = G::TRACKS_REPETITION;
+}
+
+impl <G: CheckWinMonteCarloGame> CheckWinMonteCarloGame for Mirrored<G> {
+    fn win_state(&self) -> Option<Winner> {
+        self.0.win_state()
+    }
+}
+
+// shows the board of the wrapped game, the seats only differ in player()
+impl <G: Debug> Debug for Mirrored<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::GameStrategy;
+    use crate::mirrored_game::Mirrored;
+    use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer};
+    use crate::monte_carlo_game::conformance::self_play;
+    use crate::old_monte_carlo::monte_carlo_main8::{MctsV8Builder, MonteCarloStrategyV8};
+    use crate::tic_tac_toe::TicTacToe;
+
+    #[test]
+    fn symmetric_strategy_plays_both_seats_alike() {
+        assert_eq!(TwoPlayer::P2, Mirrored::<TicTacToe>::new().player());
+        let config = || MctsV8Builder::new().playoffs(300).seed([3; 32]).build();
+        assert_eq!(self_play::<TicTacToe, _>(&MonteCarloStrategyV8::new(config())), self_play::<Mirrored<TicTacToe>, _>(&MonteCarloStrategyV8::new(config())));
+    }
+}
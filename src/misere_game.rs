@@ -0,0 +1,176 @@
+use std::fmt::{Debug, Formatter};
+use rand::Rng;
+use crate::monte_carlo_game::{MonteCarloGame, MoveError, TwoPlayer, Winner};
+
+// the wrapped game where the player who would win loses instead. A WIN always belongs to the player who
+// moved last, so the loss of the mover cannot be reported by the move itself: the opponent gets Claim
+// as only move, which wins the game for them
+#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+pub struct Misere<G> {
+    game: G,
+    // the player the wrapped game was lost to, they have to claim it
+    claimer: Option<TwoPlayer>,
+    // the game is over once the claim is made
+    claimed: bool,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum MisereMove<M> {
+    Play(M),
+    Claim,
+}
+
+impl <G: MonteCarloGame> Misere<G> {
+    // running out of moves can lose the wrapped game as well, for the player to move
+    fn can_claim(&self) -> bool {
+        !self.claimed && (self.claimer.is_some() || (self.game.moves().into_iter().next().is_none() && self.game.no_moves_result() == Some(Winner::WIN)))
+    }
+}
+
+impl <G: MonteCarloGame> MonteCarloGame for Misere<G> {
+    type MOVE = MisereMove<G::MOVE>;
+    type MOVES<'s> = Vec<Self::MOVE> where Self: 's;
+
+    fn new() -> Self {
+        Misere { game: G::new(), claimer: None, claimed: false }
+    }
+
+    fn moves(&self) -> Self::MOVES<'_> {
+        if self.claimed {
+            vec![]
+        } else if self.can_claim() {
+            vec![MisereMove::Claim]
+        } else {
+            self.game.moves().into_iter().map(MisereMove::Play).collect()
+        }
+    }
+
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError> {
+        if self.claimed {
+            return Err(MoveError::GameOver);
+        }
+        match (m, self.can_claim()) {
+            // the claimer stays the player, like the winner of the wrapped games
+            (MisereMove::Claim, true) => Ok((Misere { claimer: Some(self.player()), claimed: true, ..self.clone() }, Some(Winner::WIN))),
+            (MisereMove::Play(m), false) => {
+                let (game, winner) = self.game.make_move(m)?;
+                Ok(match winner {
+                    Some(Winner::WIN) => (Misere { game, claimer: Some(self.player().next()), claimed: false }, None),
+                    winner => (Misere { game, claimer: None, claimed: false }, winner),
+                })
+            }
+            _ => Err(MoveError::Illegal),
+        }
+    }
+
+    fn player(&self) -> TwoPlayer {
+        self.claimer.unwrap_or_else(|| self.game.player())
+    }
+
+    fn ply_count(&self) -> usize {
+        self.game.ply_count() + usize::from(self.claimed)
+    }
+
+    fn random_move(&self, rng: &mut impl Rng) -> Option<Self::MOVE> {
+        if self.claimed {
+            None
+        } else if self.can_claim() {
+            Some(MisereMove::Claim)
+        } else {
+            self.game.random_move(rng).map(MisereMove::Play)
+        }
+    }
+
+    // the won states of the wrapped game exist twice, before and after the claim
+    fn state_space_hint(&self) -> Option<usize> {
+        self.game.state_space_hint().map(|states| states.saturating_mul(2))
+    }
+
+    // the key of the wrapped game and a byte for the claimer, 0 if there is none and 2 more once claimed
+    fn state_key(&self) -> Vec<u8> {
+        let mut key = self.game.state_key();
+        key.push(self.claimer.map_or(0, |claimer| claimer.as_bit() as u8 + 1) + 2 * u8::from(self.claimed));
+        key
+    }
+
+    fn no_moves_result(&self) -> Option<Winner> {
+        if self.claimed {
+            return Some(Winner::WIN);
+        }
+        // a WIN of the wrapped game is claimed instead
+        self.game.no_moves_result().filter(|winner| *winner == Winner::TIE)
+    }
+
+    fn is_drawn_by_repetition(history: &[Self]) -> bool {
+        let history = history.iter().map(|state| state.game.clone()).collect::<Vec<_>>();
+        G::is_drawn_by_repetition(&history)
+    }
+
+    const TRACKS_REPETITION: bool = G::TRACKS_REPETITION;
+}
+
+// shows the board of the wrapped game and who can claim it
+impl <G: Debug> Debug for Misere<G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.game.fmt(f)?;
+        match (self.claimer, self.claimed) {
+            (Some(claimer), false) => writeln!(f, "lost to {claimer:?}"),
+            (Some(claimer), true) => writeln!(f, "claimed by {claimer:?}"),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::GameStrategy;
+    use crate::alpha_beta_ai::AlphaBetaStrategy;
+    use crate::misere_game::{Misere, MisereMove};
+    use crate::monte_carlo_game::{MonteCarloGame, MoveError, TwoPlayer, Winner};
+    use crate::monte_carlo_game::conformance::self_play;
+    use crate::solver::{solve_to_depth, Outcome};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+
+    type MisereTicTacToe = Misere<TicTacToe>;
+
+    #[test]
+    fn completing_a_line_loses() {
+        let moves = [I1, I4, I2, I5, I3].map(MisereMove::Play);
+        let (lost, winner) = MisereTicTacToe::new().apply_moves(&moves).unwrap();
+        assert_eq!(None, winner);
+        assert_eq!(TwoPlayer::P2, lost.player());
+        assert_eq!(vec![MisereMove::Claim], lost.moves());
+        let (claimed, winner) = lost.make_move(&MisereMove::Claim).unwrap();
+        assert_eq!(Some(Winner::WIN), winner);
+        // the claim ends the game
+        assert_eq!(TwoPlayer::P2, claimed.player());
+        assert!(claimed.moves().is_empty());
+        assert_eq!(Err(MoveError::GameOver), claimed.make_move(&MisereMove::Claim).map(|_| ()));
+        assert_eq!(6, claimed.ply_count());
+    }
+
+    // misère tic tac toe is a draw, the first player keeps it by taking the center and answering every
+    // move with the point-symmetric one
+    #[test]
+    fn alpha_beta_plays_misere_optimally() {
+        let solved = solve_to_depth(&MisereTicTacToe::new(), 10);
+        assert_eq!(Some(&Outcome::Draw), solved.get(&MisereTicTacToe::new()));
+        let strategy = <AlphaBetaStrategy as GameStrategy<MisereTicTacToe>>::new((10, 100_000));
+        let mut game = MisereTicTacToe::new();
+        let mut winner = None;
+        for m in self_play::<MisereTicTacToe, _>(&strategy) {
+            // every move keeps at least the value of the position for the mover
+            let (next, result) = game.make_move(&m).unwrap();
+            winner = result;
+            let expected = solved.get(&game).copied().unwrap();
+            let reached = match winner {
+                Some(Winner::WIN) => Outcome::Win,
+                Some(Winner::TIE) => Outcome::Draw,
+                None => solved.get(&next).copied().unwrap().flip(),
+            };
+            assert_eq!(expected, reached, "{m:?} in {game:?}");
+            game = next;
+        }
+        assert_eq!(Some(Winner::TIE), winner);
+    }
+}
@@ -0,0 +1,322 @@
+use rand::Rng;
+use crate::grid_game::{fmt_grid, GridGame};
+use crate::monte_carlo_game::{MonteCarloGame, MoveError, MoveIndex, TwoPlayer, Winner};
+use crate::multi_score_reducer::CheckWinMonteCarloGame;
+
+const WORDS: usize = 4;
+
+// a bit per cell of the board, enough for gomoku on 15x15
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default)]
+struct Bits([u64; WORDS]);
+
+impl Bits {
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn and(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|w| self.0[w] & other.0[w]))
+    }
+
+    fn or(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|w| self.0[w] | other.0[w]))
+    }
+
+    fn and_not(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|w| self.0[w] & !other.0[w]))
+    }
+
+    // moves every bit to the index n lower
+    fn shr(self, n: usize) -> Bits {
+        let (words, bits) = (n / 64, n % 64);
+        Bits(std::array::from_fn(|w| {
+            let low = self.0.get(w + words).copied().unwrap_or(0);
+            let high = self.0.get(w + words + 1).copied().unwrap_or(0);
+            if bits == 0 { low } else { low >> bits | high << (64 - bits) }
+        }))
+    }
+
+    fn count_ones(&self) -> u32 {
+        self.0.iter().map(|w| w.count_ones()).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|w| *w == 0)
+    }
+
+    // index of the lowest set bit, which is cleared
+    fn pop_lowest(&mut self) -> Option<usize> {
+        let w = self.0.iter().position(|w| *w != 0)?;
+        let bit = self.0[w].trailing_zeros() as usize;
+        self.0[w] &= self.0[w] - 1;
+        Some(w * 64 + bit)
+    }
+}
+
+// the m,n,k-game: M rows and N columns, whoever gets K in a row horizontally, vertically or diagonally
+// first wins. TicTacToe is MNKGame<3, 3, 3>, gomoku MNKGame<15, 15, 5>
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct MNKGame<const M: usize, const N: usize, const K: usize> {
+    // stones of P1 and P2, the cell in row r and column c is bit r * (N + 1) + c. The column N stays empty
+    // so lines cannot continue into the next row
+    stones: [Bits; 2],
+    // like for TicTacToe, the winner stays the player once the game is over
+    player: TwoPlayer,
+}
+
+// row * N + column, counted from the top left
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MNKMove<const N: usize>(u16);
+
+pub struct MNKMoves<const N: usize> {
+    empty: Bits,
+}
+
+impl <const M: usize, const N: usize, const K: usize> MNKGame<M, N, K> {
+    const WIDTH: usize = N + 1;
+    const FITS: () = assert!(M * (N + 1) <= WORDS * 64 && K >= 1 && (K <= M || K <= N), "board does not fit or cannot be won");
+
+    fn board_mask() -> Bits {
+        let mut mask = Bits::default();
+        for row in 0..M {
+            for column in 0..N {
+                mask.set(row * Self::WIDTH + column);
+            }
+        }
+        mask
+    }
+
+    fn stones_of(&self, player: TwoPlayer) -> Bits {
+        self.stones[player.as_bit() as usize]
+    }
+
+    fn empty(&self) -> Bits {
+        Self::board_mask().and_not(self.stones[0].or(self.stones[1]))
+    }
+
+    // the empty cells, none once the game is over
+    fn open(&self) -> Bits {
+        if Self::has_line(self.stones_of(self.player)) {
+            Bits::default()
+        } else {
+            self.empty()
+        }
+    }
+
+    fn has_line(stones: Bits) -> bool {
+        [1, Self::WIDTH, Self::WIDTH + 1, Self::WIDTH - 1].into_iter().any(|direction| {
+            let mut run = stones;
+            for _ in 1..K {
+                run = run.and(run.shr(direction));
+            }
+            !run.is_empty()
+        })
+    }
+
+    fn to_move(bit: usize) -> MNKMove<N> {
+        MNKMove((bit / Self::WIDTH * N + bit % Self::WIDTH) as u16)
+    }
+}
+
+impl <const M: usize, const N: usize, const K: usize> MonteCarloGame for MNKGame<M, N, K> {
+    type MOVE = MNKMove<N>;
+    type MOVES<'s> = MNKMoves<N> where Self: 's;
+
+    fn new() -> Self {
+        let () = Self::FITS;
+        Self { stones: [Bits::default(); 2], player: TwoPlayer::P1 }
+    }
+
+    fn moves(&self) -> Self::MOVES<'_> {
+        MNKMoves { empty: self.open() }
+    }
+
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError> {
+        if self.win_state().is_some() {
+            return Err(MoveError::GameOver);
+        }
+        let index = usize::from(m.0);
+        if index >= M * N {
+            return Err(MoveError::Illegal);
+        }
+        let bit = index / N * Self::WIDTH + index % N;
+        if !self.empty().get(bit) {
+            return Err(MoveError::Illegal);
+        }
+        let mut next = *self;
+        next.stones[self.player.as_bit() as usize].set(bit);
+        let winner = if Self::has_line(next.stones_of(self.player)) {
+            Some(Winner::WIN)
+        } else if next.empty().is_empty() {
+            Some(Winner::TIE)
+        } else {
+            next.player = self.player.next();
+            None
+        };
+        Ok((next, winner))
+    }
+
+    fn player(&self) -> TwoPlayer {
+        self.player
+    }
+
+    fn no_moves_result(&self) -> Option<Winner> {
+        self.win_state()
+    }
+
+    fn ply_count(&self) -> usize {
+        (self.stones[0].count_ones() + self.stones[1].count_ones()) as usize
+    }
+
+    // the words of both players in little endian and the player at turn
+    fn state_key(&self) -> Vec<u8> {
+        let mut key = self.stones.iter().flat_map(|stones| stones.0).flat_map(u64::to_le_bytes).collect::<Vec<_>>();
+        key.push(self.player.as_bit() as u8);
+        key
+    }
+
+    fn random_move(&self, rng: &mut impl Rng) -> Option<Self::MOVE> {
+        let mut empty = self.open();
+        let count = empty.count_ones();
+        if count == 0 {
+            return None;
+        }
+        for _ in 0..rng.gen_range(0..count) {
+            empty.pop_lowest();
+        }
+        empty.pop_lowest().map(Self::to_move)
+    }
+}
+
+impl <const M: usize, const N: usize, const K: usize> CheckWinMonteCarloGame for MNKGame<M, N, K> {
+    fn win_state(&self) -> Option<Winner> {
+        if Self::has_line(self.stones_of(self.player)) {
+            Some(Winner::WIN)
+        } else if self.empty().is_empty() {
+            Some(Winner::TIE)
+        } else {
+            None
+        }
+    }
+}
+
+impl <const M: usize, const N: usize, const K: usize> GridGame for MNKGame<M, N, K> {
+    fn dimensions(&self) -> (usize, usize) {
+        (M, N)
+    }
+
+    fn cell(&self, row: usize, column: usize) -> char {
+        let bit = row * Self::WIDTH + column;
+        if self.stones_of(TwoPlayer::P1).get(bit) {
+            'x'
+        } else if self.stones_of(TwoPlayer::P2).get(bit) {
+            'o'
+        } else {
+            '.'
+        }
+    }
+}
+
+impl <const M: usize, const N: usize, const K: usize> std::fmt::Debug for MNKGame<M, N, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_grid(self, f)
+    }
+}
+
+impl <const N: usize> Iterator for MNKMoves<N> {
+    type Item = MNKMove<N>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bit = self.empty.pop_lowest()?;
+        Some(MNKMove((bit / (N + 1) * N + bit % (N + 1)) as u16))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let num = self.empty.count_ones() as usize;
+        (num, Some(num))
+    }
+}
+
+// only the range of the index is checked, whether it is on the board is up to make_move
+impl <const N: usize> TryFrom<u32> for MNKMove<N> {
+    type Error = ();
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        u16::try_from(value).map(MNKMove).map_err(|_| ())
+    }
+}
+
+impl <const N: usize> MoveIndex for MNKMove<N> {
+    fn to_index(&self) -> u32 {
+        u32::from(self.0)
+    }
+}
+
+// column letter and row from the top, like TicTacToeMove
+impl <const N: usize> std::fmt::Display for MNKMove<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (row, column) = (usize::from(self.0) / N, usize::from(self.0) % N);
+        match u8::try_from(column).ok().filter(|column| *column < 26) {
+            Some(column) => write!(f, "{}{}", char::from(b'a' + column), row + 1),
+            None => write!(f, "{}:{}", column + 1, row + 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use crate::mnk_game::{MNKGame, MNKMove};
+    use crate::monte_carlo_game::{MonteCarloGame, MoveIndex, Winner};
+    use crate::multi_score_reducer::CheckWinMonteCarloGame;
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove};
+
+    #[test]
+    fn three_by_three_is_tic_tac_toe() {
+        let mut rng = SmallRng::seed_from_u64(17);
+        for _ in 0..200 {
+            let mut expected = TicTacToe::new();
+            let mut game = MNKGame::<3, 3, 3>::new();
+            loop {
+                assert_eq!(expected.moves().map(|m| m.to_index()).collect::<Vec<_>>(), game.moves().map(|m| m.to_index()).collect::<Vec<_>>());
+                let m = expected.random_move(&mut rng).unwrap();
+                let (next_expected, expected_winner) = expected.make_move(&m).unwrap();
+                let (next, winner) = game.make_move(&MNKMove::try_from(m.to_index()).unwrap()).unwrap();
+                assert_eq!(expected_winner, winner, "{next:?}");
+                assert_eq!(next_expected.player(), next.player());
+                assert_eq!(next_expected.win_state(), next.win_state());
+                assert_eq!(next_expected.ply_count(), next.ply_count());
+                assert_eq!(format!("{next_expected:?}"), format!("{next:?}"));
+                assert_eq!(TicTacToeMove::try_from(m.to_index()).unwrap().to_string(), MNKMove::<3>::try_from(m.to_index()).unwrap().to_string());
+                if winner.is_some() {
+                    break;
+                }
+                (expected, game) = (next_expected, next);
+            }
+        }
+    }
+
+    #[test]
+    fn gomoku_lines() {
+        type Gomoku = MNKGame<15, 15, 5>;
+        let play = |moves: &[(u16, u16)]| {
+            let moves = moves.iter().map(|(row, column)| MNKMove(row * 15 + column)).collect::<Vec<_>>();
+            Gomoku::new().apply_moves(&moves).unwrap()
+        };
+        // the diagonal crosses from the first into the second word of the board
+        let (_, winner) = play(&[(0, 10), (14, 0), (1, 9), (14, 1), (2, 8), (14, 2), (3, 7), (14, 3), (4, 6)]);
+        assert_eq!(Some(Winner::WIN), winner);
+        // four at the end of one row and one at the start of the next are no line
+        let (_, winner) = play(&[(6, 11), (0, 0), (6, 12), (0, 2), (6, 13), (0, 4), (6, 14), (0, 6), (7, 0)]);
+        assert_eq!(None, winner);
+        // vertical in the last column, with the last rows in the final word
+        let (game, winner) = play(&[(10, 14), (0, 0), (11, 14), (0, 2), (12, 14), (0, 4), (13, 14), (0, 6), (14, 14)]);
+        assert_eq!((Some(Winner::WIN), Some(Winner::WIN)), (winner, game.win_state()));
+        assert!(Gomoku::new().make_move(&MNKMove(225)).is_err());
+    }
+}
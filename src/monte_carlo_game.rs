@@ -1,14 +1,18 @@
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::hash::Hash;
+use rand::Rng;
+use rand::seq::IteratorRandom;
+use serde::{Deserialize, Serialize};
 use crate::monte_carlo_game_v2::{GameState, MonteCarloGameND};
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Winner {
     WIN = 0, TIE = 1
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum TwoPlayer {
     P1 = 1, P2 = 0
@@ -21,17 +25,251 @@ impl TwoPlayer {
             TwoPlayer::P2 => TwoPlayer::P1
         }
     }
+
+    // the bit a board stores for the player, P1 is 1 and P2 is 0
+    pub const fn as_bit(self) -> u64 {
+        match self {
+            TwoPlayer::P1 => 1,
+            TwoPlayer::P2 => 0,
+        }
+    }
+
+    // inverse of as_bit, only the lowest bit is looked at
+    pub const fn from_bit(bit: u64) -> TwoPlayer {
+        if bit & 1 == 1 { TwoPlayer::P1 } else { TwoPlayer::P2 }
+    }
 }
 
 
+// the result of a finished game for one of the players
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Score {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Score {
+    // tournament points, a draw is worth half a win
+    pub fn to_points(self) -> f64 {
+        match self {
+            Score::Win => 1.0,
+            Score::Loss => 0.0,
+            Score::Draw => 0.5,
+        }
+    }
+}
+
+// the score of perspective in a game that ended with winner after the move of last_mover
+pub fn outcome_for(winner: Winner, last_mover: TwoPlayer, perspective: TwoPlayer) -> Score {
+    match winner {
+        Winner::TIE => Score::Draw,
+        Winner::WIN if last_mover == perspective => Score::Win,
+        Winner::WIN => Score::Loss,
+    }
+}
+
+// why make_move refused a move
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MoveError {
+    // not a move of the player at turn in this state, like a full column
+    Illegal,
+    // the state is terminal, no move can be made from it
+    GameOver,
+}
+
 pub trait MonteCarloGame: Clone + Hash + Eq + Debug{
     type MOVE: Copy + Debug + PartialEq + Eq;
     type MOVES<'s>: IntoIterator<Item = Self::MOVE> + 's where Self: 's;
 
     fn new() -> Self;
+    // empty once the game is over, no_moves_result tells how it ended
     fn moves(&self) -> Self::MOVES<'_>;
-    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), ()>;
+    // moves in a heuristically good order, only searches that prune depend on it
+    fn ordered_moves(&self) -> impl Iterator<Item = Self::MOVE> + '_ {
+        self.moves().into_iter()
+    }
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError>;
     fn player(&self) -> TwoPlayer;
+
+    // number of moves made since new, games whose pieces can leave the board have to track it themselves
+    fn ply_count(&self) -> usize;
+
+    // number of states reachable from this one if it is small enough to be useful for presizing maps,
+    // search trees fall back to a guess from their budget with None
+    fn state_space_hint(&self) -> Option<usize> {
+        None
+    }
+
+    // the position as bytes that stay the same across runs and machines, unlike Hash. Equal keys mean
+    // equal states, so it can key opening books on disk or be sent over the network
+    fn state_key(&self) -> Vec<u8>;
+
+    // every legal move with the state it leads to, in the order of moves
+    fn successors(&self) -> impl Iterator<Item = (Self::MOVE, Self, Option<Winner>)> + '_ {
+        self.moves().into_iter().map(|m| {
+            let (next, winner) = self.make_move(&m).expect("generated move is illegal");
+            (m, next, winner)
+        })
+    }
+
+    // every legal move with the result it ends the game with right away, None if the game goes on. Front
+    // ends can mark winning moves with it and strategies take them. Empty for a finished game
+    fn moves_with_outcome(&self) -> impl Iterator<Item = (Self::MOVE, Option<Winner>)> + '_ {
+        self.moves().into_iter().filter_map(|m| self.make_move(&m).ok().map(|(_, winner)| (m, winner)))
+    }
+
+    // uniformly chosen legal move for rollouts, bitboard games should pick from their move mask directly
+    fn random_move(&self, rng: &mut impl Rng) -> Option<Self::MOVE> {
+        self.moves().into_iter().choose(rng)
+    }
+
+    // how the game ends if the player to move has no moves, WIN is a win for the player who moved last
+    // like for make_move. Finished games have no moves and report the result make_move ended them with
+    fn no_moves_result(&self) -> Option<Winner> {
+        None
+    }
+
+    // for games that can cycle, history contains every state so far with the current state last
+    fn is_drawn_by_repetition(_history: &[Self]) -> bool {
+        false
+    }
+
+    // set by games overriding is_drawn_by_repetition, searches and runners only keep the history if it is
+    const TRACKS_REPETITION: bool = false;
+
+    // true if the player to move has to pick without knowing the move made last, like the second pick of a
+    // turn in a simultaneous game, see simultaneous_game::Simultaneous. Runners do not pass that move on,
+    // but the state still holds it, so strategies that search the state itself should not play such games
+    fn is_simultaneous(&self) -> bool {
+        false
+    }
+
+    // on failure returns the index of the first illegal move and the state before it,
+    // moves after the game has ended are illegal
+    fn apply_moves(self, ms: &[Self::MOVE]) -> Result<(Self, Option<Winner>), (usize, Self)> {
+        let mut state = (self, None);
+        for (i, m) in ms.iter().enumerate() {
+            if state.1.is_some() {
+                return Err((i, state.0));
+            }
+            state = match state.0.make_move(m) {
+                Ok(next) => next,
+                Err(_) => return Err((i, state.0)),
+            };
+        }
+        Ok(state)
+    }
+}
+
+// counts the positions reachable in exactly depth moves, finished games count as a leaf wherever they end
+pub fn perft<G: MonteCarloGame>(state: &G, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    state.successors()
+        .map(|(_, next, winner)| match winner {
+            Some(_) => 1,
+            None => perft(&next, depth - 1),
+        })
+        .sum()
+}
+
+// every distinct position a move within depth plies from the start ends the game in, with the result
+// make_move reported for it. Positions are expanded once per ply, so transpositions are not walked twice
+pub fn terminal_positions<G: MonteCarloGame>(depth: usize) -> Vec<(G, Winner)> {
+    let mut terminals = Vec::new();
+    let mut seen_terminals = HashSet::new();
+    let mut layer = HashSet::from([G::new()]);
+    for _ in 0..depth {
+        let mut next_layer = HashSet::new();
+        for state in &layer {
+            for m in state.moves() {
+                match state.make_move(&m).expect("generated move is illegal") {
+                    (next, Some(winner)) => {
+                        if seen_terminals.insert(next.clone()) {
+                            terminals.push((next, winner));
+                        }
+                    }
+                    (next, None) => {
+                        next_layer.insert(next);
+                    }
+                }
+            }
+        }
+        layer = next_layer;
+    }
+    terminals
+}
+
+// index of a uniformly chosen set bit of mask
+pub fn random_set_bit(mask: u64, rng: &mut impl Rng) -> Option<u32> {
+    if mask == 0 {
+        return None;
+    }
+    let mut mask = mask;
+    for _ in 0..rng.gen_range(0..mask.count_ones()) {
+        mask &= mask - 1;
+    }
+    Some(mask.trailing_zeros())
+}
+
+// zero based column of "col 4", "column 4" or "d", numbers are one based like the numeric input
+pub fn parse_column_notation(s: &str, columns: u32) -> Result<u32, ()> {
+    let s = s.trim().to_ascii_lowercase();
+    let column = match s.strip_prefix("column").or_else(|| s.strip_prefix("col")) {
+        Some(num) => num.trim().parse::<u32>().map_err(|_| ())?.checked_sub(1).ok_or(())?,
+        None => match s.as_bytes() {
+            [letter @ b'a'..=b'z'] => u32::from(letter - b'a'),
+            _ => return Err(()),
+        },
+    };
+    if column < columns { Ok(column) } else { Err(()) }
+}
+
+pub trait MoveIndex {
+    fn to_index(&self) -> u32;
+}
+
+// state_key of the bitboard games: both boards in little endian and the player at turn
+pub fn bitboard_key(set_by_p1: u64, set_by_p2: u64, player: TwoPlayer) -> Vec<u8> {
+    let mut key = Vec::with_capacity(17);
+    key.extend_from_slice(&set_by_p1.to_le_bytes());
+    key.extend_from_slice(&set_by_p2.to_le_bytes());
+    key.push(player.as_bit() as u8);
+    key
+}
+
+// packs the move indices back to back with `bits` bits each, the last byte is padded with zeros
+pub fn pack_move_indices<M: MoveIndex>(moves: &[M], bits: u32) -> Vec<u8> {
+    let mut packed = vec![0u8; (moves.len() * bits as usize).div_ceil(8)];
+    for (i, m) in moves.iter().enumerate() {
+        let index = m.to_index();
+        debug_assert!(index < 1 << bits, "move index {index} does not fit into {bits} bits");
+        for bit in 0..bits as usize {
+            if index & (1 << bit) != 0 {
+                let pos = i * bits as usize + bit;
+                packed[pos / 8] |= 1 << (pos % 8);
+            }
+        }
+    }
+    packed
+}
+
+// the padding cannot be told apart from moves, so the number of moves has to be stored separately
+pub fn unpack_move_indices<M: TryFrom<u32>>(packed: &[u8], bits: u32, count: usize) -> Option<Vec<M>> {
+    if packed.len() * 8 < count * bits as usize {
+        return None;
+    }
+    (0..count)
+        .map(|i| {
+            let index = (0..bits as usize)
+                .map(|bit| (i * bits as usize + bit, bit))
+                .filter(|(pos, _)| packed[pos / 8] & (1 << (pos % 8)) != 0)
+                .fold(0u32, |acc, (_, bit)| acc | 1 << bit);
+            M::try_from(index).ok()
+        })
+        .collect()
 }
 
 pub trait GameWithMoves {
@@ -43,11 +281,599 @@ pub trait GameWithMoves {
 
 impl <G: MonteCarloGame> GameWithMoves for G {
     type Move = G::MOVE;
-    type MoveErr = ();
+    type MoveErr = MoveError;
 
-    fn execute_move(&mut self, m: &Self::Move) -> Result<GameState, ()> {
+    fn execute_move(&mut self, m: &Self::Move) -> Result<GameState, MoveError> {
         let new_state = MonteCarloGameND::make_move(self, m, &())?;
         *self = new_state.0;
         Ok(new_state.1)
     }
-}
\ No newline at end of file
+}
+
+// invariants every game has to keep, test_game_conformance!(module_name, Game) generates a test per invariant
+#[cfg(test)]
+pub mod conformance {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::hash::Hasher;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use crate::ai_infra::GameStrategy;
+    use crate::monte_carlo_game::{MonteCarloGame, MoveError, MoveIndex, TwoPlayer, Winner};
+    use crate::multi_score_reducer::CheckWinMonteCarloGame;
+    use crate::simultaneous_game::{JointOutcome, SimultaneousGame};
+
+    // take one or two of the stones left, the player who cannot move loses. make_move never ends the game,
+    // only no_moves_result knows the winner, so searches that ignore it cannot tell the end from a draw
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub struct TakeAway {
+        pub left: u8,
+        plies: u8,
+    }
+
+    impl MonteCarloGame for TakeAway {
+        type MOVE = u8;
+        type MOVES<'s> = std::ops::RangeInclusive<u8>;
+
+        fn new() -> Self {
+            Self { left: 7, plies: 0 }
+        }
+
+        fn moves(&self) -> Self::MOVES<'_> {
+            1..=self.left.min(2)
+        }
+
+        fn make_move(&self, m: &u8) -> Result<(Self, Option<Winner>), MoveError> {
+            if self.left == 0 {
+                Err(MoveError::GameOver)
+            } else if self.moves().contains(m) {
+                Ok((Self { left: self.left - m, plies: self.plies + 1 }, None))
+            } else {
+                Err(MoveError::Illegal)
+            }
+        }
+
+        fn player(&self) -> TwoPlayer {
+            [TwoPlayer::P1, TwoPlayer::P2][self.plies as usize % 2]
+        }
+
+        fn ply_count(&self) -> usize {
+            self.plies as usize
+        }
+
+        fn state_key(&self) -> Vec<u8> {
+            vec![self.left, self.plies]
+        }
+
+        fn no_moves_result(&self) -> Option<Winner> {
+            (self.left == 0).then_some(Winner::WIN)
+        }
+    }
+
+    impl CheckWinMonteCarloGame for TakeAway {
+        fn win_state(&self) -> Option<Winner> {
+            None
+        }
+    }
+
+    // the only move swings the pendulum to the other side, the game is drawn once a position repeats
+    #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+    pub struct Pendulum {
+        pub left: bool,
+    }
+
+    impl MonteCarloGame for Pendulum {
+        type MOVE = ();
+        type MOVES<'s> = std::iter::Once<()>;
+
+        fn new() -> Self {
+            Self { left: true }
+        }
+
+        fn moves(&self) -> Self::MOVES<'_> {
+            std::iter::once(())
+        }
+
+        fn make_move(&self, _: &()) -> Result<(Self, Option<Winner>), MoveError> {
+            Ok((Self { left: !self.left }, None))
+        }
+
+        fn player(&self) -> TwoPlayer {
+            if self.left { TwoPlayer::P1 } else { TwoPlayer::P2 }
+        }
+
+        // the position cannot tell more than whose turn it is
+        fn ply_count(&self) -> usize {
+            usize::from(!self.left)
+        }
+
+        fn state_key(&self) -> Vec<u8> {
+            vec![u8::from(self.left)]
+        }
+
+        fn is_drawn_by_repetition(history: &[Self]) -> bool {
+            history.split_last().is_some_and(|(last, before)| before.contains(last))
+        }
+
+        const TRACKS_REPETITION: bool = true;
+    }
+
+    impl CheckWinMonteCarloGame for Pendulum {
+        fn win_state(&self) -> Option<Winner> {
+            None
+        }
+    }
+
+    // three rounds of both players showing a card from 1 to 3, the higher card wins the round and
+    // whoever wins more rounds wins the game, so showing 3 is never worse
+    #[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+    pub struct HighCard {
+        pub round: u8,
+        // rounds won by p1 minus rounds won by p2
+        pub balance: i8,
+    }
+
+    impl MoveIndex for u8 {
+        fn to_index(&self) -> u32 {
+            u32::from(*self)
+        }
+    }
+
+    impl SimultaneousGame for HighCard {
+        type MOVE = u8;
+
+        fn new() -> Self {
+            Self { round: 0, balance: 0 }
+        }
+
+        fn moves(&self, _player: TwoPlayer) -> Vec<u8> {
+            if self.round < 3 { vec![1, 2, 3] } else { vec![] }
+        }
+
+        fn make_joint_move(&self, p1_move: &u8, p2_move: &u8) -> Result<(Self, Option<JointOutcome>), ()> {
+            if self.round >= 3 || !(1..=3).contains(p1_move) || !(1..=3).contains(p2_move) {
+                return Err(());
+            }
+            let next = Self {
+                round: self.round + 1,
+                balance: self.balance + (p1_move > p2_move) as i8 - (p2_move > p1_move) as i8,
+            };
+            let outcome = (next.round == 3).then_some(match next.balance {
+                0 => JointOutcome::Tie,
+                balance if balance > 0 => JointOutcome::Won(TwoPlayer::P1),
+                _ => JointOutcome::Won(TwoPlayer::P2),
+            });
+            Ok((next, outcome))
+        }
+
+        fn state_key(&self) -> Vec<u8> {
+            vec![self.round, self.balance as u8]
+        }
+    }
+
+    // every state of some seeded random games, with the result make_move reported for it
+    pub fn random_walks<G: MonteCarloGame>() -> Vec<(G, Option<Winner>)> {
+        let mut rng = SmallRng::seed_from_u64(23);
+        let mut states = Vec::new();
+        for _ in 0..100 {
+            let mut state = (G::new(), None);
+            states.push(state.clone());
+            while state.1.is_none() {
+                let Some(m) = state.0.random_move(&mut rng) else { break };
+                state = state.0.make_move(&m).expect("random move is illegal");
+                states.push(state.clone());
+            }
+        }
+        states
+    }
+
+    pub fn new_is_open<G: MonteCarloGame>() {
+        assert!(G::new().moves().into_iter().next().is_some(), "new game has no moves");
+    }
+
+    pub fn moves_are_legal<G: MonteCarloGame>() {
+        for (state, _) in random_walks::<G>().into_iter().filter(|(_, winner)| winner.is_none()) {
+            for m in state.moves() {
+                assert!(state.make_move(&m).is_ok(), "{m:?} is listed but illegal in {state:?}");
+            }
+        }
+    }
+
+    pub fn players_alternate<G: MonteCarloGame>() {
+        for (state, _) in random_walks::<G>().into_iter().filter(|(_, winner)| winner.is_none()) {
+            for (m, next, winner) in state.successors() {
+                if winner.is_none() {
+                    assert_eq!(state.player().next(), next.player(), "{m:?} in {state:?} does not pass the turn");
+                }
+            }
+        }
+    }
+
+    pub fn finished_has_no_moves<G: MonteCarloGame>() {
+        for (state, winner) in random_walks::<G>().into_iter().filter(|(_, winner)| winner.is_some()) {
+            assert!(state.moves().into_iter().next().is_none(), "the finished {state:?} lists moves");
+            assert!(state.random_move(&mut SmallRng::seed_from_u64(0)).is_none(), "the finished {state:?} has a random move");
+            assert_eq!(winner, state.no_moves_result(), "{state:?} ended differently than make_move said");
+        }
+    }
+
+    // the moves of a new game are tried, since a finished one lists none
+    pub fn finished_refuses_moves<G: MonteCarloGame>() {
+        for (state, _) in random_walks::<G>().into_iter().filter(|(_, winner)| winner.is_some()) {
+            for m in G::new().moves() {
+                assert_eq!(Err(MoveError::GameOver), state.make_move(&m).map(|_| ()), "{m:?} is accepted in the finished {state:?}");
+            }
+        }
+    }
+
+    // a running game without moves has to say how it ended, like a finished one
+    pub fn no_moves_has_result<G: MonteCarloGame>() {
+        for (state, _) in random_walks::<G>().into_iter().filter(|(_, winner)| winner.is_none()) {
+            if state.moves().into_iter().next().is_none() {
+                assert!(state.no_moves_result().is_some(), "{state:?} has no moves and no result");
+            }
+        }
+    }
+
+    pub fn ordered_moves_complete<G: MonteCarloGame>() {
+        for (state, _) in random_walks::<G>() {
+            let moves = state.moves().into_iter().collect::<Vec<_>>();
+            let ordered = state.ordered_moves().collect::<Vec<_>>();
+            assert_eq!(moves.len(), ordered.len(), "{state:?} orders {ordered:?} but lists {moves:?}");
+            assert!(moves.iter().all(|m| ordered.contains(m)), "{state:?} orders {ordered:?} but lists {moves:?}");
+        }
+    }
+
+    // every legal move comes up and nothing else does
+    pub fn random_move_covers_moves<G: MonteCarloGame>() {
+        let mut rng = SmallRng::seed_from_u64(5);
+        for (state, _) in random_walks::<G>().into_iter().filter(|(_, winner)| winner.is_none()).step_by(10) {
+            let moves = state.moves().into_iter().collect::<Vec<_>>();
+            let mut seen = Vec::new();
+            for _ in 0..200 {
+                let Some(random) = state.random_move(&mut rng) else { break };
+                assert!(moves.contains(&random), "{random:?} is not a move of {state:?}");
+                if !seen.contains(&random) {
+                    seen.push(random);
+                }
+            }
+            assert_eq!(moves.len(), seen.len(), "random moves of {state:?} miss some of {moves:?}");
+        }
+    }
+
+    pub fn ply_count_matches_moves_made<G: MonteCarloGame>() {
+        assert_eq!(0, G::new().ply_count());
+        for (state, _) in random_walks::<G>().into_iter().filter(|(_, winner)| winner.is_none()) {
+            for (m, next, _) in state.successors() {
+                assert_eq!(state.ply_count() + 1, next.ply_count(), "{m:?} in {state:?} does not count one ply");
+            }
+        }
+    }
+
+    // the repetition rule of a game that does not set TRACKS_REPETITION is never asked
+    pub fn repetition_is_tracked<G: MonteCarloGame>() {
+        if G::TRACKS_REPETITION {
+            return;
+        }
+        let states = random_walks::<G>().into_iter().map(|(state, _)| state).collect::<Vec<_>>();
+        for end in 1..=states.len() {
+            assert!(!G::is_drawn_by_repetition(&states[..end]), "{:?} is drawn by repetition but TRACKS_REPETITION is not set", states[end - 1]);
+        }
+    }
+
+    pub fn keys_match_eq<G: MonteCarloGame>() {
+        let mut by_key = HashMap::new();
+        for (state, _) in random_walks::<G>() {
+            let known = by_key.entry(state.state_key()).or_insert_with(|| state.clone());
+            assert_eq!(*known, state, "{known:?} and {state:?} have the same key");
+        }
+        // equal states reached in different move orders give equal keys
+        let mut keys = HashMap::new();
+        for state in shallow_positions::<G>() {
+            let key = state.state_key();
+            let known = keys.entry(state.clone()).or_insert_with(|| key.clone());
+            assert_eq!(*known, key, "{state:?} has two keys");
+        }
+    }
+
+    // the positions up to three moves deep, reached in every order
+    fn shallow_positions<G: MonteCarloGame>() -> Vec<G> {
+        let mut states = vec![G::new()];
+        let mut frontier = vec![G::new()];
+        for _ in 0..3 {
+            frontier = frontier.iter()
+                .flat_map(|state| state.successors().filter(|(_, _, winner)| winner.is_none()).map(|(_, next, _)| next).collect::<Vec<_>>())
+                .collect();
+            states.extend(frontier.iter().cloned());
+        }
+        states
+    }
+
+    pub fn hash_matches_eq<G: MonteCarloGame>() {
+        let hash = |state: &G| {
+            let mut hasher = DefaultHasher::new();
+            state.hash(&mut hasher);
+            hasher.finish()
+        };
+        let states = shallow_positions::<G>();
+        for a in &states {
+            assert_eq!(hash(a), hash(&a.clone()));
+            for b in states.iter().filter(|b| *b == a) {
+                assert_eq!(hash(a), hash(b), "{a:?} is equal to {b:?} but hashes differently");
+            }
+        }
+    }
+
+    // one strategy plays both sides of a whole game and gets its carry back like from a GameStrategyPlayer
+    pub fn self_play<G: MonteCarloGame, S: GameStrategy<G>>(strategy: &S) -> Vec<G::MOVE> {
+        let mut game = G::new();
+        let mut carry = None;
+        let mut moves = Vec::new();
+        loop {
+            let (m, next_carry) = strategy.make_move(&game, carry);
+            moves.push(m);
+            let (next, winner) = game.make_move(&m).unwrap();
+            if winner.is_some() {
+                return moves;
+            }
+            game = next;
+            carry = Some((m, next_carry));
+        }
+    }
+}
+
+#[cfg(test)]
+macro_rules! test_game_conformance {
+    ($name: ident, $game: ty) => {
+        mod $name {
+            use crate::monte_carlo_game::conformance;
+
+            #[test]
+            fn new_is_open() {
+                conformance::new_is_open::<$game>();
+            }
+
+            #[test]
+            fn moves_are_legal() {
+                conformance::moves_are_legal::<$game>();
+            }
+
+            #[test]
+            fn players_alternate() {
+                conformance::players_alternate::<$game>();
+            }
+
+            #[test]
+            fn finished_has_no_moves() {
+                conformance::finished_has_no_moves::<$game>();
+            }
+
+            #[test]
+            fn finished_refuses_moves() {
+                conformance::finished_refuses_moves::<$game>();
+            }
+
+            #[test]
+            fn no_moves_has_result() {
+                conformance::no_moves_has_result::<$game>();
+            }
+
+            #[test]
+            fn ordered_moves_complete() {
+                conformance::ordered_moves_complete::<$game>();
+            }
+
+            #[test]
+            fn random_move_covers_moves() {
+                conformance::random_move_covers_moves::<$game>();
+            }
+
+            #[test]
+            fn ply_count_matches_moves_made() {
+                conformance::ply_count_matches_moves_made::<$game>();
+            }
+
+            #[test]
+            fn repetition_is_tracked() {
+                conformance::repetition_is_tracked::<$game>();
+            }
+
+            #[test]
+            fn keys_match_eq() {
+                conformance::keys_match_eq::<$game>();
+            }
+
+            #[test]
+            fn hash_matches_eq() {
+                conformance::hash_matches_eq::<$game>();
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::SmallRng;
+    use rand::seq::IteratorRandom;
+    use rand::SeedableRng;
+    use crate::line_four_7x6::{self, LineFourGame};
+    use crate::line_four_8x8::{self, LineFour8x8};
+    use crate::monte_carlo_game::conformance::random_walks;
+    use crate::monte_carlo_game::{outcome_for, parse_column_notation, perft, terminal_positions, MonteCarloGame, Score, TwoPlayer, Winner};
+    use crate::multi_score_reducer::CheckWinMonteCarloGame;
+    use crate::tic_tac_toe::{self, TicTacToe};
+
+    fn random_game<G: MonteCarloGame>(rng: &mut SmallRng) -> Vec<G::MOVE> {
+        let mut game = G::new();
+        let mut moves = Vec::new();
+        while let Some(m) = game.moves().into_iter().choose(rng) {
+            moves.push(m);
+            let (next, winner) = game.make_move(&m).unwrap();
+            if winner.is_some() {
+                break;
+            }
+            game = next;
+        }
+        moves
+    }
+
+    #[test]
+    fn player_bit_round_trip() {
+        assert_eq!(1, TwoPlayer::P1.as_bit());
+        assert_eq!(0, TwoPlayer::P2.as_bit());
+        for player in [TwoPlayer::P1, TwoPlayer::P2] {
+            assert_eq!(player, TwoPlayer::from_bit(player.as_bit()));
+            assert_eq!(player.next().as_bit(), player.as_bit() ^ 1);
+        }
+    }
+
+    #[test]
+    fn outcome_from_perspective() {
+        assert_eq!(Score::Win, outcome_for(Winner::WIN, TwoPlayer::P2, TwoPlayer::P2));
+        assert_eq!(Score::Loss, outcome_for(Winner::WIN, TwoPlayer::P1, TwoPlayer::P2));
+        assert_eq!(Score::Draw, outcome_for(Winner::TIE, TwoPlayer::P1, TwoPlayer::P2));
+        assert_eq!(1.5, [Score::Win, Score::Loss, Score::Draw].into_iter().map(Score::to_points).sum::<f64>());
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        for _ in 0..200 {
+            let moves = random_game::<LineFour8x8>(&mut rng);
+            let packed = line_four_8x8::pack_moves(&moves);
+            assert_eq!((moves.len() * 3).div_ceil(8), packed.len());
+            assert_eq!(Some(moves.clone()), line_four_8x8::unpack_moves(&packed, moves.len()));
+
+            let moves = random_game::<LineFourGame>(&mut rng);
+            let packed = line_four_7x6::pack_moves(&moves);
+            assert_eq!(Some(moves.clone()), line_four_7x6::unpack_moves(&packed, moves.len()));
+
+            let moves = random_game::<TicTacToe>(&mut rng);
+            let packed = tic_tac_toe::pack_moves(&moves);
+            assert_eq!((moves.len() * 4).div_ceil(8), packed.len());
+            assert_eq!(Some(moves.clone()), tic_tac_toe::unpack_moves(&packed, moves.len()));
+        }
+    }
+
+    #[test]
+    fn legal_mask_matches_moves() {
+        for (game, _) in random_walks::<LineFour8x8>().into_iter().filter(|(_, winner)| winner.is_none()) {
+            assert_eq!(game.moves().fold(0u8, |acc, m| acc | 1 << m as u8), game.legal_mask());
+        }
+        for (game, _) in random_walks::<LineFourGame>().into_iter().filter(|(_, winner)| winner.is_none()) {
+            assert_eq!(game.moves().fold(0u8, |acc, m| acc | 1 << m as u8), game.legal_mask());
+        }
+    }
+
+    #[test]
+    fn perft_tic_tac_toe() {
+        let counts = (0..=9).map(|depth| perft(&TicTacToe::new(), depth)).collect::<Vec<_>>();
+        assert_eq!(vec![1, 9, 72, 504, 3024, 15120, 56160, 154944, 255168, 255168], counts);
+    }
+
+    #[test]
+    fn state_space_hint_tic_tac_toe() {
+        let mut seen = std::collections::HashSet::from([TicTacToe::new()]);
+        let mut open = vec![TicTacToe::new()];
+        while let Some(state) = open.pop() {
+            for (_, next, winner) in state.successors() {
+                if seen.insert(next) && winner.is_none() {
+                    open.push(next);
+                }
+            }
+        }
+        assert_eq!(Some(seen.len()), TicTacToe::new().state_space_hint());
+    }
+
+    #[test]
+    fn state_key_layout() {
+        let (game, _) = LineFour8x8::new().apply_moves(&[line_four_8x8::LineFour8x8Index::I1, line_four_8x8::LineFour8x8Index::I1]).unwrap();
+        let mut expected = vec![0b10, 0, 0, 0, 0, 0, 0, 0];
+        // P1 to move, which is bit 1 like in the boards
+        expected.extend([0, 0b10, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(expected, game.state_key());
+        assert_eq!(vec![0, 0, 0, 0x80], TicTacToe::new().state_key());
+    }
+
+    #[test]
+    fn outcomes_of_moves() {
+        use tic_tac_toe::TicTacToeMove::*;
+        // x completes the top row with 3, no other move ends the game
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5]).unwrap();
+        let outcomes = game.moves_with_outcome().collect::<Vec<_>>();
+        assert_eq!(game.moves().collect::<Vec<_>>(), outcomes.iter().map(|(m, _)| *m).collect::<Vec<_>>());
+        assert!(outcomes.iter().all(|(m, winner)| *winner == (*m == I3).then_some(Winner::WIN)), "{outcomes:?}");
+        let (won, _) = game.make_move(&I3).unwrap();
+        assert_eq!(0, won.moves_with_outcome().count());
+    }
+
+    // the result make_move reports has to agree with win_state of the position it returned
+    fn assert_terminals_agree<G: CheckWinMonteCarloGame>(depth: usize) -> Vec<(G, Winner)> {
+        let terminals = terminal_positions::<G>(depth);
+        for (state, winner) in &terminals {
+            assert_eq!(Some(*winner), state.win_state(), "{state:?}");
+        }
+        terminals
+    }
+
+    #[test]
+    fn terminal_positions_tic_tac_toe() {
+        let terminals = assert_terminals_agree::<TicTacToe>(9);
+        let count = |winner: Winner, player: TwoPlayer| terminals.iter()
+            .filter(|(state, w)| *w == winner && (winner == Winner::TIE || state.player() == player))
+            .count();
+        // 958 distinct final boards, 626 won by the first player, 316 by the second and 16 drawn
+        assert_eq!(958, terminals.len());
+        assert_eq!(626, count(Winner::WIN, TwoPlayer::P1));
+        assert_eq!(316, count(Winner::WIN, TwoPlayer::P2));
+        assert_eq!(16, count(Winner::TIE, TwoPlayer::P1));
+    }
+
+    #[test]
+    fn terminal_positions_line_four() {
+        let terminals = assert_terminals_agree::<LineFour8x8>(7);
+        assert!(!terminals.is_empty());
+        assert!(terminals.iter().all(|(_, winner)| *winner == Winner::WIN));
+    }
+
+    #[test]
+    fn perft_line_four() {
+        let counts = (0..=7).map(|depth| perft(&LineFourGame::new(), depth)).collect::<Vec<_>>();
+        assert_eq!(vec![1, 7, 49, 343, 2401, 16807, 117649, 823536], counts);
+        let counts = (0..=6).map(|depth| perft(&LineFour8x8::new(), depth)).collect::<Vec<_>>();
+        assert_eq!(vec![1, 8, 64, 512, 4096, 32768, 262144], counts);
+    }
+
+    #[test]
+    fn move_notation() {
+        assert_eq!(Ok(3), parse_column_notation("col 4", 7));
+        assert_eq!(Ok(3), parse_column_notation(" Column4 ", 7));
+        assert_eq!(Ok(0), parse_column_notation("a", 7));
+        assert_eq!(Ok(7), parse_column_notation("h", 8));
+        for invalid in ["h", "col 0", "col 8", "col", "4", "ab", ""] {
+            assert_eq!(Err(()), parse_column_notation(invalid, 7), "{invalid:?}");
+        }
+        assert_eq!(Ok(line_four_8x8::LineFour8x8Index::I7), "col 8".try_into());
+        assert_eq!(Ok(tic_tac_toe::TicTacToeMove::I1), "a1".try_into());
+        assert_eq!(Ok(tic_tac_toe::TicTacToeMove::I6), "C2".try_into());
+        assert_eq!(Err(()), tic_tac_toe::TicTacToeMove::try_from("d1"));
+        assert_eq!("column 4", line_four_7x6::LineFourIndex::I3.to_string());
+        for m in 0..8u32 {
+            let m = line_four_8x8::LineFour8x8Index::try_from(m).unwrap();
+            assert_eq!(Ok(m), m.to_string().as_str().try_into());
+        }
+        for m in TicTacToe::new().moves() {
+            assert_eq!(Ok(m), m.to_string().as_str().try_into());
+        }
+    }
+
+    test_game_conformance!(tic_tac_toe_conformance, crate::tic_tac_toe::TicTacToe);
+    test_game_conformance!(line_four_conformance, crate::line_four_7x6::LineFourGame);
+    test_game_conformance!(line_four_8x8_conformance, crate::line_four_8x8::LineFour8x8);
+    test_game_conformance!(pop_out_conformance, crate::line_four_pop_out::ConnectFourPopOut);
+    test_game_conformance!(mnk_3_3_3_conformance, crate::mnk_game::MNKGame<3, 3, 3>);
+    test_game_conformance!(mnk_4_3_3_conformance, crate::mnk_game::MNKGame<4, 3, 3>);
+    test_game_conformance!(misere_tic_tac_toe_conformance, crate::misere_game::Misere<crate::tic_tac_toe::TicTacToe>);
+    test_game_conformance!(take_away_conformance, crate::monte_carlo_game::conformance::TakeAway);
+    test_game_conformance!(high_card_conformance, crate::simultaneous_game::Simultaneous<crate::monte_carlo_game::conformance::HighCard>);
+}
@@ -1,6 +1,6 @@
 use std::fmt::Debug;
 use std::hash::Hash;
-use crate::monte_carlo_game::MonteCarloGame;
+use crate::monte_carlo_game::{MonteCarloGame, MoveError};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum GameState {
@@ -18,7 +18,26 @@ pub trait MonteCarloGameND: Clone + Hash + Eq + Debug{
     fn moves(&self) -> Self::MOVES<'_>;
     fn get_outcomes(&self, m: &Self::MOVE) -> Result<Self::Outcomes<'_>, ()>;
 
-    fn make_move(&self, m: &Self::MOVE, e: &Self::Outcome) -> Result<(Self, GameState), ()>;
+    fn make_move(&self, m: &Self::MOVE, e: &Self::Outcome) -> Result<(Self, GameState), MoveError>;
+
+    // panics in debug builds unless the outcomes of m have non-negative chances summing to 1, searches
+    // sample outcomes by their chance and cannot pick any if they sum to 0
+    fn validate_outcomes(&self, m: &Self::MOVE) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let outcomes = self.get_outcomes(m).expect("cannot get outcomes of the move").into_iter().collect::<Vec<_>>();
+        assert!(outcomes.iter().all(|(_, chance)| *chance >= 0.0), "negative chance in {outcomes:?} of {m:?} in\n{self:?}");
+        let chance_sum = outcomes.iter().map(|(_, chance)| *chance).sum::<f64>();
+        assert!((chance_sum - 1.0).abs() < 1e-6, "chances of {outcomes:?} of {m:?} sum to {chance_sum} in\n{self:?}");
+    }
+
+    // see MonteCarloGame::is_drawn_by_repetition
+    fn is_drawn_by_repetition(_history: &[Self]) -> bool {
+        false
+    }
+
+    const TRACKS_REPETITION: bool = false;
 }
 
 impl <T: MonteCarloGame> MonteCarloGameND for T {
@@ -39,7 +58,7 @@ impl <T: MonteCarloGame> MonteCarloGameND for T {
         Ok(std::iter::once(((), 1.0)))
     }
 
-    fn make_move(&self, m: &Self::MOVE, _: &()) -> Result<(Self, GameState), ()> {
+    fn make_move(&self, m: &Self::MOVE, _: &()) -> Result<(Self, GameState), MoveError> {
         self.make_move(m).map(|(state, winner)| {
             let gs = match winner {
                 Some(_) => GameState::Finished,
@@ -48,4 +67,60 @@ impl <T: MonteCarloGame> MonteCarloGameND for T {
             (state, gs)
         })
     }
-}
\ No newline at end of file
+
+    fn is_drawn_by_repetition(history: &[Self]) -> bool {
+        <T as MonteCarloGame>::is_drawn_by_repetition(history)
+    }
+
+    const TRACKS_REPETITION: bool = <T as MonteCarloGame>::TRACKS_REPETITION;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::monte_carlo_game::{MonteCarloGame, MoveError};
+    use crate::monte_carlo_game_v2::{GameState, MonteCarloGameND};
+    use crate::tic_tac_toe::TicTacToe;
+
+    #[test]
+    fn deterministic_games_have_one_outcome() {
+        let game = <TicTacToe as MonteCarloGame>::new();
+        for m in MonteCarloGame::moves(&game) {
+            let outcomes = game.get_outcomes(&m).unwrap().collect::<Vec<_>>();
+            assert_eq!(vec![((), 1.0)], outcomes);
+            game.validate_outcomes(&m);
+        }
+    }
+
+    // a coin flip that forgot the tails side
+    #[derive(Clone, Hash, Eq, PartialEq, Debug)]
+    struct HalfCoin;
+
+    impl MonteCarloGameND for HalfCoin {
+        type MOVE = ();
+        type Outcome = bool;
+        type MOVES<'s> = [(); 1];
+        type Outcomes<'s> = [(bool, f64); 1];
+
+        fn new() -> Self {
+            HalfCoin
+        }
+
+        fn moves(&self) -> Self::MOVES<'_> {
+            [()]
+        }
+
+        fn get_outcomes(&self, _m: &()) -> Result<Self::Outcomes<'_>, ()> {
+            Ok([(true, 0.5)])
+        }
+
+        fn make_move(&self, _m: &(), _e: &bool) -> Result<(Self, GameState), MoveError> {
+            Ok((HalfCoin, GameState::Finished))
+        }
+    }
+
+    #[test]
+    fn missing_chance_is_caught() {
+        let caught = std::panic::catch_unwind(|| HalfCoin.validate_outcomes(&()));
+        assert_eq!(cfg!(debug_assertions), caught.is_err());
+    }
+}
@@ -5,19 +5,22 @@ use std::mem::MaybeUninit;
 
 pub struct ArenaHandle<T>(usize, PhantomData<T>);
 
-pub struct Arena<T> {
-    content: Vec<Chunk<T>>,
+// CHUNK is the number of slots allocated at once
+pub struct Arena<T, const CHUNK: usize = 64> {
+    content: Vec<Chunk<T, CHUNK>>,
     last_free: usize,
 }
 
-struct Chunk<T> {
-    used: u64,
-    content: Box<[MaybeUninit<T>; 64]>,
+struct Chunk<T, const CHUNK: usize> {
+    // bit i of word i / 64 is set if slot i is live
+    used: Box<[u64]>,
+    content: Box<[MaybeUninit<T>; CHUNK]>,
 }
 
-impl<T> Arena<T> {
+impl<T, const CHUNK: usize> Arena<T, CHUNK> {
     #[must_use]
     pub fn new() -> Self {
+        assert!(CHUNK > 0, "chunks need at least one slot");
         Self {
             content: vec![Chunk::new()],
             last_free: 0,
@@ -25,31 +28,33 @@ impl<T> Arena<T> {
     }
 
     pub fn insert(&mut self, item: T) -> ArenaHandle<T> {
+        // last_free is the first chunk that may have a free slot, within a chunk the lowest free slot is
+        // taken, so the slot an item ends up in only depends on the insert/remove order
         self.last_free = self.last_free.min(self.content.len());
-        for (i, chunk) in self.content[self.last_free..].iter_mut().enumerate() {
-            let slot = chunk.used.trailing_ones() as usize;
-            if let Some(slot_ref) = chunk.content.get_mut(slot) {
-                chunk.used |= 1 << slot;
-                slot_ref.write(item);
-                self.last_free += i;
-                return ArenaHandle::new(self.last_free * 64 | slot);
+        for chunk_idx in self.last_free..self.content.len() {
+            let chunk = &mut self.content[chunk_idx];
+            if let Some(slot) = chunk.first_free() {
+                chunk.content[slot].write(item);
+                chunk.set_used(slot);
+                self.last_free = chunk_idx;
+                return ArenaHandle::new(chunk_idx * CHUNK + slot);
             }
         }
         let mut new_chunk = Chunk::new();
-        new_chunk.used |= 0b1;
         new_chunk.content[0].write(item);
+        new_chunk.set_used(0);
         self.content.push(new_chunk);
         self.last_free = self.content.len() - 1;
-        ArenaHandle::new((self.content.len() - 1) * 64)
+        ArenaHandle::new((self.content.len() - 1) * CHUNK)
     }
 
     #[must_use]
     pub fn get(&self, handle: &ArenaHandle<T>) -> Option<&T> {
         debug_assert!(self.content.len() > 0);
-        let chunk_idx = handle.0 / 64;
-        let slot_idx = handle.0 % 64;
+        let chunk_idx = handle.0 / CHUNK;
+        let slot_idx = handle.0 % CHUNK;
         let chunk = self.content.get(chunk_idx)?;
-        if (chunk.used & (1 << slot_idx)) > 0 {
+        if chunk.is_used(slot_idx) {
             Some(unsafe { chunk.content[slot_idx].assume_init_ref() })
         } else {
             None
@@ -58,16 +63,69 @@ impl<T> Arena<T> {
 
     #[must_use]
     pub fn get_mut(&mut self, handle: &ArenaHandle<T>) -> Option<&mut T> {
-        let chunk_idx = handle.0 / 64;
-        let slot_idx = handle.0 % 64;
+        let chunk_idx = handle.0 / CHUNK;
+        let slot_idx = handle.0 % CHUNK;
         let chunk = self.content.get_mut(chunk_idx)?;
-        if (chunk.used & (1 << slot_idx)) > 0 {
+        if chunk.is_used(slot_idx) {
             Some(unsafe { chunk.content[slot_idx].assume_init_mut() })
         } else {
             None
         }
     }
 
+    pub fn remove(&mut self, handle: &ArenaHandle<T>) -> Option<T> {
+        let chunk_idx = handle.0 / CHUNK;
+        let slot_idx = handle.0 % CHUNK;
+        let chunk = self.content.get_mut(chunk_idx)?;
+        if !chunk.is_used(slot_idx) {
+            return None;
+        }
+        chunk.unset_used(slot_idx);
+        self.last_free = self.last_free.min(chunk_idx);
+        Some(unsafe { chunk.content[slot_idx].assume_init_read() })
+    }
+
+    // number of live slots
+    pub fn len(&self) -> usize {
+        self.content.iter().map(|chunk| chunk.used.iter().map(|word| word.count_ones() as usize).sum::<usize>()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.iter().all(|chunk| chunk.used.iter().all(|word| *word == 0))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.content.len() * CHUNK
+    }
+
+    // the live slots with their handles, in handle order
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaHandle<T>, &T)> {
+        self.content.iter().enumerate().flat_map(|(chunk_idx, chunk)| (0..CHUNK)
+            .filter(|slot| chunk.is_used(*slot))
+            .map(move |slot| (ArenaHandle::new(chunk_idx * CHUNK + slot), unsafe { chunk.content[slot].assume_init_ref() })))
+    }
+
+    // copies the arena with the same handles, f is only called for the live slots
+    pub fn clone_with(&self, mut f: impl FnMut(&T) -> T) -> Self {
+        let content = self.content.iter()
+            .map(|chunk| {
+                let mut copy = Chunk::new();
+                for (i, slot) in chunk.content.iter().enumerate() {
+                    if chunk.is_used(i) {
+                        copy.content[i].write(f(unsafe { slot.assume_init_ref() }));
+                        // only mark the slot after it is written, so a panicking f does not drop uninit memory
+                        copy.set_used(i);
+                    }
+                }
+                copy
+            })
+            .collect();
+        Self {
+            content,
+            last_free: self.last_free,
+        }
+    }
+
     pub fn purge(&mut self) {
         for chunk in &mut self.content {
             chunk.clear(|_| ());
@@ -76,28 +134,54 @@ impl<T> Arena<T> {
     }
 }
 
-impl<T> Chunk<T> {
+impl<T: Clone, const CHUNK: usize> Clone for Arena<T, CHUNK> {
+    fn clone(&self) -> Self {
+        self.clone_with(T::clone)
+    }
+}
+
+impl<T, const CHUNK: usize> Chunk<T, CHUNK> {
     fn new() -> Self {
         let content = unsafe {
-            let layout = Layout::new::<[MaybeUninit<T>; 64]>();
+            let layout = Layout::new::<[MaybeUninit<T>; CHUNK]>();
             let allocated = std::alloc::alloc(layout);
-            let allocated = allocated as *mut [MaybeUninit<T>; 64];
+            let allocated = allocated as *mut [MaybeUninit<T>; CHUNK];
             Box::from_raw(allocated)
         };
-        Chunk { used: 0, content }
+        Chunk { used: vec![0; CHUNK.div_ceil(64)].into_boxed_slice(), content }
+    }
+
+    fn is_used(&self, slot: usize) -> bool {
+        (self.used[slot / 64] & (1 << (slot % 64))) > 0
+    }
+
+    fn set_used(&mut self, slot: usize) {
+        self.used[slot / 64] |= 1 << (slot % 64);
+    }
+
+    fn unset_used(&mut self, slot: usize) {
+        self.used[slot / 64] &= !(1 << (slot % 64));
+    }
+
+    fn first_free(&self) -> Option<usize> {
+        self.used.iter()
+            .enumerate()
+            .find(|(_, word)| **word != u64::MAX)
+            .map(|(i, word)| i * 64 + word.trailing_ones() as usize)
+            .filter(|slot| *slot < CHUNK)
     }
 
     fn clear<F: FnMut(T)>(&mut self, mut with: F) {
-        for (i, slot) in self.content.iter_mut().enumerate() {
-            if (self.used & (1 << i as u64)) > 0 {
-                with(unsafe { slot.assume_init_read() });
+        for i in 0..CHUNK {
+            if self.is_used(i) {
+                with(unsafe { self.content[i].assume_init_read() });
             }
         }
-        self.used = 0;
+        self.used.fill(0);
     }
 }
 
-impl<T> Drop for Chunk<T> {
+impl<T, const CHUNK: usize> Drop for Chunk<T, CHUNK> {
     fn drop(&mut self) {
         self.clear(|_| ());
     }
@@ -112,6 +196,10 @@ impl<T> ArenaHandle<T> {
     pub const fn invalid() -> Self {
         Self(usize::MAX, PhantomData)
     }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
 }
 
 impl<T> PartialEq<ArenaHandle<T>> for ArenaHandle<T> {
@@ -165,4 +253,81 @@ mod tests {
         arena.purge();
         assert_eq!(handle.iter().filter_map(|handle| arena.get(handle)).next(), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn size_statistics() {
+        let mut arena: Arena<u64> = Arena::new();
+        assert!(arena.is_empty());
+        let handles = (0..1000).map(|n| arena.insert(n)).collect::<Vec<_>>();
+        assert_eq!(1000, arena.len());
+        assert_eq!(1024, arena.capacity());
+        arena.remove(&handles[500]);
+        assert_eq!(999, arena.len());
+        arena.purge();
+        assert!(arena.is_empty());
+        assert_eq!(0, arena.len());
+        // purge keeps the chunks around
+        assert_eq!(1024, arena.capacity());
+    }
+
+    #[test]
+    fn clone_keeps_handles() {
+        let mut arena: Arena<u64, 8> = Arena::new();
+        let handles = (0..20).map(|n| arena.insert(n)).collect::<Vec<_>>();
+        arena.remove(&handles[5]);
+        let mut copy = arena.clone();
+        arena.purge();
+        assert_eq!(None, copy.get(&handles[5]));
+        assert!((0..20).filter(|n| *n != 5).all(|n| copy.get(&handles[n]) == Some(&(n as u64))));
+        // the free slot is reused in the copy as well
+        assert!(copy.insert(100) == handles[5]);
+    }
+
+    #[test]
+    fn reuses_earliest_free_slot() {
+        let mut arena: Arena<u64> = Arena::new();
+        let handles = (0..200).map(|n| arena.insert(n)).collect::<Vec<_>>();
+        assert_eq!(Some(10), arena.remove(&handles[10]));
+        assert_eq!(Some(150), arena.remove(&handles[150]));
+        assert_eq!(Some(70), arena.remove(&handles[70]));
+        assert_eq!(None, arena.remove(&handles[70]));
+
+        assert!(arena.insert(1000) == handles[10]);
+        assert!(arena.insert(1001) == handles[70]);
+        assert!(arena.insert(1002) == handles[150]);
+        let next = arena.insert(1003);
+        assert_eq!(Some(&1003), arena.get(&next));
+        assert_eq!(201, arena.len());
+        assert_eq!(256, arena.capacity());
+        assert_eq!(Some(&1001), arena.get(&handles[70]));
+    }
+
+    #[test]
+    fn custom_chunk_size() {
+        let mut small: Arena<u64, 8> = Arena::new();
+        let mut large: Arena<u64, 200> = Arena::new();
+        let small_handles = (0..100).map(|n| small.insert(n)).collect::<Vec<_>>();
+        let large_handles = (0..500).map(|n| large.insert(n)).collect::<Vec<_>>();
+        assert_eq!(104, small.capacity());
+        assert_eq!(600, large.capacity());
+        assert_eq!(Some(17), small.remove(&small_handles[17]));
+        assert_eq!(Some(263), large.remove(&large_handles[263]));
+        assert!(small.insert(1000) == small_handles[17]);
+        assert!(large.insert(1000) == large_handles[263]);
+        assert_eq!(Some(&99), small.get(&small_handles[99]));
+        assert_eq!(Some(&499), large.get(&large_handles[499]));
+        assert_eq!(500, large.len());
+    }
+
+    #[test]
+    fn iter_yields_live_slots() {
+        let mut arena: Arena<u64, 8> = Arena::new();
+        let handles = (0..20).map(|n| arena.insert(n)).collect::<Vec<_>>();
+        arena.remove(&handles[3]);
+        arena.remove(&handles[12]);
+        let live = arena.iter().collect::<Vec<_>>();
+        assert_eq!(18, live.len());
+        assert!(live.iter().all(|(handle, n)| handles[**n as usize] == *handle && arena.get(handle) == Some(*n)));
+        assert!(live.iter().all(|(_, n)| **n != 3 && **n != 12));
+    }
+}
@@ -4,6 +4,7 @@ use std::rc::Rc;
 use crate::ai_infra::GameStrategy;
 use crate::monte_carlo_game::{MonteCarloGame, Winner};
 use crate::monte_carlo_v2::arena::{Arena, ArenaHandle};
+use crate::monte_carlo_v2::immediate_win;
 
 //#[derive(Clone, Eq, PartialEq, Hash)]
 //struct MCNodeId<T: MonteCarloGame>(ArenaHandle<T>);
@@ -23,6 +24,17 @@ struct MCContext<T: MonteCarloGame> {
     node_store: Arena<MCNode<T>>
 }
 
+// how the tree grows during a search, ids are the arena index of a node and stay the same for one search.
+// A state reached again on another line links its node to the new parent instead of creating one
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum McEvent {
+    Created { id: usize, parent: Option<usize> },
+    Linked { id: usize, parent: usize },
+    Visited { id: usize, score: f64, visits: u64 },
+}
+
+type Observer<'o, 'f> = Option<&'o mut (dyn FnMut(McEvent) + 'f)>;
+
 pub struct MonteCarloV2I1 {
     playoffs: usize
 }
@@ -38,11 +50,27 @@ impl <G: MonteCarloGame> GameStrategy<G> for MonteCarloV2I1 {
     }
 
     fn make_move(&self, game: &G, _carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
-        (select_move(game, self.playoffs), ())
+        (select_move(game, self.playoffs, None), ())
+    }
+}
+
+impl MonteCarloV2I1 {
+    // like make_move, every change to the tree is passed to observer
+    pub fn make_move_observed<G: MonteCarloGame>(&self, game: &G, observer: &mut dyn FnMut(McEvent)) -> G::MOVE {
+        select_move(game, self.playoffs, Some(observer))
     }
 }
 
-fn select_move<T: MonteCarloGame>(state: &T, times: usize) -> T::MOVE {
+fn emit(observer: &mut Observer, event: McEvent) {
+    if let Some(observer) = observer {
+        observer(event);
+    }
+}
+
+fn select_move<T: MonteCarloGame>(state: &T, times: usize, mut observer: Observer) -> T::MOVE {
+    if let Some(m) = immediate_win(state) {
+        return m;
+    }
     let mut context = MCContext {
         mappings: HashMap::new(),
         node_store: Arena::new(),
@@ -62,9 +90,10 @@ fn select_move<T: MonteCarloGame>(state: &T, times: usize) -> T::MOVE {
         };
         context.alloc_node(node)
     };
+    emit(&mut observer, McEvent::Created { id: root_node.index(), parent: None });
     let mut buf = Vec::new();
     for _ in 0..times {
-        playoff(root_node.clone(), &mut context, 2, &mut buf);
+        playoff(root_node.clone(), &mut context, 2, &mut buf, observer.as_deref_mut());
     }
     dbg!(context.node_store.get(&root_node).unwrap().visited_amount);
     context.node_store.get(&root_node).unwrap().moves.iter()
@@ -76,7 +105,7 @@ fn select_move<T: MonteCarloGame>(state: &T, times: usize) -> T::MOVE {
         .clone()
 }
 
-fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext<T>, player_count: u8, buf: &mut Vec<(MCNodeId<T>, f64)>) where T: Eq + Hash {
+fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext<T>, player_count: u8, buf: &mut Vec<(MCNodeId<T>, f64)>, mut observer: Observer) where T: Eq + Hash {
     let mut node = context.node_store.get(&root).expect("root node not given");
     let mut current_id = root;
     let mut current_player_num = 0;
@@ -101,6 +130,7 @@ fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext
 
                 let next_node = context.node_store.get_mut(&next_id).expect("orphan state-map entry");
                 next_node.predecessors.push(current_id.clone());
+                emit(&mut observer, McEvent::Linked { id: next_id.index(), parent: current_id.index() });
                 let next_node = context.node_store.get(&next_id).unwrap();
                 (next_id, next_node)
             } else {
@@ -123,6 +153,7 @@ fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext
                 };
 
                 let next_id = context.alloc_node(new_node);
+                emit(&mut observer, McEvent::Created { id: next_id.index(), parent: Some(current_id.index()) });
 
                 context.node_store.get_mut(&current_id).unwrap().moves.get_mut(next_move_i).unwrap().0 = next_id.clone();
 
@@ -135,7 +166,7 @@ fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext
         current_player_num = (current_player_num + 1) % player_count;
     }
 
-    backtrack_from_leaf(current_id, context, buf);
+    backtrack_from_leaf(current_id, context, buf, observer);
 }
 
 fn select_next<T: MonteCarloGame>(parent: &MCNode<T>, moves: &[(MCNodeId<T>, T::MOVE)], ai_turn: bool, context: &MCContext<T>, c: f64) -> Option<usize> {
@@ -171,7 +202,7 @@ fn compute_initial_score(ai_at_turn: bool, win_state: Option<Winner>) -> (bool,
     }
 }
 
-fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCContext<T>, buf: &mut Vec<(MCNodeId<T>, f64)>) {
+fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCContext<T>, buf: &mut Vec<(MCNodeId<T>, f64)>, mut observer: Observer) {
     buf.clear();
     {
         let leaf = context.node_store.get(&leaf).unwrap();
@@ -184,6 +215,7 @@ fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCCon
         let second_level = context.node_store.get_mut(&node).unwrap();
         second_level.score_balance += score;
         second_level.visited_amount += 1;
+        emit(&mut observer, McEvent::Visited { id: node.index(), score: second_level.score_balance, visits: second_level.visited_amount });
         buf.extend(second_level.predecessors.iter().cloned().map(|pred| (pred, score)));
     }
     buf.drain(0..initial_length);
@@ -193,6 +225,7 @@ fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCCon
         score /= node.moves.len() as f64;
         node.score_balance += score;
         node.visited_amount += 1;
+        emit(&mut observer, McEvent::Visited { id: next.index(), score: node.score_balance, visits: node.visited_amount });
         buf.extend(node.predecessors.iter().cloned().map(|pred| (pred, score)))
     }
 }
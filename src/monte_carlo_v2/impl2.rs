@@ -4,6 +4,7 @@ use std::rc::Rc;
 use crate::ai_infra::GameStrategy;
 use crate::monte_carlo_game::{MonteCarloGame, Winner};
 use crate::monte_carlo_v2::arena::{Arena, ArenaHandle};
+use crate::monte_carlo_v2::immediate_win;
 
 type MCNodeId<T> = ArenaHandle<MCNode<T>>;
 
@@ -41,6 +42,9 @@ impl <G: MonteCarloGame> GameStrategy<G> for MonteCarloV2I2 {
 }
 
 fn select_move<T: MonteCarloGame>(state: &T, times: usize) -> T::MOVE {
+    if let Some(m) = immediate_win(state) {
+        return m;
+    }
     let mut context = MCContext {
         mappings: HashMap::new(),
         node_store: Arena::new(),
@@ -2,10 +2,11 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::rc::Rc;
 use std::time::Instant;
-use rustc_hash::{FxHashMap};
+use rustc_hash::{FxHashMap, FxHashSet};
 use crate::ai_infra::GameStrategy;
 use crate::monte_carlo_game::{MonteCarloGame, Winner};
 use crate::monte_carlo_v2::arena::{Arena, ArenaHandle};
+use crate::monte_carlo_v2::{immediate_win, map_capacity};
 use crate::monte_carlo_v2::moves_buffer::{SliceArena, SliceHandle};
 
 type MCNodeId<T> = ArenaHandle<MCNode<T>>;
@@ -48,7 +49,7 @@ impl <G: MonteCarloGame> GameStrategy<G> for MonteCarloV2I3 {
 
     fn make_move(&self, game: &G, carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
         let mut context = carry.map(|(_, ctx)| ctx).unwrap_or_else(|| MCContext {
-            mappings: HashMap::with_capacity_and_hasher(self.playoffs / 10, Default::default()),
+            mappings: HashMap::with_capacity_and_hasher(map_capacity(game, self.playoffs), Default::default()),
             node_store: Arena::new(),
             unused_rcs: vec![],
             move_store: SliceArena::new(),
@@ -67,10 +68,10 @@ impl <G: MonteCarloGame> GameStrategy<G> for MonteCarloV2I3 {
 }
 
 fn select_move<T: MonteCarloGame>(state: &T, times: usize, context: &mut MCContext<T>) -> T::MOVE {
-    context.node_store.purge();
-    context.move_store.clear();
-    context.unused_rcs.reserve(context.mappings.len());
-    context.unused_rcs.extend(context.mappings.drain().map(|(state, _)| state));
+    if let Some(m) = immediate_win(state) {
+        return m;
+    }
+    context.reset();
 
 
     let root_node = {
@@ -89,8 +90,9 @@ fn select_move<T: MonteCarloGame>(state: &T, times: usize, context: &mut MCConte
         context.alloc_node(node)
     };
     let mut buf = Vec::new();
+    let mut visited = FxHashSet::default();
     for _ in 0..times {
-        playoff(root_node.clone(), context, 2, &mut buf);
+        playoff(root_node.clone(), context, 2, &mut buf, &mut visited);
     }
     dbg!(context.node_store.get(&root_node).unwrap().visited_amount);
     let root_node = context.node_store.get(&root_node).unwrap();
@@ -104,7 +106,7 @@ fn select_move<T: MonteCarloGame>(state: &T, times: usize, context: &mut MCConte
         .clone()
 }
 
-fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext<T>, player_count: u8, buf: &mut Vec<(MCNodeId<T>, f64, bool)>) where T: Eq + Hash {
+fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext<T>, player_count: u8, buf: &mut Vec<(MCNodeId<T>, f64, bool)>, visited: &mut FxHashSet<MCNodeId<T>>) where T: Eq + Hash {
     let mut node = context.node_store.get(&root).expect("root node not given");
     let mut current_id = root;
     let mut current_player_num = 0;
@@ -161,7 +163,7 @@ fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext
         current_player_num = (current_player_num + 1) % player_count;
     }
 
-    backtrack_from_leaf(current_id, context, buf);
+    backtrack_from_leaf(current_id, context, buf, visited);
 }
 
 #[inline(never)]
@@ -216,8 +218,9 @@ fn compute_initial_score(win_state: Option<Winner>) -> (bool, f64) {
     }
 }
 
+// visited makes sure a node reachable from the leaf on several paths is only updated once
 #[inline(never)]
-fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCContext<T>, buf: &mut Vec<(MCNodeId<T>, f64, bool)>) {
+fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCContext<T>, buf: &mut Vec<(MCNodeId<T>, f64, bool)>, visited: &mut FxHashSet<MCNodeId<T>>) {
     fn compute_completely_computed<T: MonteCarloGame>(node: &MCNode<T>, context: &MCContext<T>) -> bool {
         if let Some(moves) = context.move_store.get(&node.moves) {
             moves.iter()
@@ -228,6 +231,8 @@ fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCCon
         }
     }
     buf.clear();
+    visited.clear();
+    visited.insert(leaf);
     {
         let leaf = context.node_store.get_mut(&leaf).unwrap();
         // queue immediate predecessors
@@ -236,6 +241,9 @@ fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCCon
     let initial_length = buf.len();
     for i in 0..initial_length {
         let (node, score, _) = buf[i].clone();
+        if !visited.insert(node) {
+            continue;
+        }
         let second_level = context.node_store.get(&node).unwrap();
         let new_cc = compute_completely_computed(second_level, context);
         let second_level = context.node_store.get_mut(&node).unwrap();
@@ -247,6 +255,9 @@ fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCCon
     buf.drain(0..initial_length);
 
     while let Some((next, mut score, check_cc)) = buf.pop() {
+        if !visited.insert(next) {
+            continue;
+        }
         let node = context.node_store.get(&next).unwrap();
         let new_cc = if check_cc {
             compute_completely_computed(node, context)
@@ -296,6 +307,14 @@ impl <T: MonteCarloGame> CompactPred<T> {
 }
 
 impl<T: MonteCarloGame> MCContext<T> {
+    // drops the whole tree, the game states are kept in unused_rcs for the next nodes
+    pub fn reset(&mut self) {
+        self.node_store.purge();
+        self.move_store.clear();
+        self.unused_rcs.reserve(self.mappings.len());
+        self.unused_rcs.extend(self.mappings.drain().map(|(state, _)| state));
+    }
+
     fn alloc_node(&mut self, node: MCNode<T>) -> MCNodeId<T> {
         let node_game = node.game_state.clone();
         let id = self.node_store.insert(node);
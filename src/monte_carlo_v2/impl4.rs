@@ -7,15 +7,18 @@ use std::time::Instant;
 use bumpalo::Bump;
 use rand::{Rng, SeedableRng};
 use rand::seq::SliceRandom;
-use rustc_hash::{FxHashMap};
+use rustc_hash::{FxHashMap, FxHashSet};
 use crate::ai_infra::GameStrategy;
 use crate::monte_carlo_game::{MonteCarloGame, Winner};
 use crate::monte_carlo_v2::arena::{Arena, ArenaHandle};
+use crate::monte_carlo_v2::{immediate_win, map_capacity};
 use crate::monte_carlo_v2::moves_buffer::{SliceArena, SliceHandle};
+use crate::multi_score_reducer::{ExecutionLimiter, ExecutionLimiterFactory, Unlimited};
 
 type MCNodeId<T> = ArenaHandle<MCNode<T>>;
 type Successor<T: MonteCarloGame> = (MCNodeId<T>, T::MOVE);
 
+#[derive(Clone)]
 enum CompactPred<T: MonteCarloGame> {
     LessThanThree([MCNodeId<T>; 2]),
     MoreOrEqThree(Vec<MCNodeId<T>>)
@@ -27,6 +30,8 @@ struct MCNode<T: MonteCarloGame> {
     game_state: Rc<T>,
     visited_amount: u64,
     score_balance: f64,
+    // sum of the squared rewards, for the variance estimate of UCB1-Tuned
+    sum_of_squares: f64,
     completely_computed: bool
 }
 
@@ -35,28 +40,155 @@ pub struct MCContext<T: MonteCarloGame> {
     node_store: Arena<MCNode<T>>,
     unused_rcs: Vec<Rc<T>>,
     move_store: SliceArena<Successor<T>>,
+    // see MonteCarloConfigV2I4
+    max_nodes: Option<usize>,
+    use_transpositions: bool,
+    // nodes allocated by the last search, evicted ones included
+    allocated_nodes: usize,
 
     tmp_buf: Bump,
     rng: RefCell<rand::rngs::SmallRng>,
 }
 
-pub struct MonteCarloV2I4 {
+impl <T: MonteCarloGame> MCContext<T> {
+    // deep copy of the search state, the game states get copied as well, since unused_rcs relies on
+    // being their only owner
+    pub fn snapshot(&self) -> Self {
+        let mut copies = FxHashMap::<*const T, Rc<T>>::default();
+        let mut copy_state = |state: &Rc<T>| copies.entry(Rc::as_ptr(state))
+            .or_insert_with(|| Rc::new(T::clone(state)))
+            .clone();
+        let mappings = self.mappings.iter()
+            .map(|(state, id)| (copy_state(state), *id))
+            .collect();
+        let node_store = self.node_store.clone_with(|node| MCNode {
+            predecessors: node.predecessors.clone(),
+            moves: node.moves.clone(),
+            game_state: copy_state(&node.game_state),
+            visited_amount: node.visited_amount,
+            score_balance: node.score_balance,
+            sum_of_squares: node.sum_of_squares,
+            completely_computed: node.completely_computed,
+        });
+        Self {
+            mappings,
+            node_store,
+            unused_rcs: self.unused_rcs.iter().map(|state| Rc::new(T::clone(state))).collect(),
+            move_store: self.move_store.clone(),
+            max_nodes: self.max_nodes,
+            use_transpositions: self.use_transpositions,
+            allocated_nodes: self.allocated_nodes,
+            tmp_buf: Bump::new(),
+            rng: self.rng.clone(),
+        }
+    }
+}
+
+pub struct MonteCarloV2I4<L = Unlimited> {
     playoffs: usize,
-    rng_seed: Option<[u8; 32]>
+    rng_seed: Option<[u8; 32]>,
+    search: SearchParams,
+    max_nodes: Option<usize>,
+    use_transpositions: bool,
+    limiter: L,
+}
+
+// the knobs of a single search, see MonteCarloConfigV2I4
+#[derive(Copy, Clone, Debug)]
+struct SearchParams {
+    tie_value: f64,
+    policy: Policy,
+    rollout_policy: RolloutPolicy,
+    virtual_loss: f64,
+    paths_in_flight: usize,
+    backup: Backup,
+}
+
+// RandomAfter(depth) stops expanding the tree at the given depth below the root and plays randomly
+// until the game ends instead, only that result is backpropagated
+#[derive(Copy, Clone, Debug)]
+pub enum RolloutPolicy {
+    FullExpand,
+    RandomAfter(usize),
+}
+
+// Ucb1Tuned scales the exploration term by the empirical variance of the rewards of the node
+#[derive(Copy, Clone, Debug)]
+pub enum Policy {
+    Ucb1,
+    Ucb1Tuned,
+}
+
+// Average backs up the mean of all playoffs through a node, Minimax sets a node to the mean of its best
+// child from the view of the player to move there, which finds forced wins with fewer playoffs
+#[derive(Copy, Clone, Debug)]
+pub enum Backup {
+    Average,
+    Minimax,
 }
 
-pub struct MonteCarloConfigV2I4 {
+pub struct MonteCarloConfigV2I4<L = Unlimited> {
     pub num_playoffs: usize,
-    pub rng_seed: Option<[u8; 32]>
+    pub rng_seed: Option<[u8; 32]>,
+    // score of a tie for the player to move at the root, a win is worth 1.0. The opponent is taken to score
+    // it as -tie_value, so a positive value makes the search avoid ties and a negative one seek them
+    pub tie_value: f64,
+    pub policy: Policy,
+    pub rollout_policy: RolloutPolicy,
+    // batched selection: paths_in_flight paths are selected one after the other before any of them is
+    // backpropagated. Every node on a pending path counts as visited once more with virtual_loss taken from its
+    // score, so the following selections spread out. This only mimics the selection of a tree parallel search,
+    // the search still runs on one thread and MCContext is not thread safe. 1 is the plain sequential search
+    pub virtual_loss: f64,
+    pub paths_in_flight: usize,
+    // once the tree grows past max_nodes, the least visited leaves are evicted until it is 1/8 below the cap
+    pub max_nodes: Option<usize>,
+    pub backup: Backup,
+    // without transpositions a position reached on several paths gets a node on each, so the search is a tree
+    // and not a DAG. Only the first node of a position is in mappings then
+    pub use_transpositions: bool,
+    // asked on every step of a playoff with the number of nodes in the tree, before a new one is allocated.
+    // A NodeLimitFactory stops the tree from growing beyond its max_nodes instead of evicting nodes
+    pub limiter: L,
+}
+
+impl <L: Default> Default for MonteCarloConfigV2I4<L> {
+    fn default() -> Self {
+        Self {
+            num_playoffs: 1000,
+            rng_seed: None,
+            tie_value: 0.0,
+            policy: Policy::Ucb1,
+            rollout_policy: RolloutPolicy::FullExpand,
+            virtual_loss: 0.0,
+            paths_in_flight: 1,
+            max_nodes: None,
+            backup: Backup::Average,
+            use_transpositions: true,
+            limiter: L::default(),
+        }
+    }
 }
-impl <G: MonteCarloGame> GameStrategy<G> for MonteCarloV2I4 {
+
+impl <G: MonteCarloGame, L: ExecutionLimiterFactory<G>> GameStrategy<G> for MonteCarloV2I4<L> {
     type Carry = MCContext<G>;
-    type Config = MonteCarloConfigV2I4;
+    type Config = MonteCarloConfigV2I4<L>;
 
     fn new(config: Self::Config) -> Self {
         Self {
             playoffs: config.num_playoffs,
             rng_seed: config.rng_seed,
+            search: SearchParams {
+                tie_value: config.tie_value,
+                policy: config.policy,
+                rollout_policy: config.rollout_policy,
+                virtual_loss: config.virtual_loss,
+                paths_in_flight: config.paths_in_flight.max(1),
+                backup: config.backup,
+            },
+            max_nodes: config.max_nodes,
+            use_transpositions: config.use_transpositions,
+            limiter: config.limiter,
         }
     }
 
@@ -65,15 +197,20 @@ impl <G: MonteCarloGame> GameStrategy<G> for MonteCarloV2I4 {
             .map(|seed| rand::rngs::SmallRng::from_seed(seed))
             .unwrap_or_else(|| rand::rngs::SmallRng::from_entropy());
         let mut context = carry.map(|(_, ctx)| ctx).unwrap_or_else(|| MCContext {
-            mappings: HashMap::with_capacity_and_hasher(self.playoffs / 10, Default::default()),
+            mappings: HashMap::with_capacity_and_hasher(map_capacity(game, self.playoffs), Default::default()),
             node_store: Arena::new(),
             unused_rcs: vec![],
             move_store: SliceArena::new(),
+            max_nodes: None,
+            use_transpositions: true,
+            allocated_nodes: 0,
             tmp_buf: Default::default(),
             rng: RefCell::new(rng),
         });
+        context.max_nodes = self.max_nodes;
+        context.use_transpositions = self.use_transpositions;
         let start = Instant::now();
-        let result = (select_move(game, self.playoffs, &mut context), context);
+        let result = (select_move(game, self.playoffs, &self.search, &self.limiter, &mut context), context);
         //1.34836958s
         //1.347581748s
         //1.376205498s
@@ -83,13 +220,25 @@ impl <G: MonteCarloGame> GameStrategy<G> for MonteCarloV2I4 {
         println!("time taken: {}s", start.elapsed().as_secs_f64());
         result
     }
+
+    fn searched_nodes(&self, context: &Self::Carry) -> Option<u64> {
+        Some(context.allocated_nodes as u64)
+    }
+
+    fn reset(&self, context: &mut Self::Carry) {
+        context.reset();
+        context.unused_rcs.clear();
+        if let Some(seed) = self.rng_seed {
+            context.rng = RefCell::new(rand::rngs::SmallRng::from_seed(seed));
+        }
+    }
 }
 
-fn select_move<T: MonteCarloGame>(state: &T, times: usize, context: &mut MCContext<T>) -> T::MOVE {
-    context.node_store.purge();
-    context.move_store.clear();
-    context.unused_rcs.reserve(context.mappings.len());
-    context.unused_rcs.extend(context.mappings.drain().map(|(state, _)| state));
+fn select_move<T: MonteCarloGame>(state: &T, times: usize, search: &SearchParams, limiter: &impl ExecutionLimiterFactory<T>, context: &mut MCContext<T>) -> T::MOVE {
+    context.reset();
+    if let Some(m) = immediate_win(state) {
+        return m;
+    }
 
 
     let root_node = {
@@ -103,15 +252,44 @@ fn select_move<T: MonteCarloGame>(state: &T, times: usize, context: &mut MCConte
             game_state,
             visited_amount: 0,
             score_balance: 0.0,
+            sum_of_squares: 0.0,
             completely_computed: false,
         };
         context.alloc_node(node)
     };
+    let paths_in_flight = search.paths_in_flight;
     let mut buf = Vec::new();
-    for _ in 0..times {
-        playoff(root_node.clone(), context, 2, &mut buf);
+    let mut visited = FxHashSet::default();
+    let mut paths = (0..paths_in_flight).map(|_| (MCNodeId::invalid(), None, Vec::new())).collect::<Vec<_>>();
+    let mut remaining = times;
+    while remaining > 0 {
+        let batch = &mut paths[..paths_in_flight.min(remaining)];
+        for (leaf, score, path) in batch.iter_mut() {
+            path.clear();
+            (*leaf, *score) = playoff(root_node.clone(), context, search, limiter, path);
+        }
+        // the batch was selected sequentially, all virtual losses are removed before the first path is backpropagated
+        for (_, _, path) in batch.iter() {
+            remove_virtual_loss(path, search.virtual_loss, context);
+        }
+        for (leaf, score, _) in batch.iter() {
+            let score = score.unwrap_or_else(|| context.node_store.get(leaf).unwrap().score_balance);
+            backtrack_from_leaf(*leaf, score, search.backup, context, &mut buf, &mut visited);
+        }
+        // no path is in flight here, so none of their nodes can be evicted
+        if let Some(max_nodes) = context.max_nodes {
+            if context.node_store.len() > max_nodes {
+                context.evict_leaves(root_node, max_nodes - max_nodes / 8);
+            }
+        }
+        remaining -= batch.len();
     }
     dbg!(context.node_store.get(&root_node).unwrap().visited_amount);
+    log::debug!(
+        "tree size: {} of {} nodes, {} of {} moves",
+        context.node_store.len(), context.node_store.capacity(),
+        context.move_store.len(), context.move_store.capacity(),
+    );
     let root_node = context.node_store.get(&root_node).unwrap();
     let root_moves = context.move_store.get(&root_node.moves).unwrap();
     root_moves.iter()
@@ -123,27 +301,66 @@ fn select_move<T: MonteCarloGame>(state: &T, times: usize, context: &mut MCConte
         .clone()
 }
 
-fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext<T>, player_count: u8, buf: &mut Vec<(MCNodeId<T>, f64, bool)>) where T: Eq + Hash {
+// selects a path from root and expands it, every node entered is added to path with the virtual loss applied.
+// Returns the leaf to backpropagate from and the score of a random rollout if one was played, the score
+// of the leaf node is used otherwise
+fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext<T>, search: &SearchParams, limiter: &impl ExecutionLimiterFactory<T>, path: &mut Vec<MCNodeId<T>>) -> (MCNodeId<T>, Option<f64>) where T: Eq + Hash {
+    let SearchParams { tie_value, policy, rollout_policy, virtual_loss, .. } = *search;
+    // scores are for the player who moved into a node, the tie value is for the player at the root
+    let tie_for = |root_moved: bool| if root_moved { tie_value } else { -tie_value };
+    const PLAYER_COUNT: u8 = 2;
     let mut node = context.node_store.get(&root).expect("root node not given");
     let mut current_id = root;
     let mut current_player_num = 0;
+    let mut depth = 0;
+    let mut history = Vec::new();
+    if T::TRACKS_REPETITION {
+        history.push(T::clone(&node.game_state));
+    }
+    let mut limiter = limiter.create();
     loop {
+        if let RolloutPolicy::RandomAfter(max_depth) = rollout_policy {
+            if depth >= max_depth.max(1) && node.moves.len() > 0 {
+                let state = node.game_state.clone();
+                let score = random_rollout(&*state, tie_for(current_player_num == 1), context);
+                let node = context.node_store.get_mut(&current_id).unwrap();
+                node.score_balance += score;
+                node.sum_of_squares += score * score;
+                node.visited_amount += 1;
+                return (current_id, Some(score));
+            }
+        }
         // select next move;
 
         let moves_ref = context.move_store.get(&node.moves).unwrap();
 
         context.tmp_buf.reset();
-        let next_move_i = if let Some(m) = select_next::<T>(node, moves_ref, context, 2.0) { m } else { break; };
+        let next_move_i = if let Some(m) = select_next::<T>(node, moves_ref, context, 2.0, policy) { m } else { break; };
         let next_move = &moves_ref[next_move_i];
 
-        (current_id, node) = if context.node_store.get(&next_move.0).is_some() {
+        current_id = if let Some(next) = context.node_store.get(&next_move.0) {
             //Initialised
-            let next = context.node_store.get(&next_move.0).unwrap();
-            (next_move.0.clone(), next)
+            if limiter.next_with_stats(next.moves.len(), &next.game_state, context.node_store.len()).is_break() {
+                break;
+            }
+            next_move.0.clone()
         } else {
             //Not Initialised
             let (next_state, winner) = node.game_state.make_move(&next_move.1).unwrap();
-            let id = context.mappings.get(&next_state).cloned();
+            // a position without moves that make_move did not end, like a stalemate
+            let winner = match winner {
+                None if next_state.moves().into_iter().next().is_none() => next_state.no_moves_result(),
+                winner => winner,
+            };
+            let child_count = if winner.is_some() { 0 } else { next_state.moves().into_iter().count() };
+            if limiter.next_with_stats(child_count, &next_state, context.node_store.len()).is_break() {
+                break;
+            }
+            let id = if context.use_transpositions {
+                context.mappings.get(&next_state).cloned()
+            } else {
+                None
+            };
 
             if matches!(winner, Some(Winner::WIN) if current_player_num == 0) {
                 context.node_store.get_mut(&current_id).unwrap().completely_computed = true;
@@ -157,8 +374,7 @@ fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext
 
                 let next_node = context.node_store.get_mut(&next_id).expect("orphan state-map entry");
                 next_node.predecessors.push(current_id.clone());
-                let next_node = context.node_store.get(&next_id).unwrap();
-                (next_id, next_node)
+                next_id
             } else {
                 let game_state = match context.unused_rcs.pop() {
                     None => Rc::new(next_state),
@@ -167,26 +383,78 @@ fn playoff<T: MonteCarloGame + Clone>(root: MCNodeId<T>, context: &mut MCContext
                         gs
                     }
                 };
-                let next_id = new_node_entry(current_id.clone(), game_state, winner, context);
+                let next_id = new_node_entry(current_id.clone(), game_state, winner, tie_for(current_player_num == 0), context);
                 context.node_store.get_mut(&current_id)
                     .and_then(|node| context.move_store.get_mut(&node.moves))
                     .and_then(|moves| moves.get_mut(next_move_i))
                     .unwrap().0 = next_id.clone();
-                let next_node = context.node_store.get(&next_id).unwrap();
-                (next_id, next_node)
+                next_id
             }
         };
 
+        let entered = context.node_store.get_mut(&current_id).unwrap();
+        entered.visited_amount += 1;
+        entered.score_balance -= virtual_loss;
+        path.push(current_id);
+        node = context.node_store.get(&current_id).unwrap();
 
-        current_player_num = (current_player_num + 1) % player_count;
+        if T::TRACKS_REPETITION {
+            history.push(T::clone(&node.game_state));
+        }
+        if T::TRACKS_REPETITION && T::is_drawn_by_repetition(&history) {
+            // the draw depends on the path, so it is a tie for this playoff only and the node stays open
+            let tie = tie_for(current_player_num == 0);
+            let node = context.node_store.get_mut(&current_id).unwrap();
+            node.score_balance += tie;
+            node.sum_of_squares += tie * tie;
+            node.visited_amount += 1;
+            return (current_id, Some(tie));
+        }
+
+        current_player_num = (current_player_num + 1) % PLAYER_COUNT;
+        depth += 1;
     }
 
-    backtrack_from_leaf(current_id, context, buf);
+    (current_id, None)
+}
+
+fn remove_virtual_loss<T: MonteCarloGame>(path: &[MCNodeId<T>], virtual_loss: f64, context: &mut MCContext<T>) {
+    for id in path {
+        let node = context.node_store.get_mut(id).unwrap();
+        node.visited_amount -= 1;
+        node.score_balance += virtual_loss;
+    }
+}
+
+// result of random play from state, for the player who moved into state. tie is the score of a tie for that player
+fn random_rollout<T: MonteCarloGame>(state: &T, tie: f64, context: &MCContext<T>) -> f64 {
+    let mut rng = context.rng.borrow_mut();
+    let mut state = state.clone();
+    // the first move of the rollout is made by the opponent
+    let mut sign = -1.0;
+    loop {
+        let Some(m) = state.random_move(rng.deref_mut()) else {
+            // sign belongs to the player without moves, the result is for the one who moved last
+            return match state.no_moves_result() {
+                Some(Winner::WIN) => -sign,
+                Some(Winner::TIE) => tie,
+                None => 0.0,
+            };
+        };
+        let (next, winner) = state.make_move(&m).unwrap();
+        match winner {
+            Some(Winner::WIN) => return sign,
+            Some(Winner::TIE) => return tie,
+            None => {}
+        }
+        state = next;
+        sign = -sign;
+    }
 }
 
 #[inline(never)]
-fn new_node_entry<T: MonteCarloGame>(parent_id: ArenaHandle<MCNode<T>>, game_state: Rc<T>, winner: Option<Winner>, context: &mut MCContext<T>) -> ArenaHandle<MCNode<T>> {
-    let (is_leaf, initial_score) = compute_initial_score(winner);
+fn new_node_entry<T: MonteCarloGame>(parent_id: ArenaHandle<MCNode<T>>, game_state: Rc<T>, winner: Option<Winner>, tie_value: f64, context: &mut MCContext<T>) -> ArenaHandle<MCNode<T>> {
+    let (is_leaf, initial_score) = compute_initial_score(winner, tie_value);
     let moves = if !is_leaf {
         let moves = game_state.moves().into_iter()
             .map(|mov| (MCNodeId::invalid(), mov));
@@ -200,6 +468,7 @@ fn new_node_entry<T: MonteCarloGame>(parent_id: ArenaHandle<MCNode<T>>, game_sta
         game_state,
         visited_amount: 1,
         score_balance: initial_score,
+        sum_of_squares: initial_score * initial_score,
         completely_computed: is_leaf,
     };
 
@@ -208,7 +477,7 @@ fn new_node_entry<T: MonteCarloGame>(parent_id: ArenaHandle<MCNode<T>>, game_sta
 }
 
 #[inline(never)]
-fn select_next<T: MonteCarloGame>(parent: &MCNode<T>, moves: &[(MCNodeId<T>, T::MOVE)], context: &MCContext<T>, c: f64) -> Option<usize> {
+fn select_next<T: MonteCarloGame>(parent: &MCNode<T>, moves: &[(MCNodeId<T>, T::MOVE)], context: &MCContext<T>, c: f64, policy: Policy) -> Option<usize> {
     let mut existing = bumpalo::collections::Vec::with_capacity_in(moves.len(), &context.tmp_buf);
     let mut not_existing = bumpalo::collections::Vec::with_capacity_in(moves.len(), &context.tmp_buf);
 
@@ -233,10 +502,7 @@ fn select_next<T: MonteCarloGame>(parent: &MCNode<T>, moves: &[(MCNodeId<T>, T::
     let mut scores = bumpalo::collections::Vec::with_capacity_in(existing.len(), &context.tmp_buf);
     let mut highest_score = 0.0;
     for node in existing {
-        let visited = node.visited_amount as f64;
-        let win_score= node.score_balance;
-        // may introduce nan if p_score is negative
-        let score = (win_score / visited) + (p_score / visited).sqrt();
+        let score = ucb_score(node, p_score, policy);
         let score = if score < 0.0 {
             0.0
         } else {
@@ -253,16 +519,33 @@ fn select_next<T: MonteCarloGame>(parent: &MCNode<T>, moves: &[(MCNodeId<T>, T::
     scores.iter().enumerate().find_map(|(i, s)| (*s <= rng_value).then_some(i))
 }
 
-fn compute_initial_score(win_state: Option<Winner>) -> (bool, f64) {
+// may introduce nan if p_score is negative
+fn ucb_score<T: MonteCarloGame>(node: &MCNode<T>, p_score: f64, policy: Policy) -> f64 {
+    let visited = node.visited_amount as f64;
+    let mean = node.score_balance / visited;
+    match policy {
+        Policy::Ucb1 => mean + (p_score / visited).sqrt(),
+        Policy::Ucb1Tuned => {
+            // the bound of 1/4 on the variance holds for rewards in [0, 1], scores are in [-1, 1], so the
+            // variance is taken of the halved scores and the exploration term is doubled again
+            let variance = (node.sum_of_squares / visited - mean * mean) / 4.0;
+            let variance_bound = variance + (2.0 * p_score / visited).sqrt();
+            mean + 2.0 * (p_score / visited * variance_bound.min(0.25)).sqrt()
+        }
+    }
+}
+
+fn compute_initial_score(win_state: Option<Winner>, tie_value: f64) -> (bool, f64) {
     match win_state {
         None => (false, 0.0),
-        Some(Winner::TIE) => (true, 0.0),
+        Some(Winner::TIE) => (true, tie_value),
         Some(Winner::WIN) => (true, 1.0)
     }
 }
 
+// visited makes sure a node reachable from the leaf on several paths is only updated once
 #[inline(never)]
-fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCContext<T>, buf: &mut Vec<(MCNodeId<T>, f64, bool)>) {
+fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, leaf_score: f64, backup: Backup, context: &mut MCContext<T>, buf: &mut Vec<(MCNodeId<T>, f64, bool)>, visited: &mut FxHashSet<MCNodeId<T>>) {
     fn compute_completely_computed<T: MonteCarloGame>(node: &MCNode<T>, context: &MCContext<T>) -> bool {
         if let Some(moves) = context.move_store.get(&node.moves) {
             moves.iter()
@@ -272,37 +555,82 @@ fn backtrack_from_leaf<T: MonteCarloGame>(leaf: MCNodeId<T>, context: &mut MCCon
             true
         }
     }
+    // mean of the best child for the player to move at node, negated for the player who moved into it. None
+    // until every move has been tried, the best of a few children is too pessimistic for the opponent
+    fn minimax_score<T: MonteCarloGame>(node: &MCNode<T>, context: &MCContext<T>) -> Option<f64> {
+        let mut best = None::<f64>;
+        for (id, _) in context.move_store.get(&node.moves)? {
+            let child = context.node_store.get(id).filter(|child| child.visited_amount > 0)?;
+            let mean = child.score_balance / child.visited_amount as f64;
+            best = Some(best.map_or(mean, |best| best.max(mean)));
+        }
+        best.map(|best| -best)
+    }
     buf.clear();
+    visited.clear();
+    visited.insert(leaf);
     {
         let leaf = context.node_store.get_mut(&leaf).unwrap();
         // queue immediate predecessors
-        buf.extend(leaf.predecessors.iter().cloned().map(|pred| (pred, leaf.score_balance, true)));
+        buf.extend(leaf.predecessors.iter().cloned().map(|pred| (pred, leaf_score, true)));
     };
     let initial_length = buf.len();
     for i in 0..initial_length {
         let (node, score, _) = buf[i].clone();
+        if !visited.insert(node) {
+            continue;
+        }
         let second_level = context.node_store.get(&node).unwrap();
         let new_cc = compute_completely_computed(second_level, context);
+        let minimax = minimax_score(second_level, context);
         let second_level = context.node_store.get_mut(&node).unwrap();
         second_level.completely_computed |= new_cc;
-        second_level.score_balance -= score;
         second_level.visited_amount += 1;
+        match (backup, minimax) {
+            (Backup::Minimax, Some(best)) => {
+                second_level.score_balance = best * second_level.visited_amount as f64;
+                second_level.sum_of_squares += best * best;
+            }
+            _ => {
+                second_level.score_balance -= score;
+                second_level.sum_of_squares += score * score;
+            }
+        }
+        // the predecessors get the new value of the node and not the leaf score it replaced
+        let score = match (backup, minimax) {
+            (Backup::Minimax, Some(best)) => -best,
+            _ => score,
+        };
         buf.extend(second_level.predecessors.iter().cloned().map(|pred| (pred, score, second_level.completely_computed)));
     }
     buf.drain(0..initial_length);
 
     while let Some((next, mut score, check_cc)) = buf.pop() {
+        if !visited.insert(next) {
+            continue;
+        }
         let node = context.node_store.get(&next).unwrap();
         let new_cc = if check_cc {
             compute_completely_computed(node, context)
         } else {
             false
         };
+        let minimax = minimax_score(node, context);
         let node = context.node_store.get_mut(&next).unwrap();
         node.completely_computed |= new_cc;
-        score /= node.moves.len() as f64;
-        node.score_balance += score;
         node.visited_amount += 1;
+        match (backup, minimax) {
+            (Backup::Minimax, Some(best)) => {
+                node.score_balance = best * node.visited_amount as f64;
+                node.sum_of_squares += best * best;
+                score = best;
+            }
+            _ => {
+                score /= node.moves.len() as f64;
+                node.score_balance += score;
+                node.sum_of_squares += score * score;
+            }
+        }
         buf.extend(node.predecessors.iter().cloned().map(|pred| (pred, -score, node.completely_computed)))
     }
 }
@@ -311,7 +639,10 @@ impl <T: MonteCarloGame> CompactPred<T> {
     fn push(&mut self, id: MCNodeId<T>) {
         match self {
             CompactPred::LessThanThree([id0, id1]) => {
-                if *id1 == MCNodeId::invalid() {
+                // the root starts without predecessors, only a game that can repeat positions links back to it
+                if *id0 == MCNodeId::invalid() {
+                    *id0 = id;
+                } else if *id1 == MCNodeId::invalid() {
                     *id1 = id;
                 } else {
                     let content = vec![id0.clone(), id1.clone(), id];
@@ -341,10 +672,380 @@ impl <T: MonteCarloGame> CompactPred<T> {
 }
 
 impl<T: MonteCarloGame> MCContext<T> {
+    // drops the whole tree, the game states are kept in unused_rcs for the next nodes. The nodes are
+    // dropped first, so the states in mappings are the only remaining references
+    pub fn reset(&mut self) {
+        self.allocated_nodes = 0;
+        self.node_store.purge();
+        self.move_store.clear();
+        self.unused_rcs.reserve(self.mappings.len());
+        // FxHashMap hashes without a random state, so the drain order and with it seeded searches are reproducible
+        self.unused_rcs.extend(self.mappings.drain().map(|(state, _)| state));
+    }
+
+    // removes the least visited leaves until at most target nodes are left. The root and its children are
+    // kept, so a move can still be picked. The move slices of evicted nodes are reused by the next nodes
+    fn evict_leaves(&mut self, root: MCNodeId<T>, target: usize) {
+        let mut node_count = self.node_store.len();
+        while node_count > target {
+            // walks node_store and not mappings, without transpositions not every node is mapped
+            let mut leaves = self.node_store.iter()
+                .filter(|(id, _)| *id != root)
+                .filter(|(_, node)| node.predecessors.iter().all(|pred| *pred != root))
+                .filter(|(_, node)| self.move_store.get(&node.moves)
+                    .is_none_or(|moves| moves.iter().all(|(id, _)| self.node_store.get(id).is_none())))
+                .map(|(id, node)| (node.visited_amount, id))
+                .collect::<Vec<_>>();
+            if leaves.is_empty() {
+                break;
+            }
+            leaves.sort_by_key(|(visits, _)| *visits);
+            for (_, id) in leaves.into_iter().take(node_count - target) {
+                self.evict(id);
+                node_count -= 1;
+            }
+        }
+    }
+
+    // only valid for nodes without successors, no other node has them as predecessor
+    fn evict(&mut self, id: MCNodeId<T>) {
+        let node = self.node_store.remove(&id).expect("evicted node does not exist");
+        self.move_store.remove(&node.moves);
+        for pred in node.predecessors.iter() {
+            let Some(pred_moves) = self.node_store.get(pred).map(|pred| pred.moves.clone()) else { continue };
+            let slots = self.move_store.get_mut(&pred_moves).into_iter().flatten();
+            slots.filter(|(slot, _)| *slot == id).for_each(|(slot, _)| *slot = MCNodeId::invalid());
+        }
+        let state = match self.mappings.get(&*node.game_state) {
+            Some(mapped) if *mapped == id => self.mappings.remove_entry(&*node.game_state).unwrap().0,
+            // a duplicate of a mapped position, the node holds the only reference to its state
+            _ => node.game_state.clone(),
+        };
+        drop(node);
+        self.unused_rcs.push(state);
+    }
+
     fn alloc_node(&mut self, node: MCNode<T>) -> MCNodeId<T> {
         let node_game = node.game_state.clone();
+        self.allocated_nodes += 1;
         let id = self.node_store.insert(node);
-        self.mappings.insert(node_game, id.clone());
+        // a position that is already mapped can only come up again without transpositions
+        self.mappings.entry(node_game).or_insert(id.clone());
         id
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use rand::SeedableRng;
+    use rustc_hash::FxHashSet;
+    use crate::ai_infra::{GamePlayer, GameStrategy};
+    use crate::line_four_8x8::LineFour8x8;
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::monte_carlo_game::conformance::{Pendulum, TakeAway};
+    use crate::monte_carlo_v2::arena::Arena;
+    use crate::monte_carlo_v2::impl4::{backtrack_from_leaf, Backup, playoff, remove_virtual_loss, select_move, ucb_score, CompactPred, MCContext, MCNode, MCNodeId, MonteCarloConfigV2I4, MonteCarloV2I4, Policy, RolloutPolicy, SearchParams};
+    use crate::monte_carlo_v2::moves_buffer::{SliceArena, SliceHandle};
+    use crate::multi_score_reducer::{NodeLimitFactory, Unlimited};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+
+    const SEARCH: SearchParams = SearchParams {
+        tie_value: 0.5,
+        policy: Policy::Ucb1,
+        rollout_policy: RolloutPolicy::FullExpand,
+        virtual_loss: 0.0,
+        paths_in_flight: 1,
+        backup: Backup::Average,
+    };
+
+    fn empty_context<G: MonteCarloGame>(max_nodes: Option<usize>, use_transpositions: bool) -> MCContext<G> {
+        MCContext {
+            mappings: Default::default(),
+            node_store: Arena::new(),
+            unused_rcs: vec![],
+            move_store: SliceArena::new(),
+            max_nodes,
+            use_transpositions,
+            allocated_nodes: 0,
+            tmp_buf: Default::default(),
+            rng: RefCell::new(rand::rngs::SmallRng::seed_from_u64(0)),
+        }
+    }
+
+    // a node without moves, for building trees by hand
+    fn test_node<G: MonteCarloGame>(state: G, preds: [MCNodeId<G>; 2], visits: u64, score: f64) -> MCNode<G> {
+        MCNode {
+            predecessors: CompactPred::LessThanThree(preds),
+            moves: SliceHandle::empty(),
+            game_state: Rc::new(state),
+            visited_amount: visits,
+            score_balance: score,
+            sum_of_squares: 0.0,
+            completely_computed: false,
+        }
+    }
+
+    #[test]
+    fn diamond_is_backtracked_once() {
+        let mut context = empty_context::<TicTacToe>(None, true);
+        let mut node = |state: TicTacToe, preds| context.alloc_node(test_node(state, preds, 1, 0.0));
+        // root -> a, root -> b, a -> leaf, b -> leaf
+        let invalid = MCNodeId::invalid();
+        let state = |moves: &[_]| TicTacToe::new().apply_moves(moves).unwrap().0;
+        let root = node(state(&[]), [invalid; 2]);
+        let a = node(state(&[I1]), [root, invalid]);
+        let b = node(state(&[I2]), [root, invalid]);
+        let leaf = node(state(&[I1, I5, I2]), [a, b]);
+        context.node_store.get_mut(&a).unwrap().moves = context.move_store.insert([(leaf, I2)].into_iter());
+        context.node_store.get_mut(&b).unwrap().moves = context.move_store.insert([(leaf, I1)].into_iter());
+        context.node_store.get_mut(&root).unwrap().moves = context.move_store.insert([(a, I1), (b, I2)].into_iter());
+
+        backtrack_from_leaf(leaf, 1.0, Backup::Average, &mut context, &mut Vec::new(), &mut FxHashSet::default());
+        for id in [root, a, b] {
+            assert_eq!(2, context.node_store.get(&id).unwrap().visited_amount);
+        }
+        assert_eq!(1, context.node_store.get(&leaf).unwrap().visited_amount);
+    }
+
+    #[test]
+    fn reset_recycles_states() {
+        let mut context = empty_context::<TicTacToe>(None, true);
+        let mut parent = MCNodeId::invalid();
+        for moves in [&[][..], &[I1], &[I1, I5]] {
+            let state = TicTacToe::new().apply_moves(moves).unwrap().0;
+            let id = context.alloc_node(test_node(state, [parent, MCNodeId::invalid()], 1, 0.0));
+            if let Some(m) = moves.last() {
+                context.node_store.get_mut(&parent).unwrap().moves = context.move_store.insert([(id, *m)].into_iter());
+            }
+            parent = id;
+        }
+        context.reset();
+        assert!(context.node_store.is_empty());
+        assert!(context.mappings.is_empty());
+        assert_eq!(3, context.unused_rcs.len());
+        assert!(context.unused_rcs.iter().all(|state| Rc::strong_count(state) == 1));
+    }
+
+    #[test]
+    fn virtual_losses_are_removed() {
+        let mut context = empty_context::<TicTacToe>(None, true);
+        select_move(&TicTacToe::new(), 50, &SEARCH, &Unlimited, &mut context);
+        let before = context.mappings.values()
+            .map(|id| (*id, context.node_store.get(id).map(|node| (node.visited_amount, node.score_balance)).unwrap()))
+            .collect::<Vec<_>>();
+        let root = *context.mappings.get(&TicTacToe::new()).unwrap();
+        let mut path = Vec::new();
+        playoff(root, &mut context, &SearchParams { virtual_loss: 3.0, ..SEARCH }, &Unlimited, &mut path);
+        assert!(!path.is_empty());
+        for (id, (visits, score)) in &before {
+            let node = context.node_store.get(id).unwrap();
+            let (entered, loss) = if path.contains(id) { (1, 3.0) } else { (0, 0.0) };
+            assert_eq!(visits + entered, node.visited_amount);
+            assert!((score - loss - node.score_balance).abs() < 1e-9);
+        }
+        remove_virtual_loss(&path, 3.0, &mut context);
+        for (id, (visits, score)) in &before {
+            let node = context.node_store.get(id).unwrap();
+            assert_eq!(*visits, node.visited_amount);
+            assert!((score - node.score_balance).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn eviction_keeps_tree_under_cap() {
+        let mut context = empty_context::<LineFour8x8>(Some(300), true);
+        let game = LineFour8x8::new();
+        for _ in 0..3 {
+            select_move(&game, 4000, &SEARCH, &Unlimited, &mut context);
+            assert!(context.node_store.len() <= 300);
+            assert_eq!(context.mappings.len(), context.node_store.len());
+            let live_moves = context.node_store.iter().map(|(_, node)| node.moves.len()).sum::<usize>();
+            assert_eq!(live_moves, context.move_store.len());
+            // every move slot either points to a live node that knows its predecessor or is empty
+            for id in context.mappings.values() {
+                let node = context.node_store.get(id).unwrap();
+                for (child, _) in context.move_store.get(&node.moves).unwrap() {
+                    if *child != MCNodeId::invalid() {
+                        let child = context.node_store.get(child).expect("dangling move slot");
+                        assert!(child.predecessors.iter().any(|pred| pred == id));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn eviction_without_transpositions() {
+        let mut context = empty_context::<LineFour8x8>(Some(300), false);
+        let game = LineFour8x8::new();
+        for _ in 0..3 {
+            select_move(&game, 4000, &SEARCH, &Unlimited, &mut context);
+            assert!(context.node_store.len() <= 300);
+            for (id, node) in context.node_store.iter() {
+                for (child, _) in context.move_store.get(&node.moves).unwrap() {
+                    if *child != MCNodeId::invalid() {
+                        let child = context.node_store.get(child).expect("dangling move slot");
+                        assert!(child.predecessors.iter().eq([&id]));
+                    }
+                }
+            }
+            assert!(context.mappings.values().all(|id| context.node_store.get(id).is_some()));
+            assert!(context.unused_rcs.iter().all(|state| Rc::strong_count(state) == 1));
+        }
+    }
+
+    #[test]
+    fn node_limit_stops_the_tree_growing() {
+        let mut context = empty_context::<LineFour8x8>(None, true);
+        let game = LineFour8x8::new();
+        let m = select_move(&game, 2000, &SEARCH, &NodeLimitFactory::new(Unlimited, 100), &mut context);
+        assert!(game.moves().any(|legal| legal == m));
+        assert_eq!(100, context.node_store.len());
+    }
+
+    #[test]
+    fn evicted_nodes_count_as_searched() {
+        let config = MonteCarloConfigV2I4 { num_playoffs: 500, rng_seed: Some([1; 32]), tie_value: 0.5, max_nodes: Some(50), ..MonteCarloConfigV2I4::<Unlimited>::default() };
+        let mut player = MonteCarloV2I4::strategy_of(config);
+        assert_eq!(None, GamePlayer::<LineFour8x8>::searched_nodes(&player));
+        player.make_move(&LineFour8x8::new(), None);
+        let nodes = GamePlayer::<LineFour8x8>::searched_nodes(&player).unwrap();
+        assert!(nodes > 50, "{nodes}");
+    }
+
+    #[test]
+    fn minimax_backup_takes_the_best_reply() {
+        // after x forks with 4, o blocks 7 and x wins with 6. 3 was tried before and does not win
+        let state = |moves: &[_]| TicTacToe::new().apply_moves(&[&[I1, I2, I5, I9, I4], moves].concat()).unwrap().0;
+        for (backup, expected) in [(Backup::Average, 0.25), (Backup::Minimax, 1.0)] {
+            let mut context = empty_context::<TicTacToe>(None, true);
+            let mut node = |state, pred, visits, score| context.alloc_node(test_node(state, [pred, MCNodeId::invalid()], visits, score));
+            let fork = node(state(&[]), MCNodeId::invalid(), 3, 0.0);
+            let block = node(state(&[I7]), fork, 3, 0.0);
+            let win = node(state(&[I7, I6]), block, 1, 1.0);
+            let other = node(state(&[I7, I3]), block, 2, -1.0);
+            context.node_store.get_mut(&fork).unwrap().moves = context.move_store.insert([(block, I7)].into_iter());
+            context.node_store.get_mut(&block).unwrap().moves = context.move_store.insert([(win, I6), (other, I3)].into_iter());
+
+            backtrack_from_leaf(win, 1.0, backup, &mut context, &mut Vec::new(), &mut FxHashSet::default());
+            let mean = |id| {
+                let node: &MCNode<TicTacToe> = context.node_store.get(&id).unwrap();
+                node.score_balance / node.visited_amount as f64
+            };
+            assert!((mean(block) + expected).abs() < 1e-9, "{backup:?}: {}", mean(block));
+            assert!((mean(fork) - expected).abs() < 1e-9, "{backup:?}: {}", mean(fork));
+        }
+    }
+
+    #[test]
+    fn minimax_values_reach_averaging_ancestors() {
+        // like minimax_backup_takes_the_best_reply, but the losing reply is backtracked and the position
+        // before the fork still has an untried move, so it averages the value of the fork
+        let state = |moves: &[_]| TicTacToe::new().apply_moves(&[&[I1, I2, I5, I9], moves].concat()).unwrap().0;
+        let mut context = empty_context::<TicTacToe>(None, true);
+        let mut node = |state, pred, visits, score| context.alloc_node(test_node(state, [pred, MCNodeId::invalid()], visits, score));
+        let before_fork = node(state(&[]), MCNodeId::invalid(), 1, 0.0);
+        let fork = node(state(&[I4]), before_fork, 3, 0.0);
+        let block = node(state(&[I4, I7]), fork, 3, 0.0);
+        let win = node(state(&[I4, I7, I6]), block, 1, 1.0);
+        let other = node(state(&[I4, I7, I3]), block, 2, -1.0);
+        context.node_store.get_mut(&before_fork).unwrap().moves = context.move_store.insert([(fork, I4), (MCNodeId::invalid(), I3)].into_iter());
+        context.node_store.get_mut(&fork).unwrap().moves = context.move_store.insert([(block, I7)].into_iter());
+        context.node_store.get_mut(&block).unwrap().moves = context.move_store.insert([(win, I6), (other, I3)].into_iter());
+
+        backtrack_from_leaf(other, -1.0, Backup::Minimax, &mut context, &mut Vec::new(), &mut FxHashSet::default());
+        let mean = |id| {
+            let node: &MCNode<TicTacToe> = context.node_store.get(&id).unwrap();
+            node.score_balance / node.visited_amount as f64
+        };
+        assert!((mean(fork) - 1.0).abs() < 1e-9, "{}", mean(fork));
+        // the fork is won for x, so o gets -1 split over its two moves and not the +1 of the leaf
+        assert!((mean(before_fork) + 0.25).abs() < 1e-9, "{}", mean(before_fork));
+    }
+
+    #[test]
+    fn running_out_of_moves_is_scored() {
+        let mut context = empty_context::<TakeAway>(None, true);
+        // leaving a multiple of three stones wins, make_move never reports the end
+        assert_eq!(1, select_move(&TakeAway::new(), 500, &SEARCH, &Unlimited, &mut context));
+        let end = context.mappings.iter().find(|(state, _)| state.left == 0).unwrap().1;
+        let end = context.node_store.get(end).unwrap();
+        assert!(end.completely_computed);
+        assert_eq!(end.score_balance, end.visited_amount as f64);
+    }
+
+    #[test]
+    fn repetition_ends_the_playoff() {
+        let mut context = empty_context::<Pendulum>(None, true);
+        let mut node = |state: Pendulum| {
+            let moves = context.move_store.insert([(MCNodeId::invalid(), ())].into_iter());
+            context.alloc_node(MCNode { moves, ..test_node(state, [MCNodeId::invalid(); 2], 1, 0.0) })
+        };
+        let root = node(Pendulum::new());
+        let right = node(Pendulum { left: false });
+        // the swing back reaches the root again, the playoff would go around the cycle forever
+        let mut path = Vec::new();
+        let (leaf, score) = playoff(root, &mut context, &SEARCH, &Unlimited, &mut path);
+        assert!(leaf == root && path == [right, root]);
+        // the opponent swung back, a tie is worth the negated tie value for them
+        assert_eq!(Some(-0.5), score);
+        assert!(!context.node_store.get(&root).unwrap().completely_computed);
+    }
+
+    #[test]
+    fn tie_value_is_for_the_root_player() {
+        // every line through the open cells is blocked, so the game ends in a tie one or two moves down
+        let one_left = TicTacToe::new().apply_moves(&[I1, I2, I3, I4, I6, I9, I8, I5]).unwrap().0;
+        let two_left = TicTacToe::new().apply_moves(&[I1, I2, I3, I4, I6, I9, I8]).unwrap().0;
+        for tie_value in [0.5, -0.3] {
+            for root in [one_left, two_left] {
+                let mut context = empty_context::<TicTacToe>(None, true);
+                select_move(&root, 20, &SearchParams { tie_value, ..SEARCH }, &Unlimited, &mut context);
+                let ties = context.node_store.iter().filter(|(_, node)| node.moves.len() == 0).collect::<Vec<_>>();
+                assert!(!ties.is_empty());
+                for (_, tie) in ties {
+                    // the root player moves into the odd levels
+                    let depth = tie.game_state.ply_count() - root.ply_count();
+                    let expected = if depth % 2 == 1 { tie_value } else { -tie_value };
+                    assert_eq!(expected, tie.score_balance / tie.visited_amount as f64, "depth {depth}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ucb1_tuned_explores_at_most_like_ucb1() {
+        let node = |score_balance, sum_of_squares| MCNode { sum_of_squares, ..test_node(TicTacToe::new(), [MCNodeId::invalid(); 2], 10_000, score_balance) };
+        let p_score = 2.0 * 20_000f64.ln();
+        let exploration = |node: &MCNode<TicTacToe>, policy| ucb_score(node, p_score, policy) - node.score_balance / 10_000.0;
+        // as many wins as losses is the largest variance scores in [-1, 1] can have
+        let split = node(0.0, 10_000.0);
+        assert!((exploration(&split, Policy::Ucb1Tuned) - exploration(&split, Policy::Ucb1)).abs() < 1e-9);
+        let losses = node(-10_000.0, 10_000.0);
+        assert!(exploration(&losses, Policy::Ucb1Tuned) < exploration(&losses, Policy::Ucb1));
+        assert!(exploration(&losses, Policy::Ucb1Tuned) > 0.0);
+    }
+
+    #[test]
+    fn without_transpositions_the_search_is_a_tree() {
+        let mut context = empty_context::<TicTacToe>(None, false);
+        select_move(&TicTacToe::new(), 500, &SEARCH, &Unlimited, &mut context);
+        // some positions are reached in several move orders and got a node for each
+        assert!(context.node_store.len() > context.mappings.len());
+        let root = *context.mappings.get(&TicTacToe::new()).unwrap();
+        let mut pending = vec![root];
+        let mut reached = 0;
+        while let Some(id) = pending.pop() {
+            reached += 1;
+            let node = context.node_store.get(&id).unwrap();
+            for (child, _) in context.move_store.get(&node.moves).unwrap() {
+                if let Some(child_node) = context.node_store.get(child) {
+                    assert!(child_node.predecessors.iter().eq([&id]));
+                    pending.push(*child);
+                }
+            }
+        }
+        assert_eq!(context.node_store.len(), reached);
+    }
+}
@@ -5,7 +5,82 @@ mod impl3;
 mod moves_buffer;
 mod impl4;
 
-pub use impl1::MonteCarloV2I1;
+pub use impl1::{McEvent, MonteCarloV2I1};
 pub use impl2::MonteCarloV2I2;
 pub use impl3::MonteCarloV2I3;
-pub use impl4::{MonteCarloV2I4, MonteCarloConfigV2I4};
\ No newline at end of file
+pub use impl4::{Backup, MonteCarloV2I4, MonteCarloConfigV2I4, Policy, RolloutPolicy};
+
+use crate::monte_carlo_game::{MonteCarloGame, Winner};
+
+// like the old strategies, a move that wins right away is taken without searching
+fn immediate_win<T: MonteCarloGame>(state: &T) -> Option<T::MOVE> {
+    state.moves_with_outcome()
+        .find_map(|(m, winner)| (winner == Some(Winner::WIN)).then_some(m))
+}
+
+// a search adds at most one state per playoff, without a hint of the game a tenth of that is expected
+fn map_capacity<T: MonteCarloGame>(state: &T, playoffs: usize) -> usize {
+    state.state_space_hint().map_or(playoffs / 10, |states| states.min(playoffs))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::GameStrategy;
+    use crate::line_four_8x8::LineFour8x8;
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::monte_carlo_game::conformance::self_play;
+    use std::collections::HashSet;
+    use crate::monte_carlo_v2::{immediate_win, Backup, McEvent, MonteCarloConfigV2I4, MonteCarloV2I1, MonteCarloV2I2, MonteCarloV2I3, MonteCarloV2I4, Policy, RolloutPolicy};
+    use crate::multi_score_reducer::Unlimited;
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove};
+
+    fn first_move<S: GameStrategy<TicTacToe>>(config: S::Config, game: &TicTacToe) -> TicTacToeMove {
+        S::new(config).make_move(game, None).0
+    }
+
+    #[test]
+    fn takes_immediate_win() {
+        use TicTacToeMove::*;
+        // X has I1 and I2, O threatens with I4 and I5
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5]).unwrap();
+        assert_eq!(I3, first_move::<MonteCarloV2I1>(1, &game));
+        assert_eq!(I3, first_move::<MonteCarloV2I2>(1, &game));
+        assert_eq!(I3, first_move::<MonteCarloV2I3>(1, &game));
+        let config = MonteCarloConfigV2I4 { num_playoffs: 1, rng_seed: Some([0; 32]), ..MonteCarloConfigV2I4::<Unlimited>::default() };
+        assert_eq!(I3, first_move::<MonteCarloV2I4>(config, &game));
+        // nothing is left to win on the final board
+        assert_eq!(None, immediate_win(&game.make_move(&I3).unwrap().0));
+    }
+
+    #[test]
+    fn seeded_v2i4_is_reproducible() {
+        for (seed, policy, rollout_policy, backup) in [(1, Policy::Ucb1, RolloutPolicy::FullExpand, Backup::Average), (2, Policy::Ucb1Tuned, RolloutPolicy::RandomAfter(3), Backup::Minimax)] {
+            let config = || MonteCarloConfigV2I4 { num_playoffs: 300, rng_seed: Some([seed; 32]), tie_value: 0.5, policy, rollout_policy, backup, ..MonteCarloConfigV2I4::<Unlimited>::default() };
+            let strategy = || <MonteCarloV2I4 as GameStrategy<TicTacToe>>::new(config());
+            assert_eq!(self_play::<LineFour8x8, _>(&strategy()), self_play::<LineFour8x8, _>(&strategy()));
+            assert_eq!(self_play::<TicTacToe, _>(&strategy()), self_play::<TicTacToe, _>(&strategy()));
+        }
+    }
+
+    #[test]
+    fn observed_events_describe_the_tree() {
+        let mut events = Vec::new();
+        let strategy = <MonteCarloV2I1 as GameStrategy<TicTacToe>>::new(200);
+        let m = strategy.make_move_observed(&TicTacToe::new(), &mut |event| events.push(event));
+        assert!(TicTacToe::new().make_move(&m).is_ok());
+        assert!(matches!(events[0], McEvent::Created { parent: None, .. }));
+        // every event refers to nodes created before it
+        let mut created = HashSet::new();
+        for event in &events {
+            match *event {
+                McEvent::Created { id, parent } => {
+                    assert!(parent.is_none_or(|parent| created.contains(&parent)));
+                    assert!(created.insert(id));
+                }
+                McEvent::Linked { id, parent } => assert!(created.contains(&id) && created.contains(&parent)),
+                McEvent::Visited { id, visits, .. } => assert!(created.contains(&id) && visits > 0),
+            }
+        }
+        assert!(created.len() > 1);
+    }
+}
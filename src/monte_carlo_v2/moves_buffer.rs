@@ -1,5 +1,6 @@
 use std::cmp::max;
 use std::marker::PhantomData;
+use rustc_hash::FxHashMap;
 
 pub struct SliceHandle<T> {
     chunk_idx: usize,
@@ -8,21 +9,39 @@ pub struct SliceHandle<T> {
     _data: PhantomData<T>
 }
 
-pub struct SliceArena<T>(Vec<Vec<T>>);
+pub struct SliceArena<T> {
+    chunks: Vec<Vec<T>>,
+    // removed slices by their length, an insert of the same length reuses them
+    free: FxHashMap<usize, Vec<SliceHandle<T>>>,
+    free_len: usize,
+}
 
 impl <T> SliceArena<T> {
     pub fn new() -> Self {
-        Self(vec![alloc_chunk(0)])
+        Self {
+            chunks: vec![alloc_chunk(0)],
+            free: FxHashMap::default(),
+            free_len: 0,
+        }
     }
 
     pub fn insert(&mut self, mut insert: impl Iterator<Item = T>) -> SliceHandle<T> {
-        if self.0.is_empty() {
-            self.0.push(alloc_chunk(0));
+        let (min_size, max_size) = insert.size_hint();
+        if Some(min_size) == max_size {
+            if let Some(handle) = self.free.get_mut(&min_size).and_then(Vec::pop) {
+                self.free_len -= min_size;
+                for (slot, item) in self.get_mut(&handle).unwrap().iter_mut().zip(insert) {
+                    *slot = item;
+                }
+                return handle;
+            }
+        }
+        if self.chunks.is_empty() {
+            self.chunks.push(alloc_chunk(0));
         }
-        let mut chunk_ref = self.0.last_mut().unwrap();
+        let mut chunk_ref = self.chunks.last_mut().unwrap();
         let mut starting_len = chunk_ref.len();
 
-        let min_size = insert.size_hint().0;
         if min_size <= chunk_ref.capacity() - chunk_ref.len(){
             while let Some(next) = insert.next() {
                 if chunk_ref.capacity() - chunk_ref.len() > 0 {
@@ -31,15 +50,15 @@ impl <T> SliceArena<T> {
                     let mut new_chunk = alloc_chunk(0);
                     new_chunk.extend(chunk_ref.drain(starting_len..));
                     new_chunk.extend(insert);
-                    self.0.push(new_chunk);
+                    self.chunks.push(new_chunk);
                     starting_len = 0;
-                    chunk_ref = self.0.last_mut().unwrap();
+                    chunk_ref = self.chunks.last_mut().unwrap();
                     break;
                 }
             }
             let len =  chunk_ref.len() - starting_len;
             SliceHandle {
-                chunk_idx: self.0.len() - 1,
+                chunk_idx: self.chunks.len() - 1,
                 start_idx: starting_len,
                 len,
                 _data: PhantomData,
@@ -48,9 +67,9 @@ impl <T> SliceArena<T> {
             let mut new_chunk = alloc_chunk(min_size);
             new_chunk.extend(insert);
             let nc_len = new_chunk.len();
-            self.0.push(new_chunk);
+            self.chunks.push(new_chunk);
             SliceHandle {
-                chunk_idx: self.0.len() - 1,
+                chunk_idx: self.chunks.len() - 1,
                 start_idx: 0,
                 len: nc_len,
                 _data: PhantomData
@@ -59,21 +78,62 @@ impl <T> SliceArena<T> {
     }
 
     pub fn get(&self, handle: &SliceHandle<T>) ->  Option<&[T]> {
-        self.0.get(handle.chunk_idx)
+        self.chunks.get(handle.chunk_idx)
             .and_then(|chunk| chunk.get(handle.start_idx..(handle.start_idx + handle.len)))
     }
 
     pub fn get_mut(&mut self, handle: &SliceHandle<T>) -> Option<&mut [T]> {
-        self.0.get_mut(handle.chunk_idx)
+        self.chunks.get_mut(handle.chunk_idx)
             .and_then(|chunk| chunk.get_mut(handle.start_idx..(handle.start_idx + handle.len)))
     }
 
+    // the slice is kept for the next insert of the same length, the handle must not be used afterwards
+    pub fn remove(&mut self, handle: &SliceHandle<T>) {
+        if handle.len > 0 {
+            self.free_len += handle.len;
+            self.free.entry(handle.len).or_default().push(handle.clone());
+        }
+    }
+
+    // number of stored elements, in all slices that were not removed
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(Vec::len).sum::<usize>() - self.free_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.chunks.iter().map(Vec::capacity).sum()
+    }
+
     pub(crate) fn clear(&mut self) {
-        for chunk in &mut self.0 {
+        for chunk in &mut self.chunks {
             chunk.clear();
         }
+        self.free.clear();
+        self.free_len = 0;
     }
 }
+impl <T: Clone> Clone for SliceArena<T> {
+    fn clone(&self) -> Self {
+        // the spare capacity of the chunks is used by insert, so it has to survive the copy
+        let chunks = self.chunks.iter()
+            .map(|chunk| {
+                let mut copy = Vec::with_capacity(chunk.capacity());
+                copy.extend_from_slice(chunk);
+                copy
+            })
+            .collect();
+        Self {
+            chunks,
+            free: self.free.iter().map(|(len, handles)| (*len, handles.clone())).collect(),
+            free_len: self.free_len,
+        }
+    }
+}
+
 #[inline(never)]
 fn alloc_chunk<T>(required: usize) -> Vec<T> {
     const PAGE_SIZE: usize = 4096;
@@ -81,6 +141,17 @@ fn alloc_chunk<T>(required: usize) -> Vec<T> {
     Vec::with_capacity(max(allocated_amount, required))
 }
 
+impl <T> Clone for SliceHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            chunk_idx: self.chunk_idx,
+            start_idx: self.start_idx,
+            len: self.len,
+            _data: PhantomData,
+        }
+    }
+}
+
 impl <T> SliceHandle<T> {
     pub fn empty() -> Self {
         Self {
@@ -141,4 +212,23 @@ mod test {
         assert_eq!(handle_4.chunk_idx,  1);
         assert_eq!(arena.get_mut(&handle_4), Some([1u64, 2, 3, 4].as_mut_slice()));
     }
+
+    #[test]
+    fn removed_slices_are_reused() {
+        let mut arena = SliceArena::<u64>::new();
+        let handle_1 = arena.insert([1, 2, 3].into_iter());
+        let handle_2 = arena.insert([4, 5].into_iter());
+        arena.remove(&handle_1);
+        assert_eq!(2, arena.len());
+        // other lengths and iterators without an exact length get a new slice
+        let handle_3 = arena.insert([6, 7].into_iter());
+        let handle_4 = arena.insert([8, 9, 10].into_iter().filter(|_| true));
+        assert_ne!(handle_1.start_idx, handle_3.start_idx);
+        assert_ne!(handle_1.start_idx, handle_4.start_idx);
+        let handle_5 = arena.insert([11, 12, 13].into_iter());
+        assert_eq!((handle_1.chunk_idx, handle_1.start_idx), (handle_5.chunk_idx, handle_5.start_idx));
+        assert_eq!(arena.get(&handle_5), Some([11u64, 12, 13].as_slice()));
+        assert_eq!(arena.get(&handle_2), Some([4u64, 5].as_slice()));
+        assert_eq!(10, arena.len());
+    }
 }
\ No newline at end of file
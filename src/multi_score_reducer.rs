@@ -10,6 +10,8 @@ pub trait CheckWinMonteCarloGame: MonteCarloGame {
 pub trait MultiScoreReducerFactory<G> {
     type WR<'a>: ScoreReducer + 'a where Self: 'a;
     fn create<'wr>(&'wr self, game: &'_ G) -> Self::WR<'wr>;
+    // for a playoff that was drawn without reaching a final position, like by repetition
+    fn create_drawn<'wr>(&'wr self) -> Self::WR<'wr>;
 }
 
 pub trait ScoreReducer {
@@ -27,6 +29,11 @@ pub trait ExecutionLimiter<G> {
     fn next_with_game(&mut self, child_count: usize, _game: &G) -> std::ops::ControlFlow<(), ()> {
         self.next(child_count)
     }
+    // allocated_nodes is the number of nodes the search created for the current move so far,
+    // for a new node this is called before it is allocated
+    fn next_with_stats(&mut self, child_count: usize, game: &G, _allocated_nodes: usize) -> std::ops::ControlFlow<(), ()> {
+        self.next_with_game(child_count, game)
+    }
 }
 
 pub trait GetMostExtremeSourceScore {
@@ -52,6 +59,67 @@ pub struct TwoScoreReducerExecutionLimiter<WR1, WR2> {
 #[derive(Clone)]
 pub struct TwoScoreReducer<R1, R2>(R1, R2, bool);
 
+// like TwoScoreReducerFactory for games with N players, every player gets a reducer from the same kind of factory
+#[derive(Copy, Clone)]
+pub struct NScoreReducerFactory<F, const N: usize> {
+    facs: [F; N]
+}
+
+// cycles through the reducers on successive next_score calls, for N = 2 this behaves like TwoScoreReducer
+#[derive(Clone)]
+pub struct NScoreReducer<R, const N: usize> {
+    reducers: [R; N], turn: usize
+}
+
+// never breaks, for searches that are only limited by their number of playoffs or through a NodeLimitFactory
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Unlimited;
+
+// wraps another factory, the playoff additionally breaks before the search grows beyond max_nodes nodes
+#[derive(Copy, Clone)]
+pub struct NodeLimitFactory<F> {
+    inner: F, max_nodes: usize
+}
+
+#[derive(Clone)]
+pub struct NodeLimit<EL> {
+    inner: EL, max_nodes: usize
+}
+
+// wraps another factory, every score is clamped to [min, max] so extreme rewards cannot outweigh the
+// exploration term. Limiting is left to the wrapped factory
+#[derive(Copy, Clone)]
+pub struct ClampedReducerFactory<F> {
+    inner: F, min: f64, max: f64
+}
+
+#[derive(Clone)]
+pub struct ClampedReducer<R> {
+    inner: R, min: f64, max: f64
+}
+
+// combine two limiter factories, AND breaks only once both limiters break, OR as soon as either does.
+// Scores come from the first factory
+#[derive(Copy, Clone)]
+pub struct AndLimiterFactory<F1, F2> {
+    first: F1, second: F2
+}
+
+#[derive(Copy, Clone)]
+pub struct OrLimiterFactory<F1, F2> {
+    first: F1, second: F2
+}
+
+#[derive(Clone)]
+pub struct AndLimiter<EL1, EL2> {
+    first: EL1, second: EL2
+}
+
+#[derive(Clone)]
+pub struct OrLimiter<EL1, EL2> {
+    first: EL1, second: EL2
+}
+
 pub trait WinReducerFactoryWinInit {
     type WR: WinReducer;
     fn create(&self, end_result: Winner) -> Self::WR;
@@ -116,12 +184,46 @@ impl <G: CheckWinMonteCarloGame, F1: WinReducerFactoryWinInit, F2: WinReducerFac
     type WR<'a> = TwoScoreReducer<F1::WR, F2::WR> where F1: 'a, F2: 'a;
 
     fn create<'wr>(&'wr self, game: &'_ G) -> Self::WR<'wr> {
-        let win_state = game.win_state().expect("game not in a winning state");
+        // a playoff also ends in a position without moves that make_move did not end, like a stalemate
+        let win_state = game.win_state().or_else(|| game.no_moves_result()).expect("game not in a winning state");
         let r1 = self.fac_1.create(win_state);
         let r2 = self.fac_2.create(win_state);
 
         TwoScoreReducer(r1, r2, false)
     }
+
+    fn create_drawn<'wr>(&'wr self) -> Self::WR<'wr> {
+        TwoScoreReducer(self.fac_1.create(Winner::TIE), self.fac_2.create(Winner::TIE), false)
+    }
+}
+
+impl <F, const N: usize> NScoreReducerFactory<F, N> {
+    pub fn new(facs: [F; N]) -> Self {
+        assert!(N > 0, "at least one player required");
+        Self {
+            facs,
+        }
+    }
+}
+
+impl <G: CheckWinMonteCarloGame, F: WinReducerFactoryWinInit, const N: usize> MultiScoreReducerFactory<G> for NScoreReducerFactory<F, N> {
+    type WR<'a> = NScoreReducer<F::WR, N> where F: 'a;
+
+    fn create<'wr>(&'wr self, game: &'_ G) -> Self::WR<'wr> {
+        // a playoff also ends in a position without moves that make_move did not end, like a stalemate
+        let win_state = game.win_state().or_else(|| game.no_moves_result()).expect("game not in a winning state");
+        NScoreReducer {
+            reducers: self.facs.each_ref().map(|fac| fac.create(win_state)),
+            turn: 0,
+        }
+    }
+
+    fn create_drawn<'wr>(&'wr self) -> Self::WR<'wr> {
+        NScoreReducer {
+            reducers: self.facs.each_ref().map(|fac| fac.create(Winner::TIE)),
+            turn: 0,
+        }
+    }
 }
 
 impl <G, F1, F2> MultiScoreReducerFactory<G> for TwoScoreReducerExecutionLimiterFactory<F1, F2> where TwoScoreReducerFactory<F1, F2>: MultiScoreReducerFactory<G> {
@@ -130,6 +232,10 @@ impl <G, F1, F2> MultiScoreReducerFactory<G> for TwoScoreReducerExecutionLimiter
     fn create<'wr>(&'wr self, game: &'_ G) -> Self::WR<'wr> {
         self.fac.create(game)
     }
+
+    fn create_drawn<'wr>(&'wr self) -> Self::WR<'wr> {
+        self.fac.create_drawn()
+    }
 }
 
 impl <G, WR1: WinReducer, WR2: WinReducer> ExecutionLimiter<G> for TwoScoreReducerExecutionLimiter<WR1, WR2> {
@@ -158,6 +264,174 @@ impl <G, F1: GetMostExtremeSourceScore, F2: GetMostExtremeSourceScore> Execution
     }
 }
 
+impl <G> ExecutionLimiterFactory<G> for Unlimited {
+    type EL<'a> = Unlimited;
+
+    fn create(&self) -> Unlimited {
+        Unlimited
+    }
+}
+
+impl <G> ExecutionLimiter<G> for Unlimited {
+    fn next(&mut self, _child_count: usize) -> ControlFlow<(), ()> {
+        ControlFlow::Continue(())
+    }
+}
+
+impl <F> NodeLimitFactory<F> {
+    pub fn new(inner: F, max_nodes: usize) -> Self {
+        Self {
+            inner,
+            max_nodes,
+        }
+    }
+}
+
+impl <G, F: MultiScoreReducerFactory<G>> MultiScoreReducerFactory<G> for NodeLimitFactory<F> {
+    type WR<'a> = F::WR<'a> where Self: 'a;
+
+    fn create<'wr>(&'wr self, game: &'_ G) -> Self::WR<'wr> {
+        self.inner.create(game)
+    }
+
+    fn create_drawn<'wr>(&'wr self) -> Self::WR<'wr> {
+        self.inner.create_drawn()
+    }
+}
+
+impl <G, F: ExecutionLimiterFactory<G>> ExecutionLimiterFactory<G> for NodeLimitFactory<F> {
+    type EL<'a> = NodeLimit<F::EL<'a>> where Self: 'a;
+
+    fn create(&self) -> Self::EL<'_> {
+        NodeLimit {
+            inner: self.inner.create(),
+            max_nodes: self.max_nodes,
+        }
+    }
+}
+
+impl <G, EL: ExecutionLimiter<G>> ExecutionLimiter<G> for NodeLimit<EL> {
+    fn next(&mut self, child_count: usize) -> ControlFlow<(), ()> {
+        self.inner.next(child_count)
+    }
+
+    fn next_with_game(&mut self, child_count: usize, game: &G) -> ControlFlow<(), ()> {
+        self.inner.next_with_game(child_count, game)
+    }
+
+    fn next_with_stats(&mut self, child_count: usize, game: &G, allocated_nodes: usize) -> ControlFlow<(), ()> {
+        if allocated_nodes >= self.max_nodes {
+            ControlFlow::Break(())
+        } else {
+            self.inner.next_with_stats(child_count, game, allocated_nodes)
+        }
+    }
+}
+
+impl <F> ClampedReducerFactory<F> {
+    pub fn new(inner: F, min: f64, max: f64) -> Self {
+        assert!(min <= max, "empty score range");
+        Self {
+            inner,
+            min,
+            max,
+        }
+    }
+}
+
+impl <G, F: MultiScoreReducerFactory<G>> MultiScoreReducerFactory<G> for ClampedReducerFactory<F> {
+    type WR<'a> = ClampedReducer<F::WR<'a>> where Self: 'a;
+
+    fn create<'wr>(&'wr self, game: &'_ G) -> Self::WR<'wr> {
+        ClampedReducer {
+            inner: self.inner.create(game),
+            min: self.min,
+            max: self.max,
+        }
+    }
+
+    fn create_drawn<'wr>(&'wr self) -> Self::WR<'wr> {
+        ClampedReducer {
+            inner: self.inner.create_drawn(),
+            min: self.min,
+            max: self.max,
+        }
+    }
+}
+
+impl <G, F: ExecutionLimiterFactory<G>> ExecutionLimiterFactory<G> for ClampedReducerFactory<F> {
+    type EL<'a> = F::EL<'a> where Self: 'a;
+
+    fn create(&self) -> Self::EL<'_> {
+        self.inner.create()
+    }
+}
+
+impl <R: ScoreReducer> ScoreReducer for ClampedReducer<R> {
+    fn next_score(&mut self, child_count: usize) -> f64 {
+        self.inner.next_score(child_count).clamp(self.min, self.max)
+    }
+}
+
+// both limiters are asked on every call, since they may keep state like a deteriorating score
+macro_rules! composed_limiter {
+    ($factory: ident, $limiter: ident, $combine: expr) => {
+        impl <F1, F2> $factory<F1, F2> {
+            pub fn new(first: F1, second: F2) -> Self {
+                Self {
+                    first,
+                    second,
+                }
+            }
+        }
+
+        impl <G, F1: MultiScoreReducerFactory<G>, F2> MultiScoreReducerFactory<G> for $factory<F1, F2> {
+            type WR<'a> = F1::WR<'a> where Self: 'a;
+
+            fn create<'wr>(&'wr self, game: &'_ G) -> Self::WR<'wr> {
+                self.first.create(game)
+            }
+
+            fn create_drawn<'wr>(&'wr self) -> Self::WR<'wr> {
+                self.first.create_drawn()
+            }
+        }
+
+        impl <G, F1: ExecutionLimiterFactory<G>, F2: ExecutionLimiterFactory<G>> ExecutionLimiterFactory<G> for $factory<F1, F2> {
+            type EL<'a> = $limiter<F1::EL<'a>, F2::EL<'a>> where Self: 'a;
+
+            fn create(&self) -> Self::EL<'_> {
+                $limiter {
+                    first: self.first.create(),
+                    second: self.second.create(),
+                }
+            }
+        }
+
+        impl <G, EL1: ExecutionLimiter<G>, EL2: ExecutionLimiter<G>> ExecutionLimiter<G> for $limiter<EL1, EL2> {
+            fn next(&mut self, child_count: usize) -> ControlFlow<(), ()> {
+                $combine(self.first.next(child_count), self.second.next(child_count))
+            }
+
+            fn next_with_game(&mut self, child_count: usize, game: &G) -> ControlFlow<(), ()> {
+                $combine(self.first.next_with_game(child_count, game), self.second.next_with_game(child_count, game))
+            }
+
+            fn next_with_stats(&mut self, child_count: usize, game: &G, allocated_nodes: usize) -> ControlFlow<(), ()> {
+                let first = self.first.next_with_stats(child_count, game, allocated_nodes);
+                $combine(first, self.second.next_with_stats(child_count, game, allocated_nodes))
+            }
+        }
+    };
+}
+
+composed_limiter!(AndLimiterFactory, AndLimiter, |first: ControlFlow<()>, second: ControlFlow<()>| {
+    if first.is_break() && second.is_break() { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+});
+composed_limiter!(OrLimiterFactory, OrLimiter, |first: ControlFlow<()>, second: ControlFlow<()>| {
+    if first.is_break() || second.is_break() { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+});
+
 impl <R1: WinReducer, R2: WinReducer> ScoreReducer for TwoScoreReducer<R1, R2> {
     fn next_score(&mut self, child_count: usize) -> f64 {
         self.2 = !self.2;
@@ -169,4 +443,99 @@ impl <R1: WinReducer, R2: WinReducer> ScoreReducer for TwoScoreReducer<R1, R2> {
             self.1.get_and_deteriorate(child_count)
         }
     }
-}
\ No newline at end of file
+}
+impl <R: WinReducer, const N: usize> ScoreReducer for NScoreReducer<R, N> {
+    fn next_score(&mut self, child_count: usize) -> f64 {
+        self.turn = (self.turn + 1) % N;
+        for (i, reducer) in self.reducers.iter_mut().enumerate() {
+            if i != self.turn {
+                reducer.deteriorate(child_count);
+            }
+        }
+        self.reducers[self.turn].get_and_deteriorate(child_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::monte_carlo_win_reducer::WinFactorReduceFactory;
+    use crate::multi_score_reducer::{AndLimiterFactory, ClampedReducerFactory, ExecutionLimiter, ExecutionLimiterFactory, MultiScoreReducerFactory, NodeLimitFactory, NScoreReducerFactory, OrLimiterFactory, ScoreReducer, TwoScoreReducerFactory, WinRewardInit};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+
+    // continues for the given number of calls, then breaks
+    struct BreakAfter(usize);
+
+    impl ExecutionLimiterFactory<TicTacToe> for BreakAfter {
+        type EL<'a> = BreakAfter;
+
+        fn create(&self) -> BreakAfter {
+            BreakAfter(self.0)
+        }
+    }
+
+    impl ExecutionLimiter<TicTacToe> for BreakAfter {
+        fn next(&mut self, _child_count: usize) -> ControlFlow<(), ()> {
+            match self.0.checked_sub(1) {
+                Some(remaining) => {
+                    self.0 = remaining;
+                    ControlFlow::Continue(())
+                }
+                None => ControlFlow::Break(()),
+            }
+        }
+    }
+
+    fn calls_until_break(limiter: &impl ExecutionLimiterFactory<TicTacToe>, allocated_nodes: usize) -> usize {
+        let mut limiter = limiter.create();
+        (0..100).take_while(|_| limiter.next_with_stats(1, &TicTacToe::new(), allocated_nodes).is_continue()).count()
+    }
+
+    #[test]
+    fn composed_limiters() {
+        assert_eq!(2, calls_until_break(&OrLimiterFactory::new(BreakAfter(2), BreakAfter(4)), 0));
+        assert_eq!(4, calls_until_break(&AndLimiterFactory::new(BreakAfter(2), BreakAfter(4)), 0));
+        // the node count reaches limiters nested in a composition
+        let node_limited = OrLimiterFactory::new(BreakAfter(50), NodeLimitFactory::new(BreakAfter(50), 10));
+        assert_eq!(50, calls_until_break(&node_limited, 9));
+        assert_eq!(0, calls_until_break(&node_limited, 10));
+    }
+
+    #[test]
+    fn two_players_match_two_score_reducer() {
+        let (won, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5, I3]).unwrap();
+        let reduce = WinFactorReduceFactory { by: 0.5 };
+        let init1 = WinRewardInit::new(1.0, 0.5, reduce);
+        let init2 = WinRewardInit::new(-1.0, 0.5, reduce);
+        let two = TwoScoreReducerFactory::new(init1, init2);
+        let n = NScoreReducerFactory::new([init1, init2]);
+        let mut two = MultiScoreReducerFactory::<TicTacToe>::create(&two, &won);
+        let mut n = MultiScoreReducerFactory::<TicTacToe>::create(&n, &won);
+        for child_count in [3, 1, 4, 1, 5] {
+            assert_eq!(two.next_score(child_count), n.next_score(child_count));
+        }
+    }
+
+    #[test]
+    fn cycles_through_players() {
+        let (won, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5, I3]).unwrap();
+        let inits = [1.0, 2.0, 3.0].map(|on_win| WinRewardInit::new(on_win, 0.0, WinFactorReduceFactory { by: 0.5 }));
+        let fac = NScoreReducerFactory::new(inits);
+        let mut reducer = MultiScoreReducerFactory::<TicTacToe>::create(&fac, &won);
+        let scores = (0..4).map(|_| reducer.next_score(1)).collect::<Vec<_>>();
+        // every call halves all scores, the first call belongs to the second player
+        assert_eq!(vec![2.0, 1.5, 0.25, 0.25], scores);
+    }
+
+    #[test]
+    fn clamped_scores() {
+        let (won, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5, I3]).unwrap();
+        let reduce = WinFactorReduceFactory { by: 0.5 };
+        let fac = TwoScoreReducerFactory::new(WinRewardInit::new(10.0, 0.0, reduce), WinRewardInit::new(-10.0, 0.0, reduce));
+        let mut clamped = MultiScoreReducerFactory::<TicTacToe>::create(&ClampedReducerFactory::new(fac, -2.0, 3.0), &won);
+        let scores = (0..6).map(|_| clamped.next_score(1)).collect::<Vec<_>>();
+        // the unclamped scores are -10, 5, -2.5, 1.25, -0.625, 0.3125
+        assert_eq!(vec![-2.0, 3.0, -2.0, 1.25, -0.625, 0.3125], scores);
+    }
+}
@@ -0,0 +1,152 @@
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use crate::ai_infra::GamePlayer;
+use crate::monte_carlo_game::{MoveIndex, TwoPlayer, Winner};
+use crate::monte_carlo_game_v2::MonteCarloGameND;
+
+// Moves are exchanged as newline delimited, 1-based move numbers like for PlayerInput.
+// Each turn the opponents last move(0 if there is none) is sent, followed by the board and a line containing "end",
+// the reply is the move number to play. Once the game is over "P1 won", "P2 won" or "tie" is sent in place of the
+// move, followed by the final board and "end" as well, no reply is expected
+pub struct NetworkPlayer {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    // the first failure talking to the remote, afterwards the first legal move is played without asking it
+    error: Option<io::Error>,
+}
+
+impl NetworkPlayer {
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let writer = TcpStream::connect(addr)?;
+        let reader = BufReader::new(writer.try_clone()?);
+        Ok(Self {
+            reader,
+            writer,
+            error: None,
+        })
+    }
+
+    // why the remote is no longer asked for moves, None while the connection works
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    fn read_move_num(&mut self) -> io::Result<Option<u32>> {
+        let mut s = String::with_capacity(10);
+        let read = self.reader.read_line(&mut s)?;
+        if read == 0 {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "connection closed by remote"));
+        }
+        Ok(s.trim().parse().ok().filter(|num| *num > 0))
+    }
+
+    fn request_move<G: MonteCarloGameND>(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> io::Result<G::MOVE> where G::MOVE: TryFrom<u32> + MoveIndex {
+        let enemy_move = enemy_move.map_or(0, |m| m.to_index() + 1);
+        write!(self.writer, "{enemy_move}\n{game:?}\nend\n")?;
+        loop {
+            let m = self.read_move_num()?
+                .and_then(|num| G::MOVE::try_from(num - 1).ok())
+                .filter(|m| game.moves().into_iter().any(|it| it == *m));
+            match m {
+                Some(m) => break Ok(m),
+                None => writeln!(self.writer, "invalid move")?,
+            }
+        }
+    }
+}
+
+impl <G: MonteCarloGameND> GamePlayer<G> for NetworkPlayer where G::MOVE: TryFrom<u32> + MoveIndex {
+    fn make_move(&mut self, game: &G, enemy_move: Option<G::MOVE>) -> G::MOVE {
+        if self.error.is_none() {
+            match self.request_move(game, enemy_move) {
+                Ok(m) => return m,
+                Err(e) => {
+                    log::warn!("lost the remote player, playing the first legal move from now on: {e}");
+                    self.error = Some(e);
+                }
+            }
+        }
+        game.moves().into_iter().next().expect("no legal move")
+    }
+
+    fn finish(&mut self, winner: Winner, last_mover: TwoPlayer, game: &G) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = match winner {
+            Winner::WIN => format!("{last_mover:?} won"),
+            Winner::TIE => "tie".to_string(),
+        };
+        if let Err(e) = write!(self.writer, "{result}\n{game:?}\nend\n") {
+            self.error = Some(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use crate::ai_infra::GamePlayer;
+    use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+    use crate::net::NetworkPlayer;
+    use crate::tic_tac_toe::TicTacToe;
+    use crate::tic_tac_toe::TicTacToeMove::*;
+
+    // the lines the remote got up to and including the next "end"
+    fn read_message(reader: &mut impl BufRead) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end().to_string();
+            let end = line == "end";
+            lines.push(line);
+            if end {
+                break lines;
+            }
+        }
+    }
+
+    #[test]
+    fn moves_and_result_are_exchanged() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let remote = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let turn = read_message(&mut reader);
+            // I1 is taken already, the remote is told so and answers again
+            writeln!(stream, "1").unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!("invalid move", line.trim_end());
+            writeln!(stream, "5").unwrap();
+            (turn, read_message(&mut reader))
+        });
+        let mut player = NetworkPlayer::connect(addr).unwrap();
+        let (game, _) = TicTacToe::new().apply_moves(&[I1]).unwrap();
+        assert_eq!(I5, player.make_move(&game, Some(I1)));
+        let (game, _) = game.apply_moves(&[I5, I2, I4, I3]).unwrap();
+        GamePlayer::finish(&mut player, Winner::WIN, TwoPlayer::P1, &game);
+        let (turn, result) = remote.join().unwrap();
+        assert_eq!("1", turn[0]);
+        assert_eq!("P1 won", result[0]);
+        assert_eq!(format!("{game:?}"), result[1..result.len() - 1].join("\n"));
+        assert!(player.error().is_none());
+    }
+
+    #[test]
+    fn closed_connection_is_no_panic() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let remote = thread::spawn(move || drop(listener.accept().unwrap()));
+        let mut player = NetworkPlayer::connect(addr).unwrap();
+        remote.join().unwrap();
+        let game = TicTacToe::new();
+        assert!(game.moves().into_iter().any(|m| m == player.make_move(&game, None)));
+        assert!(player.error().is_some());
+        GamePlayer::finish(&mut player, Winner::TIE, TwoPlayer::P1, &game);
+    }
+}
@@ -55,6 +55,15 @@ impl MonteLimit {
     pub const fn times(times: u32) -> Self { Self::Times { times } }
 }
 
+impl std::fmt::Display for MonteLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonteLimit::Duration { millis } => write!(f, "{millis}ms"),
+            MonteLimit::Times { times } => write!(f, "{times} playoffs"),
+        }
+    }
+}
+
 macro_rules! monte_carlo_loop {
     ($limit: expr, $operations: ident, $action: block) => {
         let mut $operations = 0.0f64;
@@ -11,7 +11,7 @@ use crate::monte_carlo_win_reducer::{WinReducer, WinReducerFactory};
 
 #[allow(dead_code)]
 pub struct MonteCarloStrategyV5<WRF: WinReducerFactory> {
-    limit: MonteLimit, c: f64, wrf: WRF, win_reward: WinReward, seed: Option<[u8; 32]>
+    limit: MonteLimit, c: f64, wrf: WRF, win_reward: WinReward, seed: Option<[u8; 32]>, max_playoff_depth: Option<usize>
 }
 
 pub struct MonteCarloCarry {
@@ -83,6 +83,16 @@ macro_rules! monte_carlo_loop {
     };
 }
 
+impl<W: WinReducerFactory> MonteCarloStrategyV5<W> {
+    // a playoff stops after depth plies from the searched position. The reducers score the undecided position
+    // like a tied game, as an estimate of its value
+    pub fn with_max_playoff_depth(mut self, depth: usize) -> Self {
+        assert!(depth > 0, "playoffs need at least one ply");
+        self.max_playoff_depth = Some(depth);
+        self
+    }
+}
+
 impl<G: MonteCarloGame + 'static, W: WinReducerFactory> GameStrategy<G> for MonteCarloStrategyV5<W> {
     type Carry = MonteCarloCarry;
     type Config = (MonteLimit, f64, W, WinReward, Option<[u8; 32]>);
@@ -94,6 +104,7 @@ impl<G: MonteCarloGame + 'static, W: WinReducerFactory> GameStrategy<G> for Mont
             wrf,
             win_reward,
             seed,
+            max_playoff_depth: None,
         }
     }
 
@@ -108,13 +119,16 @@ impl<G: MonteCarloGame + 'static, W: WinReducerFactory> GameStrategy<G> for Mont
             playoff_buf: Bump::new(),
             rng,
         });
-        let m = make_monte_carlo_move(game, &carry.allocator, &mut carry.playoff_buf, &mut carry.rng, self.limit, self.c, &self.wrf, self.win_reward);
+        let m = make_monte_carlo_move(game, &mut carry, self);
         carry.allocator.reset();
         (m, carry)
     }
 }
 
-fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: WinReducerFactory>(g: &G, bump: &Bump, tmp_buf: &mut Bump, rng: &mut impl Rng, limit: MonteLimit, c: f64, wr_factory: &W, win_reward: WinReward) -> G::MOVE {
+fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: WinReducerFactory>(g: &G, carry: &mut MonteCarloCarry, strategy: &MonteCarloStrategyV5<W>) -> G::MOVE {
+    let MonteCarloCarry { allocator: bump, playoff_buf: tmp_buf, rng } = carry;
+    let bump = &*bump;
+    let (limit, c) = (strategy.limit, strategy.c);
     let mut children = {
         let moves = g.moves().into_iter();
         let mut children = Vec::with_capacity(moves.size_hint().0);
@@ -135,7 +149,7 @@ fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: WinReducerFactory>(g: &
         } else {
             break;
         };
-        playoff(g, next, wr_factory, bump, tmp_buf, rng, c, win_reward);
+        playoff(g, next, bump, tmp_buf, rng, strategy);
     });
 
     return children
@@ -153,15 +167,16 @@ fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: WinReducerFactory>(g: &
 fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: WinReducerFactory>(
     mut g: &'a G,
     next: &'a mut MonteCarloChild<'b, G>,
-    wr_config: &W,
     bump: &'b Bump,
     tmp_buf: &mut Bump,
     rng: &mut impl Rng,
-    c: f64,
-    win_reward: WinReward
+    strategy: &MonteCarloStrategyV5<W>,
 ) {
+    let MonteCarloStrategyV5 { c, wrf: wr_config, win_reward, max_playoff_depth: max_depth, .. } = strategy;
+    let (c, win_reward, max_depth) = (*c, *win_reward, *max_depth);
     let mut path = Vec::with_capacity(30);
     let mut next = next;
+    // None if the playoff was cut at max_depth
     let winner;
     loop {
         let current = match next.1 {
@@ -183,7 +198,7 @@ fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: WinReducerFactory>(
         };
         current.visited += 1.0;
         if let Some(w) = current.winner {
-            winner = w;
+            winner = Some(w);
             current.wins += match w {
                 Winner::WIN => win_reward.on_win.0,
                 Winner::TIE => win_reward.on_tie.0,
@@ -191,6 +206,12 @@ fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: WinReducerFactory>(
             current.leaf_count += 1;
             break;
         }
+        // the node stays open, so it is not counted as a leaf
+        if max_depth.is_some_and(|max_depth| path.len() + 1 >= max_depth) {
+            winner = None;
+            current.wins += win_reward.on_tie.0;
+            break;
+        }
         g = &current.game;
 
         let child_count = current.children.len();
@@ -209,15 +230,15 @@ fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: WinReducerFactory>(
     }
 
     let (first_score, second_score) = match winner {
-        Winner::WIN => win_reward.on_win,
-        Winner::TIE => win_reward.on_tie,
+        Some(Winner::WIN) => win_reward.on_win,
+        Some(Winner::TIE) | None => win_reward.on_tie,
     };
     let (mut first_score, mut second_score) = (
         wr_config.create(first_score),
         wr_config.create(second_score)
     );
     let mut inc_first = false;
-    let mut is_leaf = true;
+    let mut is_leaf = winner.is_some();
     for (wins, leaf_count, child_count) in path.into_iter().rev() {
         *wins += if inc_first {
             second_score.deteriorate(child_count);
@@ -295,4 +316,44 @@ fn select_next<'c: 'd, 'd, 'b: 'c, G: MonteCarloGame + 'static>(
     } else {
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use crate::ai_infra::GameStrategy;
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::monte_carlo_win_reducer::WinFactorReduceFactory;
+    use crate::old_monte_carlo::monte_carlo_main::MonteLimit;
+    use crate::old_monte_carlo::monte_carlo_main3::WinReward;
+    use crate::old_monte_carlo::monte_carlo_main5::{playoff, MonteCarloChild, MonteCarloStrategyV5};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove};
+
+    fn depth(child: &MonteCarloChild<TicTacToe>) -> usize {
+        child.1.as_ref().map_or(0, |state| 1 + state.children.iter().map(depth).max().unwrap_or(0))
+    }
+
+    fn searched_depth(max_depth: Option<usize>) -> usize {
+        let (bump, mut tmp_buf) = (Bump::new(), Bump::new());
+        let mut rng = SmallRng::seed_from_u64(5);
+        let strategy = <MonteCarloStrategyV5<_> as GameStrategy<TicTacToe>>::new((MonteLimit::times(300), 1.0, WinFactorReduceFactory { by: 0.9 }, WinReward::new(0.5, 1.0, 0.0), None));
+        let strategy = match max_depth {
+            Some(max_depth) => strategy.with_max_playoff_depth(max_depth),
+            None => strategy,
+        };
+        let game = TicTacToe::new();
+        let mut child = MonteCarloChild(TicTacToeMove::I5, None);
+        for _ in 0..300 {
+            playoff(&game, &mut child, &bump, &mut tmp_buf, &mut rng, &strategy);
+        }
+        depth(&child)
+    }
+
+    #[test]
+    fn playoffs_stop_at_max_depth() {
+        assert!(searched_depth(None) > 3);
+        assert_eq!(3, searched_depth(Some(3)));
+    }
+}
@@ -15,7 +15,7 @@ use crate::multi_score_reducer::{MultiScoreReducerFactory, ScoreReducer};
 
 #[allow(dead_code)]
 pub struct MonteCarloStrategyV6<G: MonteCarloGame, WRF: MultiScoreReducerFactory<G>> {
-    limit: MonteLimit, c: f64, wrf: WRF, seed: Option<[u8; 32]>, game: PhantomData<G>
+    limit: MonteLimit, c: f64, wrf: WRF, seed: Option<[u8; 32]>, max_playoff_depth: Option<usize>, game: PhantomData<G>
 }
 
 pub struct MonteCarloCarry {
@@ -86,6 +86,16 @@ macro_rules! monte_carlo_loop {
     };
 }
 
+impl<G: MonteCarloGame, W: MultiScoreReducerFactory<G>> MonteCarloStrategyV6<G, W> {
+    // a playoff stops after depth plies from the searched position. The reducers score the undecided position
+    // like a drawn game, as an estimate of its value
+    pub fn with_max_playoff_depth(mut self, depth: usize) -> Self {
+        assert!(depth > 0, "playoffs need at least one ply");
+        self.max_playoff_depth = Some(depth);
+        self
+    }
+}
+
 impl<G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G>> GameStrategy<G> for MonteCarloStrategyV6<G, W> {
     type Carry = MonteCarloCarry;
     type Config = (MonteLimit, f64, W, Option<[u8; 32]>);
@@ -96,6 +106,7 @@ impl<G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G>> GameStrategy<G
             c,
             wrf,
             seed,
+            max_playoff_depth: None,
             game: PhantomData::default(),
         }
     }
@@ -111,13 +122,17 @@ impl<G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G>> GameStrategy<G
             playoff_buf: Bump::new(),
             rng,
         });
-        let m = make_monte_carlo_move(game, &carry.allocator, &mut carry.playoff_buf, &mut carry.rng, self.limit, self.c, &self.wrf);
+        let m = make_monte_carlo_move(game, &mut carry, self);
         carry.allocator.reset();
         (m, carry)
     }
 }
 
-fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G>>(g: &G, bump: &Bump, tmp_buf: &mut Bump, rng: &mut impl Rng, limit: MonteLimit, c: f64, wr_factory: &W) -> G::MOVE where G::MOVE: Clone{
+fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G>>(g: &G, carry: &mut MonteCarloCarry, strategy: &MonteCarloStrategyV6<G, W>) -> G::MOVE where G::MOVE: Clone{
+    let MonteCarloCarry { allocator: bump, playoff_buf: tmp_buf, rng } = carry;
+    let bump = &*bump;
+    let MonteCarloStrategyV6 { limit, c, wrf: wr_factory, max_playoff_depth: max_depth, .. } = strategy;
+    let (limit, c, max_depth) = (*limit, *c, *max_depth);
     let mut children = {
         let moves = g.moves().into_iter();
         let mut children = Vec::with_capacity(moves.size_hint().0);
@@ -140,7 +155,7 @@ fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: MultiScoreReducerFactor
             break;
         };
         let next = &mut children[next].1;
-        playoff(next, wr_factory, bump, tmp_buf, rng, c);
+        playoff(next, wr_factory, bump, tmp_buf, rng, c, max_depth);
     });
 
     return children
@@ -166,9 +181,11 @@ fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G>>(
     tmp_buf: &mut Bump,
     rng: &mut impl Rng,
     c: f64,
+    max_depth: Option<usize>,
 ) {
     let mut path = Vec::with_capacity(30);
     let mut next = next;
+    // None if the playoff was cut at max_depth
     let final_game_state;
     loop {
         let mut win_state = None;
@@ -195,10 +212,15 @@ fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G>>(
         };
         current.visited += 1.0;
         if let Some((g, w)) = win_state {
-            final_game_state = g;
+            final_game_state = Some(g);
             current.leaf_count += 1;
             break;
         }
+        // the node stays open, so it is not counted as a leaf
+        if max_depth.is_some_and(|max_depth| path.len() + 1 >= max_depth) {
+            final_game_state = None;
+            break;
+        }
 
         let child_count = current.children.len();
 
@@ -224,8 +246,8 @@ fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G>>(
         next = new;
     }
 
-    let mut score_reducer = wr_config.create(final_game_state);
-    let mut is_leaf = true;
+    let mut score_reducer = final_game_state.map_or_else(|| wr_config.create_drawn(), |game| wr_config.create(game));
+    let mut is_leaf = final_game_state.is_some();
     for (wins, leaf_count, child_count) in path.into_iter().rev() {
         *wins += score_reducer.next_score(child_count);
         *leaf_count += is_leaf as u8 as u16;
@@ -266,4 +288,58 @@ fn select_next<'c: 'd, 'd, 'b: 'c, G: MonteCarloGame + 'static>(
     } else {
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::monte_carlo_win_reducer::WinFactorReduceFactory;
+    use crate::multi_score_reducer::{MultiScoreReducerFactory, ScoreReducer, TwoScoreReducerFactory, WinRewardInit};
+    use crate::old_monte_carlo::monte_carlo_main6::{playoff, MonteCarloChild, MonteState};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove};
+
+    fn depth(child: &MonteCarloChild<TicTacToe>) -> usize {
+        match &child.0 {
+            MonteState::Computed(state) => 1 + state.children.iter().map(depth).max().unwrap_or(0),
+            MonteState::Uncomputed(_, _) => 0,
+        }
+    }
+
+    fn searched_depth(max_depth: Option<usize>) -> usize {
+        let (bump, mut tmp_buf) = (Bump::new(), Bump::new());
+        let mut rng = SmallRng::seed_from_u64(5);
+        let reduce = WinFactorReduceFactory { by: 0.9 };
+        let wrf = TwoScoreReducerFactory::new(WinRewardInit::new(0.0, 0.5, reduce), WinRewardInit::new(1.0, 0.5, reduce));
+        let game = TicTacToe::new();
+        let mut child = MonteCarloChild(MonteState::Uncomputed(TicTacToeMove::I5, &game));
+        for _ in 0..300 {
+            playoff(&mut child, &wrf, &bump, &mut tmp_buf, &mut rng, 1.0, max_depth);
+        }
+        depth(&child)
+    }
+
+    #[test]
+    fn playoffs_stop_at_max_depth() {
+        assert!(searched_depth(None) > 3);
+        assert_eq!(3, searched_depth(Some(3)));
+    }
+
+    #[test]
+    fn cut_playoffs_are_scored_as_drawn() {
+        let (bump, mut tmp_buf) = (Bump::new(), Bump::new());
+        let mut rng = SmallRng::seed_from_u64(5);
+        let reduce = WinFactorReduceFactory { by: 0.9 };
+        let wrf = TwoScoreReducerFactory::new(WinRewardInit::new(0.0, 0.5, reduce), WinRewardInit::new(1.0, 0.5, reduce));
+        let game = TicTacToe::new();
+        let mut child = MonteCarloChild(MonteState::Uncomputed(TicTacToeMove::I5, &game));
+        playoff(&mut child, &wrf, &bump, &mut tmp_buf, &mut rng, 1.0, Some(2));
+        let MonteState::Computed(state) = &child.0 else { panic!("the playoff did not expand its start") };
+        let expected = MultiScoreReducerFactory::<TicTacToe>::create_drawn(&wrf).next_score(state.children.len());
+        assert_ne!(0.0, expected);
+        assert_eq!(expected, state.wins);
+        assert_eq!(0, state.leaf_count);
+    }
+}
@@ -27,6 +27,8 @@ pub struct MonteCarloCarry {
     allocator: Bump,
     playoff_buf: Bump,
     rng: rand::rngs::SmallRng,
+    // nodes added to the tree by the last move
+    last_nodes: usize,
 }
 
 
@@ -116,14 +118,24 @@ impl<G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G> + ExecutionLimi
             allocator: Bump::with_capacity(size_of::<G>() * 50_000),
             playoff_buf: Bump::new(),
             rng,
+            last_nodes: 0,
         });
-        let m = make_monte_carlo_move(game, &carry.allocator, &mut carry.playoff_buf, &mut carry.rng, self.limit, self.c, &self.wrf);
+        let m = make_monte_carlo_move(game, &mut carry, self);
         carry.allocator.reset();
         (m, carry)
     }
+
+    fn searched_nodes(&self, carry: &Self::Carry) -> Option<u64> {
+        Some(carry.last_nodes as u64)
+    }
 }
 
-fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G> + ExecutionLimiterFactory<G>>(g: &G, bump: &Bump, tmp_buf: &mut Bump, rng: &mut impl Rng, limit: MonteLimit, c: f64, wr_factory: &W) -> G::MOVE where G::MOVE: Clone {
+fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G> + ExecutionLimiterFactory<G>>(g: &G, carry: &mut MonteCarloCarry, strategy: &MonteCarloStrategyV7<G, W>) -> G::MOVE where G::MOVE: Clone {
+    let MonteCarloCarry { allocator: bump, playoff_buf: tmp_buf, rng, last_nodes: node_count } = carry;
+    let bump = &*bump;
+    let MonteCarloStrategyV7 { limit, c, wrf: wr_factory, .. } = strategy;
+    let (limit, c) = (*limit, *c);
+    *node_count = 0;
     let mut children = {
         let moves = g.moves().into_iter();
         let mut children = Vec::with_capacity(moves.size_hint().0);
@@ -133,6 +145,7 @@ fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: MultiScoreReducerFactor
                 return m;
             }
             let s = bump.alloc(s);
+            *node_count += 1;
             let new_state = MonteCarloState::new(s, w, &bump);
             children.push((m.clone(), MonteCarloChild(MonteState::Computed(new_state))))
         }
@@ -146,7 +159,7 @@ fn make_monte_carlo_move<G: MonteCarloGame + 'static, W: MultiScoreReducerFactor
             break;
         };
         let next = &mut children[next].1;
-        playoff(next, wr_factory, bump, tmp_buf, rng, c);
+        playoff(next, wr_factory, bump, tmp_buf, rng, node_count, c);
     });
 
     let mut children = children
@@ -183,6 +196,7 @@ fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G> +
     bump: &'b Bump,
     tmp_buf: &mut Bump,
     rng: &mut impl Rng,
+    node_count: &mut usize,
     c: f64,
 ) {
     let mut el = <W as ExecutionLimiterFactory<G>>::create(wr_config);
@@ -205,6 +219,7 @@ fn playoff<'a, 'b, G: MonteCarloGame + 'static, W: MultiScoreReducerFactory<G> +
                 };
 
                 let g = &*bump.alloc(child.0);
+                *node_count += 1;
                 win_state = child.1.map(|w| (g, w));
                 let next_state = MonteCarloState::new(g, child.1, bump);
                 next.0 = MonteState::Computed(next_state);
@@ -292,4 +307,30 @@ fn select_next<'c: 'd, 'd, 'b: 'c, G: MonteCarloGame + 'static>(
     } else {
         None
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::{GamePlayer, GameStrategy};
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::monte_carlo_win_reducer::WinFactorReduceFactory;
+    use crate::multi_score_reducer::{TwoScoreReducerFactory, WinRewardInit};
+    use crate::old_monte_carlo::monte_carlo_main7::MonteCarloStrategyV7;
+    use crate::old_monte_carlo::monte_carlo_main::MonteLimit;
+    use crate::tic_tac_toe::TicTacToe;
+
+    #[test]
+    fn reports_searched_nodes() {
+        let reward = || WinRewardInit::new(1.0, 0.0, WinFactorReduceFactory { by: 0.9 });
+        let wrf = TwoScoreReducerFactory::new(reward(), reward()).limiter_from(0.0);
+        let mut player = MonteCarloStrategyV7::strategy_of((MonteLimit::times(500), 2.0, wrf, Some([4; 32])));
+        assert_eq!(None, GamePlayer::<TicTacToe>::searched_nodes(&player));
+        let m = player.make_move(&TicTacToe::new(), None);
+        let first = GamePlayer::<TicTacToe>::searched_nodes(&player).unwrap();
+        assert!(first >= 500, "{first}");
+        // the count starts over with every move
+        let (game, _) = TicTacToe::new().make_move(&m).unwrap();
+        player.make_move(&game, None);
+        let second = GamePlayer::<TicTacToe>::searched_nodes(&player).unwrap();
+        assert!(second < 2 * first, "{second}");
+    }
+}
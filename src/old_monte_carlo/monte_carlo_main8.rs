@@ -12,21 +12,92 @@ use crate::{MonteLimit};
 use crate::ai_infra::GameStrategy;
 use crate::monte_carlo_game_v2::{GameState, MonteCarloGameND};
 
-use crate::multi_score_reducer::{ExecutionLimiter, ExecutionLimiterFactory, MultiScoreReducerFactory, ScoreReducer};
+use crate::monte_carlo_win_reducer::WinFactorReduceFactory;
+use crate::multi_score_reducer::{ExecutionLimiter, ExecutionLimiterFactory, MultiScoreReducerFactory, ScoreReducer, TwoScoreReducerExecutionLimiterFactory, TwoScoreReducerFactory, WinRewardInit};
 
 #[allow(dead_code)]
 pub struct MonteCarloStrategyV8<G, WRF> {
     limit: MonteLimit,
-    c: f64,
+    c_schedule: CSchedule,
     wrf: WRF,
     seed: Option<[u8; 32]>,
+    stop_rule: StopRule,
+    selection: Selection,
+    final_select: FinalSelect,
+    focus: Option<FocusPolicy>,
     game: PhantomData<G>,
 }
 
+// Converged stops the search before the limit is reached once at least min_ops playoffs were done
+// and the most visited root move leads the second one by more than margin * total root visits
+#[derive(Copy, Clone, Debug)]
+pub enum StopRule {
+    Limit,
+    Converged { min_ops: u32, margin: f64 },
+}
+
+// Temperature samples the played move proportionally to visits^(1/temperature) instead of taking the best one
+#[derive(Copy, Clone, Debug)]
+pub enum Selection {
+    ArgMax,
+    Temperature(f64),
+}
+
+// how ArgMax picks the move: RobustMax takes the best lower confidence bound mean - c * sqrt(ln(total visits) / visits)
+#[derive(Copy, Clone, Debug)]
+pub enum FinalSelect {
+    MeanScore,
+    MostVisited,
+    RobustMax,
+}
+
+// the exploration constant over the course of a search, Linear moves from start to end as the limit is used up
+#[derive(Copy, Clone, Debug)]
+pub enum CSchedule {
+    Const(f64),
+    Linear { start: f64, end: f64 },
+}
+
+impl CSchedule {
+    // progress is the used fraction of the limit
+    pub fn at(self, progress: f64) -> f64 {
+        match self {
+            CSchedule::Const(c) => c,
+            CSchedule::Linear { start, end } => start + (end - start) * progress.clamp(0.0, 1.0),
+        }
+    }
+}
+
+// spends the first playoffs of a move on the root moves answering the enemy's last move, before the
+// normal selection takes over
+#[derive(Copy, Clone, Debug)]
+pub enum FocusPolicy {
+    // the move equal to the enemy's move, the same column in connect four
+    SameMove { playoffs: u32 },
+}
+
+// builds the config of MonteCarloStrategyV8 for two players scoring the game the same way, the winner
+// gets on_win and the loser -on_win unless a separate loss reward is set, both get on_tie on a tie
+#[derive(Copy, Clone)]
+pub struct MctsV8Builder {
+    limit: MonteLimit,
+    c: f64,
+    on_win: f64,
+    on_tie: f64,
+    on_loss: Option<f64>,
+    decay: f64,
+    threshold: f64,
+    seed: Option<[u8; 32]>,
+}
+
+pub type MctsV8Reducer = TwoScoreReducerExecutionLimiterFactory<WinRewardInit<WinFactorReduceFactory>, WinRewardInit<WinFactorReduceFactory>>;
+
 pub struct MonteCarloCarry {
     allocator: Bump,
     playoff_buf: Bump,
     rng: rand::rngs::SmallRng,
+    // nodes the last search added to the tree
+    last_nodes: usize,
 }
 
 
@@ -79,20 +150,27 @@ impl<'b, G: MonteCarloGameND> MonteCarloState<'b, G> {
     }
 }
 
+// progress is the fraction of the limit used before the current operation
 macro_rules! monte_carlo_loop {
-    ($limit: expr, $operations: ident, $action: block) => {
+    ($limit: expr, $operations: ident, $progress: ident, $action: block) => {
         let mut $operations = 0u32;
         match $limit {
             MonteLimit::Duration { millis } => {
                 let start = Instant::now();
                 let millis = Duration::from_millis(millis.get());
-                while start.elapsed() < millis {
+                loop {
+                    let elapsed = start.elapsed();
+                    if elapsed >= millis {
+                        break;
+                    }
+                    let $progress = elapsed.as_secs_f64() / millis.as_secs_f64();
                     $operations += 1;
                     $action
                 }
             }
             MonteLimit::Times { times } => {
                 while $operations < times {
+                    let $progress = f64::from($operations) / f64::from(times);
                     $operations += 1;
                     $action
                 }
@@ -102,6 +180,109 @@ macro_rules! monte_carlo_loop {
     };
 }
 
+impl<G, W> MonteCarloStrategyV8<G, W> {
+    pub fn with_stop_rule(mut self, stop_rule: StopRule) -> Self {
+        self.stop_rule = stop_rule;
+        self
+    }
+
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn with_final_select(mut self, final_select: FinalSelect) -> Self {
+        self.final_select = final_select;
+        self
+    }
+
+    pub fn with_focus(mut self, focus: Option<FocusPolicy>) -> Self {
+        self.focus = focus;
+        self
+    }
+
+    // replaces the constant c of the config
+    pub fn with_c_schedule(mut self, c_schedule: CSchedule) -> Self {
+        self.c_schedule = c_schedule;
+        self
+    }
+}
+
+impl MctsV8Builder {
+    pub fn new() -> Self {
+        Self {
+            limit: MonteLimit::duration(100),
+            c: 1.0,
+            on_win: 1.0,
+            on_tie: 0.0,
+            on_loss: None,
+            decay: 0.5,
+            threshold: 0.0001,
+            seed: None,
+        }
+    }
+
+    pub fn time_ms(mut self, millis: u64) -> Self {
+        self.limit = MonteLimit::duration(millis);
+        self
+    }
+
+    pub fn playoffs(mut self, times: u32) -> Self {
+        self.limit = MonteLimit::times(times);
+        self
+    }
+
+    pub fn exploration(mut self, c: f64) -> Self {
+        self.c = c;
+        self
+    }
+
+    pub fn win_reward(mut self, on_win: f64, on_tie: f64) -> Self {
+        self.on_win = on_win;
+        self.on_tie = on_tie;
+        self
+    }
+
+    pub fn loss_reward(mut self, on_loss: f64) -> Self {
+        self.on_loss = Some(on_loss);
+        self
+    }
+
+    // factor the reward is multiplied with for every move it is further away from the end of the game
+    pub fn decay(mut self, decay: f64) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    // the playoff stops once the decayed reward of either player drops below threshold
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn seed(mut self, seed: [u8; 32]) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn build(self) -> (MonteLimit, f64, MctsV8Reducer, Option<[u8; 32]>) {
+        let reduce = WinFactorReduceFactory { by: self.decay };
+        let on_loss = self.on_loss.unwrap_or(-self.on_win);
+        // the first reducer scores the moves of the player who lost, the second those of the winner
+        let wrf = TwoScoreReducerFactory::new(
+            WinRewardInit::new(on_loss, self.on_tie, reduce),
+            WinRewardInit::new(self.on_win, self.on_tie, reduce),
+        ).limiter_from(self.threshold);
+        (self.limit, self.c, wrf, self.seed)
+    }
+}
+
+impl Default for MctsV8Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G> + ExecutionLimiterFactory<G>> GameStrategy<G> for MonteCarloStrategyV8<G, W> {
     type Carry = MonteCarloCarry;
     type Config = (MonteLimit, f64, W, Option<[u8; 32]>);
@@ -109,9 +290,13 @@ impl<G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G> + ExecutionLi
     fn new((limit, c, wrf, seed): (MonteLimit, f64, W, Option<[u8; 32]>)) -> Self {
         Self {
             limit,
-            c,
+            c_schedule: CSchedule::Const(c),
             wrf,
             seed,
+            stop_rule: StopRule::Limit,
+            selection: Selection::ArgMax,
+            final_select: FinalSelect::MeanScore,
+            focus: None,
             game: PhantomData::default(),
         }
     }
@@ -122,18 +307,42 @@ impl<G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G> + ExecutionLi
             thread_rng().fill_bytes(&mut seed);
             SeedableRng::from_seed(seed)
         });
-        let mut carry = carry.map(|(_, c)| c).unwrap_or_else(|| MonteCarloCarry {
+        let (enemy_move, carry) = carry.unzip();
+        let mut carry = carry.unwrap_or_else(|| MonteCarloCarry {
             allocator: Bump::with_capacity(size_of::<G>() * 50_000),
             playoff_buf: Bump::new(),
             rng,
+            last_nodes: 0,
         });
-        let m = make_monte_carlo_move(game, &carry.allocator, &mut carry.playoff_buf, &mut carry.rng, self.limit, self.c, &self.wrf);
+        let m = make_monte_carlo_move(game, enemy_move.as_ref(), &carry.allocator, &mut carry.playoff_buf, &mut carry.rng, &mut carry.last_nodes, self);
         carry.allocator.reset();
         (m, carry)
     }
+
+    fn name(&self) -> String {
+        match self.c_schedule {
+            CSchedule::Const(c) => format!("MCTSv8(c={c:?}, {})", self.limit),
+            CSchedule::Linear { start, end } => format!("MCTSv8(c={start:?}..{end:?}, {})", self.limit),
+        }
+    }
+
+    fn searched_nodes(&self, carry: &Self::Carry) -> Option<u64> {
+        Some(carry.last_nodes as u64)
+    }
+
+    fn reset(&self, carry: &mut Self::Carry) {
+        carry.allocator.reset();
+        carry.playoff_buf.reset();
+        carry.last_nodes = 0;
+        if let Some(seed) = self.seed {
+            carry.rng = SeedableRng::from_seed(seed);
+        }
+    }
 }
 
-fn make_monte_carlo_move<G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G> + ExecutionLimiterFactory<G>>(g: &G, bump: &Bump, tmp_buf: &mut Bump, rng: &mut impl Rng, limit: MonteLimit, c: f64, wr_factory: &W) -> G::MOVE where G::MOVE: Clone {
+fn make_monte_carlo_move<G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G> + ExecutionLimiterFactory<G>>(g: &G, enemy_move: Option<&G::MOVE>, bump: &Bump, tmp_buf: &mut Bump, rng: &mut impl Rng, node_count: &mut usize, strategy: &MonteCarloStrategyV8<G, W>) -> G::MOVE where G::MOVE: Clone {
+    let MonteCarloStrategyV8 { limit, c_schedule, wrf: wr_factory, stop_rule, selection, final_select, focus, .. } = strategy;
+    let (limit, c_schedule, stop_rule, selection, final_select, focus) = (*limit, *c_schedule, *stop_rule, *selection, *final_select, *focus);
     let mut children = {
         let moves = g.moves().into_iter();
         let mut children = Vec::with_capacity(moves.size_hint().0);
@@ -144,17 +353,36 @@ fn make_monte_carlo_move<G: MonteCarloGameND + 'static, W: MultiScoreReducerFact
     };
     let children_len = children.len();
     let mut non_leaf_count = children.len() as u16;
-    monte_carlo_loop!(limit, operations, {
-        let next = select_next_move(children.iter().map(|(_, s)| s), operations, c);
+    *node_count = 0;
+    monte_carlo_loop!(limit, operations, progress, {
+        let c = c_schedule.at(progress);
+        if let StopRule::Converged { min_ops, margin } = stop_rule {
+            if operations > min_ops && root_converged(&children, margin) {
+                break;
+            }
+        }
+        let focused = match (focus, enemy_move) {
+            (Some(FocusPolicy::SameMove { playoffs }), Some(enemy_move)) if operations <= playoffs => children.iter()
+                .position(|(m, child)| m == enemy_move && !matches!(child, MonteCarloChild::Computed(mov) if mov.non_leaf_count == 0)),
+            _ => None,
+        };
+        let next = focused.or_else(|| select_next_move(children.iter().map(|(_, s)| s), operations, c));
         let next = if let Some(next) = next {
             next
         } else {
             break;
         };
         let next = &mut children[next].1;
-        playoff(next, g, &mut non_leaf_count, children_len, wr_factory, bump, tmp_buf, rng, c);
+        playoff(next, g, &mut non_leaf_count, children_len, wr_factory, bump, tmp_buf, rng, c, node_count);
     });
 
+    // the limit may be too tight to compute a single child, any legal move is as good as another then
+    if !children.iter().any(|(_, child)| matches!(child, MonteCarloChild::Computed(_))) {
+        let (m, _) = children.choose(rng).expect("no moves available");
+        log::debug!("no move computed, selected: {m:?}");
+        return m.clone();
+    }
+
     let mut children = children
         .into_iter()
         .filter_map(|(m, c)| if let MonteCarloChild::Computed(s) = c {
@@ -166,18 +394,53 @@ fn make_monte_carlo_move<G: MonteCarloGameND + 'static, W: MultiScoreReducerFact
     let correct_by = (-1.0) * children.iter().map(|(_m, node)| node.score).reduce(f64::min).unwrap_or(0.0);
     children.iter_mut().for_each(|(_, s)| s.score += correct_by);
 
-    let m = children.into_iter()
+    let children = children.into_iter()
         .map(|(m, s)| {
-            (m, s.visits, s.score / s.visits as f64)
+            // a playoff cut short by the limiter leaves a computed child without visits
+            (m, s.visits, s.score / s.visits.max(1) as f64)
         })
         .inspect(|(m, v, wr)| log::debug!("{m:?}({v}): {wr}"))
-        .max_by(|(_, _, wr1), (_, _, wr2)| wr1.total_cmp(&wr2))
+        .collect::<Vec<_>>();
+    let sampled = match selection {
+        Selection::Temperature(temp) if temp > 0.0 => children
+            .choose_weighted(rng, |(_, v, _)| f64::from(*v).powf(1.0 / temp))
+            .ok()
+            .map(|(m, _, _)| m.clone()),
+        _ => None
+    };
+    let total_visits = children.iter().map(|(_, v, _)| f64::from(*v)).sum::<f64>();
+    let m = sampled.unwrap_or_else(|| children.into_iter()
+        .map(|(m, v, wr)| {
+            let key = match final_select {
+                FinalSelect::MeanScore => wr,
+                FinalSelect::MostVisited => f64::from(v),
+                FinalSelect::RobustMax => wr - c_schedule.at(1.0) * (total_visits.max(1.0).ln() / f64::from(v.max(1))).sqrt(),
+            };
+            debug_assert!(key.is_finite(), "score of {m:?} is not finite: {key}");
+            (m, key)
+        })
+        .max_by(|(_, k1), (_, k2)| k1.total_cmp(k2))
         .unwrap()
-        .0;
+        .0);
     log::debug!("selected: {m:?}");
     m
 }
 
+fn root_converged<G: MonteCarloGameND>(children: &[(G::MOVE, MonteCarloChild<'_, G>)], margin: f64) -> bool {
+    let (mut best, mut second, mut total) = (0u32, 0u32, 0u32);
+    for (_, child) in children {
+        let MonteCarloChild::Computed(mov) = child else { continue };
+        total += mov.visits;
+        if mov.visits > best {
+            second = best;
+            best = mov.visits;
+        } else if mov.visits > second {
+            second = mov.visits;
+        }
+    }
+    total > 0 && f64::from(best - second) > margin * f64::from(total)
+}
+
 fn playoff<'a, 'b, G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G> + ExecutionLimiterFactory<G>>(
     mut next: &'a mut MonteCarloChild<'b, G>,
     mut game: &'b G,
@@ -188,16 +451,24 @@ fn playoff<'a, 'b, G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G>
     tmp_buf: &mut Bump,
     rng: &mut impl Rng,
     c: f64,
+    node_count: &mut usize,
 ) {
     tmp_buf.reset();
     #[derive(Debug)]
     struct PathData<'r> { score: &'r mut f64, visits: &'r mut u32, chance: &'r mut f64, non_leaf_count_next_state: &'r mut u16, non_leaf_count_current_move: &'r mut u16, child_count: usize }
     let mut el = <W as ExecutionLimiterFactory<G>>::create(wr_config);
+    let tmp_buf = &*tmp_buf;
     let mut path = bumpalo::collections::Vec::with_capacity_in(30, tmp_buf);
+    let mut history = bumpalo::collections::Vec::new_in(tmp_buf);
+    if G::TRACKS_REPETITION {
+        history.push(game.clone());
+    }
+    let mut drawn = false;
     loop {
         let current = match next {
             MonteCarloChild::Computed(ref mut child) => child,
             MonteCarloChild::Uncomputed(m) => {
+                game.validate_outcomes(m);
                 let outcomes = game.get_outcomes(&m).expect("failed to get child");
 
                 let mut outcomes_buf = bumpalo::collections::Vec::new_in(bump);
@@ -223,12 +494,20 @@ fn playoff<'a, 'b, G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G>
             Some(i) => &mut current.outcomes[i],
         };
         let mut game_state = GameState::Continue;
+        let mut limiter_called = false;
 
         let next_state = match outcome {
             MonteCarloOutcome::Computed(next) => next,
             MonteCarloOutcome::Uncomputed(mov, out) => {
                 let result = game.make_move(mov, out).expect("invalid move");
                 game_state = result.1;
+                // ask the limiter before allocating so a node limit actually bounds the arena
+                let child_count = if game_state == GameState::Finished { 0 } else { result.0.moves().into_iter().count() };
+                if el.next_with_stats(child_count, &result.0, *node_count).is_break() {
+                    return;
+                }
+                limiter_called = true;
+                *node_count += 1;
                 let g = bump.alloc(result.0);
                 let next_state = MonteCarloState::new(rng, g, game_state == GameState::Finished, bump);
                 *outcome = MonteCarloOutcome::Computed(next_state);
@@ -248,10 +527,19 @@ fn playoff<'a, 'b, G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G>
             child_count: std::mem::replace(&mut child_count, next_state.children.len()),
             non_leaf_count_current_move: &mut current.non_leaf_count,
         });
-        if el.next_with_game(next_state.children.len(), game).is_break() {
+        if !limiter_called && el.next_with_stats(next_state.children.len(), game, *node_count).is_break() {
             return;
         }
-        if let GameState::Finished = game_state {
+        // without children the game is over even if make_move did not say so, the reducer asks no_moves_result
+        if game_state == GameState::Finished || next_state.children.is_empty() {
+            break;
+        }
+        if G::TRACKS_REPETITION {
+            history.push(game.clone());
+        }
+        if G::TRACKS_REPETITION && G::is_drawn_by_repetition(&history) {
+            // the simulation would loop forever, the path is scored as a tie
+            drawn = true;
             break;
         }
 
@@ -274,8 +562,13 @@ fn playoff<'a, 'b, G: MonteCarloGameND + 'static, W: MultiScoreReducerFactory<G>
         next = new;
     }
 
-    let mut score_reducer = <W as MultiScoreReducerFactory<G>>::create(wr_config, game);
-    let mut is_leaf = true;
+    let mut score_reducer = if drawn {
+        <W as MultiScoreReducerFactory<G>>::create_drawn(wr_config)
+    } else {
+        <W as MultiScoreReducerFactory<G>>::create(wr_config, game)
+    };
+    // the draw depends on the history, the position itself stays open
+    let mut is_leaf = !drawn;
     for PathData{ score, visits, chance, non_leaf_count_next_state, non_leaf_count_current_move, child_count } in path.into_iter().rev() {
         *score += *chance * score_reducer.next_score(child_count);
         *chance = if is_leaf { 0.0 } else { *chance };
@@ -333,4 +626,133 @@ fn select_next_move<'c, 'b: 'c, G: MonteCarloGameND + 'static>(
     } else {
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+    use crate::ai_infra::{GamePlayer, GameStrategy};
+    use crate::line_four_7x6::{LineFourGame, LineFourIndex};
+    use crate::line_four_8x8::{LineFour8x8, LineFour8x8Index};
+    use crate::monte_carlo_win_reducer::{ScoreAveragerFactory, WinFactorReduceFactory, WinReducerFactory};
+    use crate::multi_score_reducer::{CheckWinMonteCarloGame, TwoScoreReducerFactory, WinRewardInit};
+    use crate::old_monte_carlo::monte_carlo_main::MonteLimit;
+    use crate::monte_carlo_game::MonteCarloGame;
+    use crate::monte_carlo_game::conformance::{self_play, Pendulum, TakeAway};
+    use crate::old_monte_carlo::monte_carlo_main8::{playoff, CSchedule, FinalSelect, FocusPolicy, MctsV8Builder, MonteCarloChild, MonteCarloStrategyV8};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+
+    // the final selection asserts that every score is finite
+    fn play_random_config<G: CheckWinMonteCarloGame + 'static, F: WinReducerFactory + Copy + 'static>(rng: &mut SmallRng, reduce: F) {
+        let reward = |rng: &mut SmallRng| WinRewardInit::new(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0), reduce);
+        let factory = TwoScoreReducerFactory::new(reward(rng), reward(rng)).limiter_from(rng.gen_range(0.0..0.5));
+        let final_select = [FinalSelect::MeanScore, FinalSelect::MostVisited, FinalSelect::RobustMax][rng.gen_range(0..3)];
+        let strategy = MonteCarloStrategyV8::new((MonteLimit::times(200), rng.gen_range(0.0..4.0), factory, Some(rng.gen())))
+            .with_final_select(final_select);
+        self_play::<G, _>(&strategy);
+    }
+
+    #[test]
+    fn scores_stay_finite() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let by = if rng.gen() { rng.gen_range(1.0..50.0) } else { rng.gen_range(0.0..1e-3) };
+            play_random_config::<TicTacToe, _>(&mut rng, WinFactorReduceFactory { by });
+            play_random_config::<TicTacToe, _>(&mut rng, ScoreAveragerFactory);
+            play_random_config::<LineFour8x8, _>(&mut rng, WinFactorReduceFactory { by });
+        }
+    }
+
+    #[test]
+    fn builder_takes_immediate_win() {
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5]).unwrap();
+        let config = MctsV8Builder::new().playoffs(2000).win_reward(1.0, 0.0).decay(0.5).seed([7; 32]).build();
+        let strategy = MonteCarloStrategyV8::new(config);
+        assert_eq!(I3, strategy.make_move(&game, None).0);
+    }
+
+    #[test]
+    fn c_schedule_interpolates() {
+        let linear = CSchedule::Linear { start: 2.0, end: 0.5 };
+        assert_eq!([2.0, 1.25, 0.5, 0.5], [0.0, 0.5, 1.0, 1.5].map(|progress| linear.at(progress)));
+        assert_eq!(1.5, CSchedule::Const(1.5).at(0.7));
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5]).unwrap();
+        let strategy = MonteCarloStrategyV8::new(MctsV8Builder::new().playoffs(2000).seed([7; 32]).build())
+            .with_c_schedule(linear);
+        assert_eq!("MCTSv8(c=2.0..0.5, 2000 playoffs)", GameStrategy::<TicTacToe>::name(&strategy));
+        assert_eq!(I3, strategy.make_move(&game, None).0);
+    }
+
+    #[test]
+    fn focus_answers_the_enemy_move() {
+        // every playoff goes to the column the enemy played, so it ends up the most visited one
+        let strategy = MonteCarloStrategyV8::new(MctsV8Builder::new().playoffs(40).seed([6; 32]).build())
+            .with_final_select(FinalSelect::MostVisited)
+            .with_focus(Some(FocusPolicy::SameMove { playoffs: 40 }));
+        let (_, carry) = strategy.make_move(&LineFour8x8::new(), None);
+        let (game, _) = LineFour8x8::new().make_move(&LineFour8x8Index::I0).unwrap();
+        assert_eq!(LineFour8x8Index::I0, strategy.make_move(&game, Some((LineFour8x8Index::I0, carry))).0);
+    }
+
+    #[test]
+    fn reports_searched_nodes() {
+        let mut player = MonteCarloStrategyV8::strategy_of(MctsV8Builder::new().playoffs(500).seed([2; 32]).build());
+        assert_eq!(None, GamePlayer::<TicTacToe>::searched_nodes(&player));
+        player.make_move(&TicTacToe::new(), None);
+        // a playoff adds every state it reaches that is new to the tree
+        let nodes = GamePlayer::<TicTacToe>::searched_nodes(&player).unwrap();
+        assert!(nodes >= 500, "{nodes}");
+    }
+
+    #[test]
+    fn running_out_of_moves_is_scored() {
+        // leaving a multiple of three stones wins, the playoffs only end when no_moves_result is asked
+        let strategy = MonteCarloStrategyV8::new(MctsV8Builder::new().playoffs(2000).win_reward(1.0, 0.0).seed([3; 32]).build());
+        assert_eq!(1, strategy.make_move(&TakeAway::new(), None).0);
+    }
+
+    #[test]
+    fn repetition_is_scored_as_tie() {
+        let (_, c, reducer, _) = MctsV8Builder::new().win_reward(1.0, 0.5).build();
+        let game = Pendulum::new();
+        let (bump, mut tmp_buf) = (Bump::new(), Bump::new());
+        let mut rng = SmallRng::seed_from_u64(0);
+        let (mut child, mut non_leaf_count, mut node_count) = (MonteCarloChild::Uncomputed(()), 1, 0);
+        for visits in 1..=3 {
+            playoff(&mut child, &game, &mut non_leaf_count, 1, &reducer, &bump, &mut tmp_buf, &mut rng, c, &mut node_count);
+            let MonteCarloChild::Computed(ref mov) = child else { panic!("the move was not expanded") };
+            assert_eq!(visits, mov.visits);
+            assert!(mov.score > 0.0, "{}", mov.score);
+            // a draw by repetition does not close the position
+            assert_eq!(1, non_leaf_count);
+        }
+    }
+
+    #[test]
+    fn no_playoffs_still_moves() {
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I5]).unwrap();
+        let strategy = MonteCarloStrategyV8::new(MctsV8Builder::new().playoffs(0).seed([1; 32]).build());
+        let (m, _) = strategy.make_move(&game, None);
+        assert!(game.moves().any(|legal| legal == m));
+    }
+
+    // the first player wins connect four by opening in the center, a strategy that stops finding the center
+    // in most seeded searches has gotten weaker
+    fn center_openings<G: CheckWinMonteCarloGame + 'static>(center: &[G::MOVE]) -> usize {
+        (0..8u8)
+            .map(|seed| {
+                let config = MctsV8Builder::new().playoffs(5000).decay(0.9).seed([seed; 32]).build();
+                MonteCarloStrategyV8::new(config).make_move(&G::new(), None).0
+            })
+            .filter(|m| center.contains(m))
+            .count()
+    }
+
+    #[test]
+    fn opens_in_the_center() {
+        assert!(center_openings::<LineFourGame>(&[LineFourIndex::I3]) >= 7);
+        assert!(center_openings::<LineFour8x8>(&[LineFour8x8Index::I3, LineFour8x8Index::I4]) >= 7);
+    }
+}
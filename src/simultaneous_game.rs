@@ -0,0 +1,311 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::hash::Hash;
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+use rustc_hash::FxHashMap;
+use crate::ai_infra::GameStrategy;
+use crate::monte_carlo_game::{MonteCarloGame, MoveError, MoveIndex, TwoPlayer, Winner};
+
+// games in which both players pick their move without knowing the other one's, the moves are revealed
+// and applied together. They are played as a MonteCarloGame through Simultaneous
+pub trait SimultaneousGame: Clone + Hash + Eq + Debug {
+    type MOVE: Copy + Debug + Eq + Hash + MoveIndex;
+
+    fn new() -> Self;
+    // empty once the game has ended
+    fn moves(&self, player: TwoPlayer) -> Vec<Self::MOVE>;
+    fn make_joint_move(&self, p1_move: &Self::MOVE, p2_move: &Self::MOVE) -> Result<(Self, Option<JointOutcome>), ()>;
+    // see MonteCarloGame::state_key
+    fn state_key(&self) -> Vec<u8>;
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum JointOutcome {
+    Won(TwoPlayer),
+    Tie,
+}
+
+impl JointOutcome {
+    // 1.0 for a win of P1, 0.0 for a win of P2
+    fn p1_reward(self) -> f64 {
+        match self {
+            JointOutcome::Won(TwoPlayer::P1) => 1.0,
+            JointOutcome::Won(TwoPlayer::P2) => 0.0,
+            JointOutcome::Tie => 0.5,
+        }
+    }
+}
+
+// a SimultaneousGame as a MonteCarloGame, so it can be played by run_game and GameStrategy. P1 picks first,
+// the pick is held back until P2 picked as well and both are applied together. Winner only names the
+// player who moved last, so a turn that wins for P1 is followed by a ply in which P1 claims the win by
+// repeating its pick
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct Simultaneous<G: SimultaneousGame> {
+    // the state at the start of the turn, it does not reveal the pick of P1
+    pub turn_start: G,
+    pending: Option<G::MOVE>,
+    claiming: bool,
+    plies: usize,
+    // how the game ended, see no_moves_result
+    result: Option<Winner>,
+}
+
+impl <G: SimultaneousGame> MonteCarloGame for Simultaneous<G> {
+    type MOVE = G::MOVE;
+    type MOVES<'s> = Vec<G::MOVE> where Self: 's;
+
+    fn new() -> Self {
+        Self {
+            turn_start: G::new(),
+            pending: None,
+            claiming: false,
+            plies: 0,
+            result: None,
+        }
+    }
+
+    fn moves(&self) -> Self::MOVES<'_> {
+        match self.pending {
+            Some(pick) if self.claiming => vec![pick],
+            _ => self.turn_start.moves(self.player()),
+        }
+    }
+
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError> {
+        let moves = self.moves();
+        if moves.is_empty() {
+            return Err(MoveError::GameOver);
+        }
+        if !moves.contains(m) {
+            return Err(MoveError::Illegal);
+        }
+        let plies = self.plies + 1;
+        match self.pending {
+            None => Ok((Self { turn_start: self.turn_start.clone(), pending: Some(*m), claiming: false, plies, result: None }, None)),
+            Some(_) if self.claiming => {
+                Ok((Self { turn_start: self.turn_start.clone(), pending: None, claiming: false, plies, result: Some(Winner::WIN) }, Some(Winner::WIN)))
+            }
+            Some(pick) => {
+                let (turn_start, outcome) = self.turn_start.make_joint_move(&pick, m).map_err(|()| MoveError::Illegal)?;
+                let next = |result| Self { turn_start, pending: None, claiming: false, plies, result };
+                Ok(match outcome {
+                    None => (next(None), None),
+                    Some(JointOutcome::Tie) => (next(Some(Winner::TIE)), Some(Winner::TIE)),
+                    Some(JointOutcome::Won(TwoPlayer::P2)) => (next(Some(Winner::WIN)), Some(Winner::WIN)),
+                    Some(JointOutcome::Won(TwoPlayer::P1)) => (Self { pending: Some(pick), claiming: true, ..next(None) }, None),
+                })
+            }
+        }
+    }
+
+    fn player(&self) -> TwoPlayer {
+        match self.pending {
+            Some(_) if !self.claiming => TwoPlayer::P2,
+            _ => TwoPlayer::P1,
+        }
+    }
+
+    fn ply_count(&self) -> usize {
+        self.plies
+    }
+
+    fn state_key(&self) -> Vec<u8> {
+        let mut key = self.turn_start.state_key();
+        key.extend(self.pending.map_or(0, |pick| pick.to_index() + 1).to_le_bytes());
+        key.push(self.claiming as u8);
+        key.push(self.result.map_or(0, |result| result as u8 + 1));
+        key
+    }
+
+    fn no_moves_result(&self) -> Option<Winner> {
+        self.result
+    }
+
+    fn is_simultaneous(&self) -> bool {
+        self.pending.is_some() && !self.claiming
+    }
+}
+
+// decoupled UCT: every state keeps separate statistics for the moves of each player, both pick with UCB1
+// on their own statistics and the picks form the joint move. The search starts from scratch every turn and
+// only looks at turn_start, so it does not see the pick of P1 when it plays P2
+pub struct DuctStrategy {
+    playoffs: u32,
+    c: f64,
+    rng: RefCell<SmallRng>,
+}
+
+struct DuctNode<M> {
+    visits: u32,
+    // (move, visits, summed reward of the player owning the move)
+    p1: Vec<(M, u32, f64)>,
+    p2: Vec<(M, u32, f64)>,
+}
+
+impl <G: SimultaneousGame> GameStrategy<Simultaneous<G>> for DuctStrategy {
+    type Carry = ();
+    // (playoffs, exploration constant, seed)
+    type Config = (u32, f64, Option<[u8; 32]>);
+
+    fn new((playoffs, c, seed): Self::Config) -> Self {
+        let rng = seed.map(SmallRng::from_seed).unwrap_or_else(SmallRng::from_entropy);
+        Self {
+            playoffs,
+            c,
+            rng: RefCell::new(rng),
+        }
+    }
+
+    fn make_move(&self, game: &Simultaneous<G>, _carry: Option<(G::MOVE, ())>) -> (G::MOVE, ()) {
+        if game.claiming {
+            return (game.pending.expect("no pick to claim"), ());
+        }
+        let (player, game) = (game.player(), &game.turn_start);
+        let rng = &mut *self.rng.borrow_mut();
+        let mut tree = FxHashMap::default();
+        let mut path = Vec::new();
+        for _ in 0..self.playoffs {
+            path.clear();
+            let reward = duct_playoff(game, &mut tree, &mut path, self.c, rng);
+            for (state, p1_i, p2_i) in &path {
+                let node = tree.get_mut(state).unwrap();
+                node.visits += 1;
+                node.p1[*p1_i].1 += 1;
+                node.p1[*p1_i].2 += reward;
+                node.p2[*p2_i].1 += 1;
+                node.p2[*p2_i].2 += 1.0 - reward;
+            }
+        }
+        let stats = tree.get(game).map(|node| match player {
+            TwoPlayer::P1 => &node.p1,
+            TwoPlayer::P2 => &node.p2,
+        });
+        let m = match stats {
+            Some(stats) => stats.iter().max_by_key(|(_, visits, _)| *visits).expect("no moves available").0,
+            None => *game.moves(player).choose(rng).expect("no moves available"),
+        };
+        (m, ())
+    }
+
+    fn name(&self) -> String {
+        format!("DUCT(c={:?}, {} playoffs)", self.c, self.playoffs)
+    }
+}
+
+// descends with a joint move per state until the game ends or a state new to the tree was added, which
+// is played out randomly. Returns the reward of P1, path holds the picked move indices of every state
+fn duct_playoff<G: SimultaneousGame>(start: &G, tree: &mut FxHashMap<G, DuctNode<G::MOVE>>, path: &mut Vec<(G, usize, usize)>, c: f64, rng: &mut impl Rng) -> f64 {
+    let mut state = start.clone();
+    loop {
+        let is_new = !tree.contains_key(&state);
+        let node = tree.entry(state.clone()).or_insert_with(|| DuctNode {
+            visits: 0,
+            p1: state.moves(TwoPlayer::P1).into_iter().map(|m| (m, 0, 0.0)).collect(),
+            p2: state.moves(TwoPlayer::P2).into_iter().map(|m| (m, 0, 0.0)).collect(),
+        });
+        let p1_i = select_ucb1(&node.p1, node.visits, c, rng);
+        let p2_i = select_ucb1(&node.p2, node.visits, c, rng);
+        let (p1_move, p2_move) = (node.p1[p1_i].0, node.p2[p2_i].0);
+        path.push((state.clone(), p1_i, p2_i));
+        let (next, outcome) = state.make_joint_move(&p1_move, &p2_move).expect("generated move is illegal");
+        if let Some(outcome) = outcome {
+            return outcome.p1_reward();
+        }
+        if is_new {
+            return random_rollout(next, rng);
+        }
+        state = next;
+    }
+}
+
+// unvisited moves are tried first, in random order
+fn select_ucb1<M>(stats: &[(M, u32, f64)], parent_visits: u32, c: f64, rng: &mut impl Rng) -> usize {
+    let unvisited = (0..stats.len()).filter(|i| stats[*i].1 == 0).collect::<Vec<_>>();
+    if let Some(i) = unvisited.choose(rng) {
+        return *i;
+    }
+    let log_visits = f64::from(parent_visits).ln();
+    (0..stats.len())
+        .max_by(|a, b| {
+            let ucb = |i: &usize| {
+                let (_, visits, reward) = stats[*i];
+                reward / f64::from(visits) + c * (log_visits / f64::from(visits)).sqrt()
+            };
+            ucb(a).total_cmp(&ucb(b))
+        })
+        .expect("no moves available")
+}
+
+fn random_rollout<G: SimultaneousGame>(mut state: G, rng: &mut impl Rng) -> f64 {
+    loop {
+        let p1_move = *state.moves(TwoPlayer::P1).choose(rng).expect("no moves in an unfinished game");
+        let p2_move = *state.moves(TwoPlayer::P2).choose(rng).expect("no moves in an unfinished game");
+        let (next, outcome) = state.make_joint_move(&p1_move, &p2_move).expect("generated move is illegal");
+        if let Some(outcome) = outcome {
+            return outcome.p1_reward();
+        }
+        state = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai_infra::GameStrategy;
+    use crate::monte_carlo_game::conformance::HighCard;
+    use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+    use crate::simultaneous_game::{DuctStrategy, Simultaneous, SimultaneousGame};
+
+    #[test]
+    fn pick_of_p1_is_held_back() {
+        let (picked, winner) = Simultaneous::<HighCard>::new().make_move(&1).unwrap();
+        assert_eq!(None, winner);
+        assert!(picked.is_simultaneous());
+        assert_eq!(TwoPlayer::P2, picked.player());
+        assert_eq!(HighCard::new(), picked.turn_start);
+        assert_ne!(Simultaneous::<HighCard>::new().state_key(), picked.state_key());
+
+        // P2 wins with its own move
+        let (end, winner) = Simultaneous::<HighCard>::new().apply_moves(&[1, 3, 1, 3, 1, 3]).unwrap();
+        assert_eq!(Some(Winner::WIN), winner);
+        assert_eq!(TwoPlayer::P1, end.player());
+    }
+
+    #[test]
+    fn p1_claims_its_win() {
+        let (end, winner) = Simultaneous::<HighCard>::new().apply_moves(&[3, 1, 3, 1, 3, 1]).unwrap();
+        assert_eq!(None, winner);
+        assert!(!end.is_simultaneous());
+        assert_eq!(TwoPlayer::P1, end.player());
+        assert_eq!(vec![3], end.moves());
+        let (end, winner) = end.make_move(&3).unwrap();
+        assert_eq!(Some(Winner::WIN), winner);
+        assert!(end.moves().is_empty());
+    }
+
+    #[test]
+    fn duct_finds_dominant_move() {
+        let strategy = <DuctStrategy as GameStrategy<Simultaneous<HighCard>>>::new((2000, 1.0, Some([5; 32])));
+        assert_eq!("DUCT(c=1.0, 2000 playoffs)", GameStrategy::<Simultaneous<HighCard>>::name(&strategy));
+        let start = Simultaneous::<HighCard>::new();
+        assert_eq!(3, strategy.make_move(&start, None).0);
+        for pick in [1, 3] {
+            assert_eq!(3, strategy.make_move(&start.make_move(&pick).unwrap().0, None).0);
+        }
+
+        let mut game = start;
+        let winner = loop {
+            let (m, ()) = strategy.make_move(&game, None);
+            let (next, winner) = game.make_move(&m).unwrap();
+            game = next;
+            if let Some(winner) = winner {
+                break winner;
+            }
+        };
+        // once the game is decided the remaining cards no longer matter, so self play need not end in a tie
+        assert_eq!(3, game.turn_start.round);
+        assert_eq!(game.turn_start.balance == 0, winner == Winner::TIE);
+    }
+}
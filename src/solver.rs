@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use crate::monte_carlo_game::{MonteCarloGame, Winner};
+
+// result for the player to move in the position, positions ended by a win are a loss for the player
+// that would be next
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    pub fn flip(self) -> Outcome {
+        match self {
+            Outcome::Loss => Outcome::Win,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Win => Outcome::Loss,
+        }
+    }
+}
+
+// exact outcomes of every position reached from start whose result is decided within plies moves,
+// positions still open at the depth limit are left out
+pub fn solve_to_depth<G: MonteCarloGame>(start: &G, plies: usize) -> HashMap<G, Outcome> {
+    let mut solved = HashMap::new();
+    let mut unresolved = HashMap::new();
+    solve(start, plies, &mut solved, &mut unresolved);
+    solved
+}
+
+// a move keeping the outcome for every solved position that has one, a win on the spot is preferred over
+// a forced one. The table of a fully solved game plays it perfectly, finished positions get no move
+pub fn policy_table<G: MonteCarloGame>(solved: &HashMap<G, Outcome>) -> HashMap<G, G::MOVE> {
+    solved.iter()
+        .filter_map(|(state, outcome)| {
+            let mut keeping = state.moves().into_iter().filter_map(|m| {
+                let (next, winner) = state.make_move(&m).ok()?;
+                let reached = match winner {
+                    Some(Winner::WIN) => Outcome::Win,
+                    Some(Winner::TIE) => Outcome::Draw,
+                    None => solved.get(&next)?.flip(),
+                };
+                (reached == *outcome).then_some((m, winner == Some(Winner::WIN)))
+            });
+            let first = keeping.next()?;
+            let (m, _) = if first.1 { first } else { keeping.find(|(_, wins)| *wins).unwrap_or(first) };
+            Some((state.clone(), m))
+        })
+        .collect()
+}
+
+// unresolved holds the plies a position was already searched with without a result
+fn solve<G: MonteCarloGame>(state: &G, plies: usize, solved: &mut HashMap<G, Outcome>, unresolved: &mut HashMap<G, usize>) -> Option<Outcome> {
+    if let Some(outcome) = solved.get(state) {
+        return Some(*outcome);
+    }
+    if unresolved.get(state).is_some_and(|searched| *searched >= plies) {
+        return None;
+    }
+    let mut moves = state.moves().into_iter().peekable();
+    if moves.peek().is_none() {
+        // finished, or stuck for games where running out of moves decides
+        let outcome = match state.no_moves_result().expect("no moves left in an unfinished game") {
+            Winner::WIN => Outcome::Loss,
+            Winner::TIE => Outcome::Draw,
+        };
+        solved.insert(state.clone(), outcome);
+        return Some(outcome);
+    }
+    if plies == 0 {
+        unresolved.insert(state.clone(), plies);
+        return None;
+    }
+    let mut best = None;
+    let mut complete = true;
+    for m in moves {
+        let (next, winner) = state.make_move(&m).expect("generated move is illegal");
+        let next_outcome = match winner {
+            Some(Winner::WIN) => Some(Outcome::Loss),
+            Some(Winner::TIE) => Some(Outcome::Draw),
+            None if plies > 1 => solve(&next, plies - 1, solved, unresolved),
+            None => None,
+        };
+        if winner.is_some() {
+            solved.insert(next, next_outcome.unwrap());
+        }
+        match next_outcome.map(Outcome::flip) {
+            Some(Outcome::Win) => {
+                best = Some(Outcome::Win);
+                complete = true;
+                break;
+            }
+            Some(outcome) => best = best.max(Some(outcome)),
+            None => complete = false,
+        }
+    }
+    let outcome = if complete { best } else { None };
+    match outcome {
+        Some(outcome) => {
+            solved.insert(state.clone(), outcome);
+        }
+        None => {
+            unresolved.insert(state.clone(), plies);
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line_four_7x6::LineFourGame;
+    use crate::monte_carlo_game::{MonteCarloGame, Winner};
+    use crate::solver::{solve_to_depth, Outcome};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+
+    #[test]
+    fn tic_tac_toe_is_drawn() {
+        let solved = solve_to_depth(&TicTacToe::new(), 9);
+        assert_eq!(Some(&Outcome::Draw), solved.get(&TicTacToe::new()));
+        let (corner_lost, _) = TicTacToe::new().apply_moves(&[I1, I2]).unwrap();
+        assert_eq!(Some(&Outcome::Win), solved.get(&corner_lost));
+    }
+
+    #[test]
+    fn depth_limits_what_is_solved() {
+        // three in the bottom row for p1, who is to move
+        let game = LineFourGame::from_raw(0b1 | 1 << 6 | 1 << 12, 0b10 | 1 << 36 | 1 << 30).unwrap();
+        assert_eq!(Some(&Outcome::Win), solve_to_depth(&game, 1).get(&game));
+        assert_eq!(None, solve_to_depth(&LineFourGame::new(), 3).get(&LineFourGame::new()));
+    }
+
+    #[test]
+    fn terminal_start_is_labeled() {
+        let moves = [I1, I2, I3, I5, I4, I6, I8, I7, I9];
+        let (drawn, winner) = TicTacToe::new().apply_moves(&moves).unwrap();
+        assert!(winner.is_some());
+        assert_eq!(Some(&Outcome::Draw), solve_to_depth(&drawn, 0).get(&drawn));
+        // an open position is not searched at all without plies
+        let (open, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5]).unwrap();
+        assert!(solve_to_depth(&open, 0).is_empty());
+        assert_eq!(Some(&Outcome::Win), solve_to_depth(&open, 1).get(&open));
+    }
+
+    #[test]
+    fn won_start_is_a_loss() {
+        let (won, winner) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5, I3]).unwrap();
+        assert_eq!(Some(Winner::WIN), winner);
+        // a finished game lists no moves, so nothing expands past the win
+        assert_eq!(0, won.successors().count());
+        assert_eq!(Some(Winner::WIN), won.no_moves_result());
+        assert_eq!(Some(&Outcome::Loss), solve_to_depth(&won, 3).get(&won));
+    }
+}
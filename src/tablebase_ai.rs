@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use crate::ai_infra::GameStrategy;
+use crate::monte_carlo_game::MonteCarloGame;
+use crate::solver::{policy_table, solve_to_depth};
+
+// plays the move of a policy table, e.g. from solver::policy_table, and asks the wrapped strategy in
+// positions missing from it. The table is a perfect player for positions the solver finished
+pub struct TablebaseStrategy<G: MonteCarloGame, F> {
+    table: HashMap<G, G::MOVE>,
+    fallback: F,
+}
+
+impl <G: MonteCarloGame, F: GameStrategy<G>> GameStrategy<G> for TablebaseStrategy<G, F> {
+    // None after a tabled move, the fallback starts over with what it knows of the game then
+    type Carry = Option<F::Carry>;
+    // (table, config of the fallback)
+    type Config = (HashMap<G, G::MOVE>, F::Config);
+
+    fn new((table, fallback): Self::Config) -> Self {
+        Self {
+            table,
+            fallback: F::new(fallback),
+        }
+    }
+
+    fn make_move(&self, game: &G, carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
+        if let Some(m) = self.table.get(game) {
+            return (*m, None);
+        }
+        log::debug!("position not in the table, asking {}", self.fallback.name());
+        let carry = carry.and_then(|(m, carry)| carry.map(|carry| (m, carry)));
+        let (m, carry) = self.fallback.make_move(game, carry);
+        (m, Some(carry))
+    }
+
+    fn reset(&self, carry: &mut Self::Carry) {
+        if let Some(carry) = carry {
+            self.fallback.reset(carry);
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("Tablebase({} positions, {})", self.table.len(), self.fallback.name())
+    }
+}
+
+// solves the whole game when the strategy is created if state_space_hint of a new game is at most max_states,
+// like for TicTacToe, and plays it perfectly from the table. Other games are left to the fallback
+pub struct SolvingStrategy<G: MonteCarloGame, F>(TablebaseStrategy<G, F>);
+
+impl <G: MonteCarloGame, F: GameStrategy<G>> GameStrategy<G> for SolvingStrategy<G, F> {
+    type Carry = Option<F::Carry>;
+    // (max_states, config of the fallback)
+    type Config = (usize, F::Config);
+
+    fn new((max_states, fallback): Self::Config) -> Self {
+        let start = G::new();
+        let table = match start.state_space_hint() {
+            // no game lasts more plies than it has states
+            Some(states) if states <= max_states => policy_table(&solve_to_depth(&start, states)),
+            _ => HashMap::new(),
+        };
+        Self(TablebaseStrategy::new((table, fallback)))
+    }
+
+    fn make_move(&self, game: &G, carry: Option<(G::MOVE, Self::Carry)>) -> (G::MOVE, Self::Carry) {
+        self.0.make_move(game, carry)
+    }
+
+    fn reset(&self, carry: &mut Self::Carry) {
+        self.0.reset(carry)
+    }
+
+    fn name(&self) -> String {
+        format!("Solving({})", self.0.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::ai_infra::{GamePlayer, GameStrategy};
+    use crate::dumm_ai::DummAi;
+    use crate::line_four_8x8::LineFour8x8;
+    use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+    use crate::solver::{policy_table, solve_to_depth, Outcome};
+    use crate::tablebase_ai::{SolvingStrategy, TablebaseStrategy};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+    use crate::{run_game, GameLimits};
+
+    #[test]
+    fn tabled_moves_keep_the_outcome() {
+        let solved = solve_to_depth(&TicTacToe::new(), 9);
+        let strategy = <TablebaseStrategy<TicTacToe, DummAi> as GameStrategy<TicTacToe>>::new((policy_table(&solved), ()));
+        // after 1 2 the first player wins by force
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I2]).unwrap();
+        let (m, carry) = strategy.make_move(&game, None);
+        assert!(carry.is_none());
+        let (next, winner) = game.make_move(&m).unwrap();
+        assert_eq!(None, winner);
+        assert_eq!(Some(&Outcome::Loss), solved.get(&next));
+        // a line is completed as soon as possible
+        let (game, _) = TicTacToe::new().apply_moves(&[I1, I4, I2, I5]).unwrap();
+        assert_eq!(Some(Winner::WIN), game.make_move(&strategy.make_move(&game, None).0).unwrap().1);
+    }
+
+    #[test]
+    fn missing_positions_use_the_fallback() {
+        let strategy = <TablebaseStrategy<TicTacToe, DummAi> as GameStrategy<TicTacToe>>::new((HashMap::new(), ()));
+        let (m, carry) = strategy.make_move(&TicTacToe::new(), None);
+        assert!(carry.is_some());
+        assert!(TicTacToe::new().make_move(&m).is_ok());
+    }
+
+    #[test]
+    fn solved_tic_tac_toe_never_loses() {
+        let solving = || Box::new(SolvingStrategy::<TicTacToe, DummAi>::strategy_of((10_000, ()))) as Box<dyn GamePlayer<TicTacToe>>;
+        for i in 0..20 {
+            let mut players = [solving(), Box::new(DummAi::strategy_of(()))];
+            if i % 2 != 0 {
+                players.swap(0, 1);
+            }
+            let (winner, _, last_mover) = run_game(players, false, GameLimits::default());
+            let solving_player = if i % 2 != 0 { TwoPlayer::P2 } else { TwoPlayer::P1 };
+            assert!(winner == Winner::TIE || last_mover == solving_player, "lost game {i}");
+        }
+        let (winner, _, _) = run_game([solving(), solving()], false, GameLimits::default());
+        assert_eq!(Winner::TIE, winner);
+        // without a hint the fallback plays
+        assert_eq!("Solving(Tablebase(0 positions, DummAi))", <SolvingStrategy<LineFour8x8, DummAi> as GameStrategy<LineFour8x8>>::new((10_000, ())).name());
+    }
+}
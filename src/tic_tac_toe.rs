@@ -1,12 +1,15 @@
-use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer, Winner};
+use crate::grid_game::{fmt_grid, GridGame};
+use crate::monte_carlo_game::{pack_move_indices, random_set_bit, unpack_move_indices, MonteCarloGame, MoveError, MoveIndex, TwoPlayer, Winner};
 use crate::multi_score_reducer::CheckWinMonteCarloGame;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct TicTacToe {
     game_state: u32
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum TicTacToeMove {
     I1 = 0, I2 = 1, I3 = 2, I4 = 3, I5 = 4, I6 = 5, I7 = 6, I8 = 7, I9 = 8
@@ -19,7 +22,7 @@ pub struct TicTacToeMoves {
 const BOARD_MASK: u32 = 0b111_111_111;
 const fn pos_player1(board: u32) -> u32 { board & BOARD_MASK }
 const fn pos_player2(board: u32) -> u32 { (board >> 9) & BOARD_MASK }
-const fn get_player(board: u32) ->  TwoPlayer { if board >> 31 == 1 { TwoPlayer::P1 } else { TwoPlayer::P2 }}
+const fn get_player(board: u32) ->  TwoPlayer { TwoPlayer::from_bit((board >> 31) as u64) }
 const fn won_one_board(board: u16) -> bool {
     const LINE_WON: u16 = 0b100_100_100;
     let row_won = (board & board << 1 & board << 2) & LINE_WON;
@@ -45,20 +48,30 @@ impl MonteCarloGame for TicTacToe {
     }
 
     fn moves(&self) -> Self::MOVES<'_> {
+        if self.win_state().is_some() {
+            return TicTacToeMoves { remaining: 0 };
+        }
         let used_pos = pos_player1(self.game_state) | pos_player2(self.game_state);
         let unused = (!used_pos)  & BOARD_MASK;
         TicTacToeMoves { remaining: unused as u16 }
     }
 
-    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), ()> {
+    fn random_move(&self, rng: &mut impl Rng) -> Option<Self::MOVE> {
+        random_set_bit(self.moves().remaining as u64, rng).map(|i| TicTacToeMove::try_from(i).unwrap())
+    }
+
+    fn make_move(&self, m: &Self::MOVE) -> Result<(Self, Option<Winner>), MoveError> {
         let player_board_off = match get_player(self.game_state) {
             TwoPlayer::P1 => 0,
             TwoPlayer::P2 => 9
         };
+        if self.win_state().is_some() {
+            return Err(MoveError::GameOver);
+        }
         let m = *m as u32;
         let m_bit = 1 << (m + player_board_off);
         if self.game_state & m_bit > 0 {
-            return Err(());
+            return Err(MoveError::Illegal);
         }
 
         let new_board = self.game_state | m_bit;
@@ -78,30 +91,57 @@ impl MonteCarloGame for TicTacToe {
     fn player(&self) -> TwoPlayer {
         get_player(self.game_state)
     }
+
+    fn no_moves_result(&self) -> Option<Winner> {
+        self.win_state()
+    }
+
+    fn ply_count(&self) -> usize {
+        (pos_player1(self.game_state).count_ones() + pos_player2(self.game_state).count_ones()) as usize
+    }
+
+    // the board of each player and the player bit, little endian
+    fn state_key(&self) -> Vec<u8> {
+        self.game_state.to_le_bytes().to_vec()
+    }
+
+    // all 5478 positions, counted from new
+    fn state_space_hint(&self) -> Option<usize> {
+        Some(5478)
+    }
+}
+
+impl GridGame for TicTacToe {
+    fn dimensions(&self) -> (usize, usize) {
+        (3, 3)
+    }
+
+    fn cell(&self, row: usize, column: usize) -> char {
+        if (self.game_state >> (row * 3 + column)) & 1 > 0 {
+            'x'
+        } else if (self.game_state >> (row * 3 + column + 9)) & 1 > 0 {
+            'o'
+        } else {
+            '.'
+        }
+    }
+}
+
+impl std::fmt::Display for TicTacToe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_grid(self, f)
+    }
 }
 
 impl std::fmt::Debug for TicTacToe {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use std::fmt::Write;
-        for row in 0..3 {
-            for col in 0..3 {
-                let write = if (self.game_state >> (row * 3 + col)) & 1 > 0 {
-                    'x'
-                } else if (self.game_state >> (row * 3 + col + 9)) & 1 > 0 {
-                    'o'
-                } else {
-                    ' '
-                };
-                f.write_char(write)?;
-            }
-            f.write_char('\n')?;
-        }
-        Ok(())
+        fmt_grid(self, f)
     }
 }
 
 impl CheckWinMonteCarloGame for TicTacToe {
     fn win_state(&self) -> Option<Winner> {
+        // the winner stays at turn
         let off = match get_player(self.game_state) {
             TwoPlayer::P1 => 0,
             TwoPlayer::P2 => 9,
@@ -154,4 +194,40 @@ impl TryFrom<u32> for TicTacToeMove {
         };
         Ok(value)
     }
-}
\ No newline at end of file
+}
+
+// grid coordinates like "b2", the letter is the column and the number the row, both counted from the
+// top left like the numbering of the moves
+impl TryFrom<&str> for TicTacToeMove {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim().to_ascii_lowercase();
+        match value.as_bytes() {
+            [column @ b'a'..=b'c', row @ b'1'..=b'3'] => Self::try_from(u32::from(row - b'1') * 3 + u32::from(column - b'a')),
+            _ => Err(())
+        }
+    }
+}
+
+// column letter and row from the top, like PlayerInput parses it
+impl std::fmt::Display for TicTacToeMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let index = *self as u8;
+        write!(f, "{}{}", char::from(b'a' + index % 3), index / 3 + 1)
+    }
+}
+
+impl MoveIndex for TicTacToeMove {
+    fn to_index(&self) -> u32 {
+        *self as u32
+    }
+}
+
+pub fn pack_moves(moves: &[TicTacToeMove]) -> Vec<u8> {
+    pack_move_indices(moves, 4)
+}
+
+pub fn unpack_moves(packed: &[u8], count: usize) -> Option<Vec<TicTacToeMove>> {
+    unpack_move_indices(packed, 4, count)
+}
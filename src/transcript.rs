@@ -0,0 +1,138 @@
+use std::io::{BufRead, Write};
+use crate::ai_infra::short_type_name;
+use crate::monte_carlo_game::{MonteCarloGame, MoveIndex, TwoPlayer, Winner};
+
+// a finished or aborted game as text: the game type and the player names as header, one move index per
+// line and the result as last line. The result is checked against the replayed moves when reading
+//   game: TicTacToe
+//   p1: DummAi
+//   p2: AlphaBetaStrategy
+//   4
+//   0
+//   ...
+//   result: P1
+#[derive(Debug)]
+pub struct Transcript<G: MonteCarloGame> {
+    pub players: [String; 2],
+    pub moves: Vec<G::MOVE>,
+    pub result: TranscriptResult,
+    pub final_state: G,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TranscriptResult {
+    Won(TwoPlayer),
+    Tie,
+    Unfinished,
+}
+
+#[derive(Debug)]
+pub enum TranscriptError {
+    Io(std::io::Error),
+    // one based line number
+    Malformed { line: usize },
+    WrongGame { expected: String, found: String },
+    // zero based index of the move
+    IllegalMove { ply: usize },
+    ResultMismatch { written: TranscriptResult, replayed: TranscriptResult },
+}
+
+impl TranscriptResult {
+    fn notation(self) -> &'static str {
+        match self {
+            TranscriptResult::Won(TwoPlayer::P1) => "P1",
+            TranscriptResult::Won(TwoPlayer::P2) => "P2",
+            TranscriptResult::Tie => "tie",
+            TranscriptResult::Unfinished => "unfinished",
+        }
+    }
+}
+
+pub fn write_transcript<G: MonteCarloGame, W: Write>(mut writer: W, players: [&str; 2], moves: &[G::MOVE], result: TranscriptResult) -> std::io::Result<()> where G::MOVE: MoveIndex {
+    writeln!(writer, "game: {}", short_type_name::<G>())?;
+    writeln!(writer, "p1: {}", players[0])?;
+    writeln!(writer, "p2: {}", players[1])?;
+    for m in moves {
+        writeln!(writer, "{}", m.to_index())?;
+    }
+    writeln!(writer, "result: {}", result.notation())
+}
+
+// replays the moves from G::new, the written result has to be the one the moves lead to
+pub fn read_transcript<G: MonteCarloGame, R: BufRead>(reader: R) -> Result<Transcript<G>, TranscriptError> where G::MOVE: TryFrom<u32> {
+    let mut lines = reader.lines().enumerate().map(|(i, line)| line.map(|line| (i + 1, line)).map_err(TranscriptError::Io));
+    let mut header = |key: &str| -> Result<String, TranscriptError> {
+        let (number, line) = lines.next().unwrap_or(Err(TranscriptError::Malformed { line: 0 }))?;
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix(": "))
+            .map(str::to_string)
+            .ok_or(TranscriptError::Malformed { line: number })
+    };
+    let game = header("game")?;
+    if game != short_type_name::<G>() {
+        return Err(TranscriptError::WrongGame { expected: short_type_name::<G>(), found: game });
+    }
+    let players = [header("p1")?, header("p2")?];
+
+    let mut state = G::new();
+    let mut moves = Vec::new();
+    let mut replayed = TranscriptResult::Unfinished;
+    let written = loop {
+        let (number, line) = lines.next().unwrap_or(Err(TranscriptError::Malformed { line: 0 }))?;
+        if let Some(result) = line.strip_prefix("result: ") {
+            let written = [TranscriptResult::Won(TwoPlayer::P1), TranscriptResult::Won(TwoPlayer::P2), TranscriptResult::Tie, TranscriptResult::Unfinished]
+                .into_iter()
+                .find(|candidate| candidate.notation() == result)
+                .ok_or(TranscriptError::Malformed { line: number })?;
+            break written;
+        }
+        let m = line.trim().parse::<u32>().ok()
+            .and_then(|index| G::MOVE::try_from(index).ok())
+            .ok_or(TranscriptError::Malformed { line: number })?;
+        if replayed != TranscriptResult::Unfinished {
+            return Err(TranscriptError::IllegalMove { ply: moves.len() });
+        }
+        let mover = state.player();
+        let (next, winner) = state.make_move(&m).map_err(|_| TranscriptError::IllegalMove { ply: moves.len() })?;
+        replayed = match winner {
+            Some(Winner::WIN) => TranscriptResult::Won(mover),
+            Some(Winner::TIE) => TranscriptResult::Tie,
+            None => TranscriptResult::Unfinished,
+        };
+        state = next;
+        moves.push(m);
+    };
+    if written != replayed {
+        return Err(TranscriptError::ResultMismatch { written, replayed });
+    }
+    Ok(Transcript { players, moves, result: written, final_state: state })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::line_four_8x8::LineFour8x8;
+    use crate::monte_carlo_game::{MonteCarloGame, TwoPlayer};
+    use crate::tic_tac_toe::{TicTacToe, TicTacToeMove::*};
+    use crate::transcript::{read_transcript, write_transcript, TranscriptError, TranscriptResult};
+
+    #[test]
+    fn transcript_round_trip() {
+        let moves = [I1, I4, I2, I5, I3];
+        let mut written = Vec::new();
+        write_transcript::<TicTacToe, _>(&mut written, ["alice", "bob"], &moves, TranscriptResult::Won(TwoPlayer::P1)).unwrap();
+        assert_eq!("game: TicTacToe\np1: alice\np2: bob\n0\n3\n1\n4\n2\nresult: P1\n", String::from_utf8(written.clone()).unwrap());
+        let transcript = read_transcript::<TicTacToe, _>(written.as_slice()).unwrap();
+        assert_eq!(["alice", "bob"], transcript.players);
+        assert_eq!(moves.to_vec(), transcript.moves);
+        assert_eq!(TranscriptResult::Won(TwoPlayer::P1), transcript.result);
+        assert_eq!(TicTacToe::new().apply_moves(&moves).unwrap().0, transcript.final_state);
+
+        assert!(matches!(read_transcript::<LineFour8x8, _>(written.as_slice()), Err(TranscriptError::WrongGame { .. })));
+        let tampered = "game: TicTacToe\np1: alice\np2: bob\n0\n3\nresult: P2\n";
+        assert!(matches!(read_transcript::<TicTacToe, _>(tampered.as_bytes()), Err(TranscriptError::ResultMismatch { .. })));
+        let twice = "game: TicTacToe\np1: alice\np2: bob\n0\n1\n0\nresult: unfinished\n";
+        assert!(matches!(read_transcript::<TicTacToe, _>(twice.as_bytes()), Err(TranscriptError::IllegalMove { ply: 2 })));
+        let cut = "game: TicTacToe\np1: alice\np2: bob\n0\n";
+        assert!(matches!(read_transcript::<TicTacToe, _>(cut.as_bytes()), Err(TranscriptError::Malformed { .. })));
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt::Formatter;
 use std::ops::{BitOr, Mul};
 use log::debug;
-use crate::monte_carlo_game::{GameWithMoves, MonteCarloGame, TwoPlayer, Winner};
+use crate::monte_carlo_game::{GameWithMoves, MonteCarloGame, MoveIndex, TwoPlayer, Winner};
 use crate::monte_carlo_game_v2::GameState;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -50,17 +50,24 @@ enum PlayerAmount {
 // bit 2-6: Kind - in decimal 0-9 numbers, 10 reverse direction, 11 skip, 12: draw two cards, 13: choosen color, 14: black choose color, 15: black draw 4 cards,
 
 // bits: 7(= bits per card) * 108(= card amount) + 2(= player count) + 2(= current player) + 1(= player move_direction) + 4(= max player count) * 6(= max amount of cards) + 6(= draw stack dist), 4(= carry dist)
+#[derive(Clone)]
 struct Uno {
     meta_data: UnoMetadata,
     cards: [u8; 108],
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
-struct UnoMetadata(u64);// 0-1 player count, 2-3 current player, 4 next player direction, 5-10 11-16 17-22 23-27 the player card offset, 28-33 the draw stack offset, 34 - 37 draw cards carry, 38-63: rng seed
+struct UnoMetadata(u64);// 0-1 player count, 2-3 current player, 4 next player direction, 5-10 11-16 17-22 23-27 the player card offset, 28-33 the draw stack offset, 34 - 37 draw cards carry, 38-63: rng state, advanced by every reshuffle
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum UnoInitError {
+    // only 2 to 4 players are supported
+    UnsupportedPlayerCount(u8),
+}
 
 #[derive(Copy, Clone, Debug)]
 enum UnoMoveErr {
-    CardCannotBePlaced, SelectedCardNotInHand, ColorChoosingRequired, ColorChoosingNotRequired, NothingNotNecessary
+    CardCannotBePlaced, SelectedCardNotInHand, ColorChoosingRequired, ColorChoosingNotRequired, NothingNotNecessary, CarryOverflow
 }
 
 
@@ -76,6 +83,7 @@ const CARD_OFFSET_BITS: u64 = 7;
 const CARD_OFFSET_MASK: u64 = !(u64::MAX << CARD_OFFSET_BITS);
 
 const DRAW_CARD_CARRY_MASK: u64 = 0b1111;
+const SEED_MASK: u64 = u64::MAX >> SEED_OFF;
 
 const UNO_CARD_REVERSE: u8 = 10;
 const UNO_CARD_SKIP: u8 = 11;
@@ -126,15 +134,12 @@ impl Uno {
             cards.swap(i, idx);
         }
 
-        let player_count = player_count as u64 - 2;
+        let players = player_count as u64;
         let current_player = 0;
-        let mut player_offs = [0, 7, 14, 14];
-        if player_count >= 1 {
-            player_offs[3] += 7;
-        }
-        player_offs.iter_mut().for_each(|o| *o += 1);
-
-        let draw_stack_off = player_offs[player_count as usize + 1] + 7;
+        // every player gets 7 cards after the open card, the hands of missing players are empty at the draw stack
+        let draw_stack_off = 1 + 7 * players;
+        let player_offs: [u64; 4] = std::array::from_fn(|i| (1 + 7 * i as u64).min(draw_stack_off));
+        let player_count = players - 2;
 
         let player_off_bits = player_offs.into_iter().enumerate().map(|(i, p)| p << i as u64 * CARD_OFFSET_BITS).fold(0,u64::bitor);
         let meta_data = (player_count << PLAYER_COUNT_OFF) | (current_player << CURRENT_PLAYER_OFF) | (1 << NEXT_PLAYER_DIRECTION_OFF) | (player_off_bits << PLAYER_CARD_OFFSET_OFF) | (draw_stack_off << DRAW_STACK_OFFSET_OFF) | ((seed as u64) << SEED_OFF);
@@ -145,6 +150,16 @@ impl Uno {
         }
     }
 
+    fn try_new(seed: u32, players: u8) -> Result<Self, UnoInitError> {
+        let player_count = match players {
+            2 => PlayerAmount::Two,
+            3 => PlayerAmount::Three,
+            4 => PlayerAmount::Four,
+            _ => return Err(UnoInitError::UnsupportedPlayerCount(players)),
+        };
+        Ok(Self::new(seed, player_count))
+    }
+
     fn get_open_card(&self) -> u8 {
         self.cards[OPEN_CARD_IDX]
     }
@@ -159,6 +174,31 @@ impl Uno {
         Some(self.cards[start..end].iter().copied())
     }
 
+    fn legal_moves(&self) -> Vec<UnoMove> {
+        let open_card = self.get_open_card();
+        if open_card >> UNO_CARD_KIND_OFF == UNO_CARD_CHOOSE_COLOR_BLACK {
+            return (0..4).map(|c| UnoMoveEnum::ChooseColor(c).into()).collect();
+        }
+        let current_player = self.meta_data.get_current_player();
+        let current_offset = self.meta_data.get_current_card_offset(current_player) as usize;
+        let next_offset = self.meta_data.get_next_card_offset(current_player) as usize;
+        let moves = self.cards[current_offset..next_offset].iter().enumerate()
+            .filter(|(_, card)| self.is_playable(**card, open_card))
+            .map(|(i, _)| UnoMoveEnum::ChooseCard(i as u8).into())
+            .collect::<Vec<_>>();
+        if moves.is_empty() {
+            vec![UnoMoveEnum::Nothing.into()]
+        } else {
+            moves
+        }
+    }
+
+    // a card that would overflow the draw carry cannot be played, a hand of only such cards has to play Nothing
+    fn is_playable(&self, card: u8, open_card: u8) -> bool {
+        can_first_be_put_onto_second(card, open_card)
+            && self.meta_data.get_draw_card_carry() + draw_carry_added(card, open_card) <= DRAW_CARD_CARRY_MASK
+    }
+
     fn get_discard_stack_cards(&self) ->  impl Iterator<Item = u8> + '_ {
         let discard_stack_end = self.meta_data.get_index_after_discard_stack() as usize;
         self.cards[1..discard_stack_end].iter().copied()
@@ -174,6 +214,12 @@ impl std::fmt::Debug for UnoMove {
     }
 }
 
+impl MoveIndex for UnoMove {
+    fn to_index(&self) -> u32 {
+        self.0 as u32
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum UnoMoveEnum {
     ChooseCard(u8),
@@ -236,6 +282,10 @@ impl GameWithMoves for Uno {
                      return Err(UnoMoveErr::CardCannotBePlaced)
                  }
 
+                 // done before anything else is changed, so an overflow leaves the game untouched. If the carry is
+                 // drawn below nothing is added, so the order does not matter otherwise
+                 self.meta_data.add_to_card_draw_carry(draw_carry_added(selected_card, open_card))?;
+
                  {
                      let player_card_start = self.meta_data.get_index_after_discard_stack() as usize;
                      self.cards.copy_within(player_card_start..card_idx, player_card_start + 1);
@@ -267,14 +317,6 @@ impl GameWithMoves for Uno {
                      self.meta_data.add_to_all_offsets_after(current_player, draw_amount as u64);
                  }
 
-                 {
-                     let mut add_to_carry: u64 = if selected_card_kind == UNO_CARD_DRAW_TWO { 2 } else if selected_card_kind == UNO_CARD_DRAW_FOUR  { 4 } else { 0 };
-                     if is_draw_card(open_card) {
-                         add_to_carry = add_to_carry.saturating_sub(2)
-                     }
-                     self.meta_data.add_to_card_draw_carry(add_to_carry);
-                 }
-
                  self.meta_data.switch_player_direction_if(selected_card_kind == UNO_CARD_REVERSE);
 
                  if self.meta_data.get_current_card_offset(current_player) == self.meta_data.get_next_card_offset(current_player) {
@@ -282,7 +324,12 @@ impl GameWithMoves for Uno {
                  }
 
                  {
-                     let advance_by = 1 + (selected_card_kind == UNO_CARD_SKIP) as u64 - (selected_card_kind == UNO_CARD_CHOOSE_COLOR_BLACK) as u64;
+                     // the player of a black card stays to choose the color
+                     let advance_by = match selected_card_kind {
+                         UNO_CARD_SKIP => 2,
+                         UNO_CARD_CHOOSE_COLOR_BLACK => 0,
+                         _ => 1,
+                     };
                      self.meta_data.compute_and_set_next_player(advance_by);
                  }
 
@@ -309,7 +356,7 @@ impl GameWithMoves for Uno {
                  let current_offset = self.meta_data.get_current_card_offset(current_player) as usize;
                  let next_offset = self.meta_data.get_next_card_offset(current_player) as usize;
 
-                 let has_viable_card = self.cards[current_offset..next_offset].iter().any(|card| can_first_be_put_onto_second(*card, open_card));
+                 let has_viable_card = self.cards[current_offset..next_offset].iter().any(|card| self.is_playable(*card, open_card));
 
                  if has_viable_card {
                      return Err(UnoMoveErr::NothingNotNecessary)
@@ -355,7 +402,7 @@ impl GameWithMoves for Uno {
                          self.meta_data.subtract_from_all_offsets(discard_stack_end as u64 - 1);
                          self.meta_data.add_to_all_offsets_after(current_player, (draw_stack_len + rotate_into_player_stack) as u64 - 1);
 
-                         if !skip_player {
+                         if skip_player {
                              self.meta_data.compute_and_set_next_player(1);
                          }
                      }
@@ -366,6 +413,26 @@ impl GameWithMoves for Uno {
     }
 }
 
+impl std::fmt::Debug for Uno {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let open_card = self.get_open_card();
+        // a black choose color card stays open until its player picked the color
+        let color_pending = open_card >> UNO_CARD_KIND_OFF == UNO_CARD_CHOOSE_COLOR_BLACK;
+        let hands = (0..self.meta_data.get_player_count())
+            .map(|p| self.get_p_cards(p).into_iter().flatten().map(card_num_to_card_repr).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        f.debug_struct("Uno")
+            .field("open_card", &card_num_to_card_repr(open_card))
+            .field("color_pending", &color_pending)
+            .field("hands", &hands)
+            .field("draw_stack_size", &(108 - self.meta_data.get_draw_stack_offset()))
+            .field("draw_card_carry", &self.meta_data.get_draw_card_carry())
+            .field("current_player", &self.meta_data.get_current_player())
+            .field("next_player_direction", &self.meta_data.get_signed_next_player())
+            .finish()
+    }
+}
+
 impl std::fmt::Debug for UnoMetadata {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("UnoMetadata")
@@ -375,7 +442,7 @@ impl std::fmt::Debug for UnoMetadata {
             .field("draw_card_carry", &((self.0 >> DRAW_CARDS_CARRY_OFF) & DRAW_CARD_CARRY_MASK))
             .field("player_card_offsets", &&self.get_all_offsets()[0..4])
             .field("draw_stack_offset", &self.get_all_offsets()[4])
-            .field("seed", &(self.0 >> SEED_OFF))
+            .field("seed", &self.get_seed())
             .finish()
     }
 }
@@ -413,6 +480,14 @@ impl UnoMetadata {
         (self.0 >> DRAW_CARDS_CARRY_OFF) & DRAW_CARD_CARRY_MASK
     }
 
+    fn get_seed(&self) -> u32 {
+        ((self.0 >> SEED_OFF) & SEED_MASK) as u32
+    }
+
+    fn set_seed(&mut self, seed: u32) {
+        self.0 = (self.0 & !(SEED_MASK << SEED_OFF)) | ((seed as u64 & SEED_MASK) << SEED_OFF);
+    }
+
     fn get_signed_next_player(&self) -> i64 {
         get_signed_direction((self.0 >> NEXT_PLAYER_DIRECTION_OFF) & 0b1)
     }
@@ -441,15 +516,19 @@ impl UnoMetadata {
         self.0 ^= (switch as u64) << NEXT_PLAYER_DIRECTION_OFF;
     }
 
-    fn add_to_card_draw_carry(&mut self, value: u64) {
-        debug_assert!(self.get_draw_card_carry().saturating_add(value) <= DRAW_CARD_CARRY_MASK);
+    fn add_to_card_draw_carry(&mut self, value: u64) -> Result<(), UnoMoveErr> {
+        // the carry field is only 4 bits wide, overflowing it would corrupt the seed
+        if self.get_draw_card_carry().saturating_add(value) > DRAW_CARD_CARRY_MASK {
+            return Err(UnoMoveErr::CarryOverflow);
+        }
         self.0 += value << DRAW_CARDS_CARRY_OFF;
+        Ok(())
     }
 
     fn compute_and_set_next_player(&mut self, advance_by: u64) {
         let current_player = self.get_current_player();
         let player_count = self.get_player_count();
-        debug_assert!(advance_by <= player_count);
+        assert!(advance_by <= player_count, "cannot advance by {advance_by} with {player_count} players");
         let signed_direction = self.get_signed_next_player() * (advance_by as i64);
         let next_player = next_player(player_count, current_player, signed_direction);
         self.0 ^= (current_player ^ next_player) << CURRENT_PLAYER_OFF;
@@ -522,18 +601,21 @@ impl UnoMetadata {
     }
 }
 
+// continues the random numbers from the seed field and stores the next one there, so every reshuffle of a
+// game follows from the seed given to Uno::new
 fn randomise_discard_stack(uno: &mut Uno) {
-
-    let seed = uno.meta_data.0 ^ uno.cards.iter().fold(0u64, |a, b| a.rotate_left(8) ^ (*b as u64));
-    let mut seed = (seed as u32).wrapping_mul((seed >> 32) as u32);
+    // xorshift never leaves 0
+    let mut seed = uno.meta_data.get_seed().max(1);
 
     let discard_stack_end = uno.meta_data.get_index_after_discard_stack();
     let discard_stack_end = discard_stack_end as usize;
     let discard_stack = &mut uno.cards[1..discard_stack_end];
+    // only the discard stack is shuffled, the open card at index 0 has to stay in place
     for i in 1..discard_stack.len() {
-        let j = generate_random_num(&mut seed) as usize % i;
-        uno.cards.swap(i, j);
+        let j = generate_random_num(&mut seed) as usize % (i + 1);
+        discard_stack.swap(i, j);
     }
+    uno.meta_data.set_seed(generate_random_num(&mut seed));
 }
 
 fn rotate_by(mem: &mut [u8], mut by: usize) {
@@ -607,18 +689,27 @@ fn is_draw_card(card: u8) -> bool {
     (card_kind == UNO_CARD_DRAW_FOUR) | (card_kind == UNO_CARD_DRAW_TWO)
 }
 
+fn draw_carry_added(selected: u8, open_card: u8) -> u64 {
+    let selected_kind = selected >> UNO_CARD_KIND_OFF;
+    let added: u64 = if selected_kind == UNO_CARD_DRAW_TWO { 2 } else if selected_kind == UNO_CARD_DRAW_FOUR { 4 } else { 0 };
+    // the open draw card already counts two
+    if is_draw_card(open_card) {
+        added.saturating_sub(2)
+    } else {
+        added
+    }
+}
+
 fn get_signed_direction(direction: u64)-> i64 {
     debug_assert!(direction == 0 ||direction == 1);
     -1 + 2 * direction as i64
 }
 
-fn next_player(max_player_value: u64, current_player: u64, next_player_direction: i64) -> u64 {
-    let max_player_value = max_player_value + 2;
-
-    let current_player = current_player + max_player_value * ((current_player == 0 || next_player_direction < 0) as u64);
-    let mut next_player = current_player.wrapping_add_signed(next_player_direction);
-    next_player = next_player - (next_player >= max_player_value) as u64 * max_player_value;
-    next_player
+// player_count is the actual number of players, the direction may advance by up to player_count in either direction
+fn next_player(player_count: u64, current_player: u64, next_player_direction: i64) -> u64 {
+    debug_assert!(current_player < player_count);
+    debug_assert!(next_player_direction.unsigned_abs() <= player_count);
+    (current_player + player_count).wrapping_add_signed(next_player_direction) % player_count
 }
 
 fn generate_random_num(seed: &mut u32) -> u32 {
@@ -693,7 +784,8 @@ fn card_repr_to_card_num(card_repr: CardRepr) -> u8 {
 mod tests {
     use regex::internal::Input;
     use crate::monte_carlo_game::GameWithMoves;
-    use crate::uno_basic_game::{can_first_be_put_onto_second, card_num_to_card_repr, card_repr_to_card_num, CardColor, CardRepr, ColoredCardKind, EffectCardKind, initial_cards, NumberCardKind, PlayerAmount, rotate_by, rotate_by_reverse, SpecialCardKind, Uno, UNO_CARD_CHOOSE_COLOR_BLACK, UNO_CARD_CHOOSE_COLOR_COLORED, UNO_CARD_KIND_OFF, UnoMove, UnoMoveEnum, UnoMoveErr};
+    use crate::monte_carlo_game_v2::GameState;
+    use crate::uno_basic_game::{can_first_be_put_onto_second, card_num_to_card_repr, card_repr_to_card_num, CardColor, CardRepr, ColoredCardKind, EffectCardKind, initial_cards, NumberCardKind, PlayerAmount, randomise_discard_stack, rotate_by, rotate_by_reverse, SpecialCardKind, Uno, UNO_CARD_CHOOSE_COLOR_BLACK, UNO_CARD_CHOOSE_COLOR_COLORED, UNO_CARD_KIND_OFF, UnoInitError, UnoMove, UnoMoveEnum, UnoMoveErr, SEED_OFF, UNO_CARD_SMALLEST_BLACK};
 
     macro_rules! assert_matches {
         ($exp: expr, $pat: pat) => {
@@ -791,6 +883,22 @@ mod tests {
         assert_eq!(mem, (50..200).chain(0..50).collect::<Vec<u8>>())
     }
 
+    #[test]
+    fn test_try_new_deals_seven_cards() {
+        for players in 2..=4u8 {
+            let uno = Uno::try_new(442522441, players).unwrap();
+            assert_eq!(u64::from(players), uno.meta_data.get_player_count());
+            let offsets = uno.meta_data.get_all_offsets();
+            for p in 0..u64::from(players) {
+                assert_eq!(7, uno.get_p_cards(p).unwrap().count(), "player {p} of {players}");
+            }
+            assert_eq!(1 + 7 * u64::from(players), offsets[4]);
+            assert_eq!(offsets[4], uno.meta_data.get_draw_stack_offset());
+        }
+        assert_eq!(Some(UnoInitError::UnsupportedPlayerCount(1)), Uno::try_new(1, 1).err());
+        assert_eq!(Some(UnoInitError::UnsupportedPlayerCount(5)), Uno::try_new(1, 5).err());
+    }
+
     #[test]
     fn test_normal_round() {
         let mut uno = Uno::new(442522441, PlayerAmount::Two);
@@ -845,6 +953,7 @@ mod tests {
 
         uno.execute_move(&UnoMoveEnum::ChooseCard(0).into()).unwrap();
         assert_eq!(uno.meta_data.get_current_player(), 0);
+        assert!(format!("{uno:?}").contains("color_pending: true"));
         assert_matches!(uno.execute_move(&UnoMoveEnum::Nothing.into()), Err(UnoMoveErr::ColorChoosingRequired));
         assert_matches!(uno.execute_move(&UnoMoveEnum::ChooseCard(0).into()), Err(UnoMoveErr::ColorChoosingRequired));
         uno.execute_move(&UnoMoveEnum::ChooseColor(CardColor::Blue as u8).into()).unwrap();
@@ -862,6 +971,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_black_card_keeps_player() {
+        let mut uno = Uno::new(324385160, PlayerAmount::Three);
+        let choose_color_card = uno.cards.iter().enumerate()
+            .find(|(_, card)| **card >> UNO_CARD_KIND_OFF == UNO_CARD_CHOOSE_COLOR_BLACK)
+            .unwrap()
+            .0;
+        uno.cards.swap(1, choose_color_card);
+
+        uno.execute_move(&UnoMoveEnum::ChooseCard(0).into()).unwrap();
+        assert_eq!(uno.meta_data.get_current_player(), 0);
+        uno.execute_move(&UnoMoveEnum::ChooseColor(CardColor::Red as u8).into()).unwrap();
+        assert_eq!(uno.meta_data.get_current_player(), 1);
+    }
+
     #[test]
     fn test_pull_from_draw_stack() {
         let mut uno = Uno::new(120321391, PlayerAmount::Three);
@@ -913,4 +1037,149 @@ mod tests {
         );
         assert_matches!(uno.execute_move(&UnoMoveEnum::Nothing.into()), Err(UnoMoveErr::NothingNotNecessary));
     }
-}
\ No newline at end of file
+
+    // a draw four is open and the first card of p1 is one as well, the carry is at 14 so it cannot be played
+    fn draw_four_on_full_carry() -> Uno {
+        let mut uno = Uno::new(324385160, PlayerAmount::Two);
+        let draw_four = card_num(SpecialCardKind::DrawFour);
+        let draw_fours = uno.cards.iter().enumerate()
+            .filter(|(_, card)| **card == draw_four)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        uno.cards.swap(0, draw_fours[0]);
+        uno.cards.swap(1, draw_fours[1]);
+        for _ in 0..7 {
+            uno.meta_data.add_to_card_draw_carry(2).unwrap();
+        }
+        uno
+    }
+
+    #[test]
+    fn test_draw_carry_overflow() {
+        let mut uno = draw_four_on_full_carry();
+        let seed = uno.meta_data.0 >> SEED_OFF;
+        let cards = uno.cards;
+        let meta_data = uno.meta_data;
+        assert_matches!(uno.meta_data.add_to_card_draw_carry(2), Err(UnoMoveErr::CarryOverflow));
+        assert_matches!(uno.execute_move(&UnoMoveEnum::ChooseCard(0).into()), Err(UnoMoveErr::CarryOverflow));
+        assert!(meta_data == uno.meta_data);
+        assert_eq!(cards, uno.cards);
+        assert_eq!(14, uno.meta_data.get_draw_card_carry());
+        assert_eq!(seed, uno.meta_data.0 >> SEED_OFF);
+    }
+
+    #[test]
+    fn test_legal_moves_skip_carry_overflow() {
+        let uno = draw_four_on_full_carry();
+        let moves = uno.legal_moves();
+        assert!(!moves.contains(&UnoMoveEnum::ChooseCard(0).into()));
+        for m in &moves {
+            uno.clone().execute_move(m).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        let mut uno = Uno::new(442522441, PlayerAmount::Two);
+        for _ in 0..50 {
+            let moves = uno.legal_moves();
+            assert!(!moves.is_empty());
+            for m in &moves {
+                uno.clone().execute_move(m).unwrap();
+            }
+            let illegal = (0..=112).map(UnoMove).filter(|m| !moves.contains(m));
+            for m in illegal {
+                assert!(uno.clone().execute_move(&m).is_err(), "{m:?} is legal but was not generated");
+            }
+            if uno.execute_move(&moves[0]).unwrap() == GameState::Finished {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_move_encoding_round_trip() {
+        let all_moves = (0..108).map(UnoMoveEnum::ChooseCard)
+            .chain((0..4).map(UnoMoveEnum::ChooseColor))
+            .chain([UnoMoveEnum::Nothing]);
+        let mut encoded = Vec::new();
+        for m in all_moves {
+            let uno_move = UnoMove::from(m);
+            assert_eq!(m, UnoMoveEnum::from(uno_move));
+            encoded.push(uno_move.0);
+        }
+        // the encoding is dense and every encoded value is canonical
+        assert_eq!((0..=112).collect::<Vec<u8>>(), encoded);
+        for raw in 113..=u8::MAX {
+            assert_eq!(UnoMoveEnum::Nothing, UnoMoveEnum::from(UnoMove(raw)));
+            assert_eq!(UnoMove(112), UnoMove::from(UnoMoveEnum::from(UnoMove(raw))));
+        }
+    }
+
+    // plays random legal moves for many games and checks the layout of the cards after every move
+    #[test]
+    fn test_random_games_keep_invariants() {
+        use rand::{Rng, SeedableRng};
+        // played choose color cards keep the chosen color in their color bits
+        fn normalize(card: u8) -> u8 {
+            let kind = card >> UNO_CARD_KIND_OFF;
+            if kind == UNO_CARD_CHOOSE_COLOR_COLORED {
+                UNO_CARD_CHOOSE_COLOR_BLACK << UNO_CARD_KIND_OFF
+            } else if kind >= UNO_CARD_SMALLEST_BLACK {
+                kind << UNO_CARD_KIND_OFF
+            } else {
+                card
+            }
+        }
+        let mut all_cards = initial_cards();
+        all_cards.sort_unstable();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        for player_count in 2..=4usize {
+            let mut turns = 0;
+            while turns < 10000 {
+                let player_amount = match player_count {
+                    2 => PlayerAmount::Two,
+                    3 => PlayerAmount::Three,
+                    _ => PlayerAmount::Four,
+                };
+                let mut uno = Uno::new(rng.gen(), player_amount);
+                for _ in 0..1000 {
+                    let moves = uno.legal_moves();
+                    let m = moves[rng.gen_range(0..moves.len())];
+                    let state = uno.execute_move(&m).unwrap();
+                    turns += 1;
+
+                    let offsets = uno.meta_data.get_all_offsets();
+                    assert!(offsets[0] >= 1, "offsets: {offsets:?}");
+                    assert!(offsets.windows(2).all(|w| w[0] <= w[1]), "offsets: {offsets:?}");
+                    // the hands of missing players stay empty
+                    assert!(offsets[player_count..].iter().all(|offset| *offset == offsets[4]), "offsets: {offsets:?}");
+                    assert!((uno.meta_data.get_current_player() as usize) < player_count);
+                    // only the open card may carry a chosen color
+                    assert!(uno.cards[1..].iter().all(|card| card >> UNO_CARD_KIND_OFF != UNO_CARD_CHOOSE_COLOR_COLORED), "after {m:?} in {uno:?}");
+                    let mut cards = uno.cards.map(normalize);
+                    cards.sort_unstable();
+                    assert_eq!(all_cards, cards, "after {m:?} in {uno:?}");
+                    if state == GameState::Finished {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_reshuffle_only_depends_on_seed() {
+        let mut uno = Uno::new(120321391, PlayerAmount::Two);
+        uno.meta_data.add_to_all_offsets_starting_at(0, 20);
+        let seed = uno.meta_data.get_seed();
+        // the hands do not influence how the discard stack is shuffled
+        let mut other = uno.clone();
+        other.cards[30..50].reverse();
+        randomise_discard_stack(&mut uno);
+        randomise_discard_stack(&mut other);
+        assert_eq!(uno.cards[..21], other.cards[..21]);
+        assert_ne!(seed, uno.meta_data.get_seed());
+        assert!(uno.meta_data == other.meta_data);
+    }
+}